@@ -1,13 +1,18 @@
-use std::process::ExitCode;
+use std::{
+    net::{SocketAddr, TcpListener},
+    path::PathBuf,
+    process::ExitCode,
+};
 
 use anyhow::{Context, Result};
 use clap::Parser;
 use tokio::{
-    fs::read as read_to_vec,
+    fs::{read as read_to_vec, write},
     io::{stdin, AsyncReadExt as _},
+    task::spawn_blocking,
 };
 
-use lune::Runtime;
+use lune::{CoverageFormat, DebugServer, ProfileFormat, Runtime};
 
 use super::utils::files::{discover_script_path_including_lune_dirs, strip_shebang};
 
@@ -18,6 +23,38 @@ pub struct RunCommand {
     script_path: String,
     /// Arguments to pass to the script, stored in process.args
     script_args: Vec<String>,
+    /// Collect code coverage for the script and any modules it requires
+    #[clap(long)]
+    coverage: bool,
+    /// The format to write code coverage in, either `lcov` or `json`
+    #[clap(long, default_value = "lcov")]
+    coverage_format: CoverageFormat,
+    /// The path to write code coverage to - defaults
+    /// to `coverage` with an extension for the format
+    #[clap(long)]
+    coverage_output: Option<PathBuf>,
+    /// Profile the script by sampling its call stacks
+    #[clap(long)]
+    profile: bool,
+    /// The number of call stacks to sample per second while profiling
+    #[clap(long, default_value_t = 1000, value_parser = clap::value_parser!(u32).range(1..))]
+    profile_rate: u32,
+    /// The format to write profiles in, either `folded` or `speedscope`
+    #[clap(long, default_value = "folded")]
+    profile_format: ProfileFormat,
+    /// The path to write the profile to - defaults
+    /// to `profile` with an extension for the format
+    #[clap(long)]
+    profile_output: Option<PathBuf>,
+    /// Wait for a debugger to connect using the Debug Adapter
+    /// Protocol before running, listening on the given address
+    #[clap(
+        long,
+        value_name = "ADDRESS",
+        num_args = 0..=1,
+        default_missing_value = "127.0.0.1:4711"
+    )]
+    inspect: Option<SocketAddr>,
 }
 
 impl RunCommand {
@@ -40,11 +77,64 @@ impl RunCommand {
             (file_display_name, file_contents)
         };
 
+        // Wait for a debugger to connect, if one was requested, so
+        // that its breakpoints are set before the script starts running
+        let debugger = match self.inspect {
+            None => None,
+            Some(addr) => {
+                let listener = TcpListener::bind(addr)
+                    .with_context(|| format!("Failed to listen for debugger on {addr}"))?;
+                eprintln!(
+                    "Waiting for debugger to connect on {}",
+                    listener.local_addr()?
+                );
+                let server = spawn_blocking(move || DebugServer::accept(&listener))
+                    .await?
+                    .context("Failed to connect to debugger")?;
+                Some(server)
+            }
+        };
+
         // Create a new lune object with all globals & run the script
-        let result = Runtime::new()
+        let mut runtime = Runtime::new()
             .with_args(self.script_args)
+            .with_coverage(self.coverage)
+            .with_profiling(self.profile.then_some(self.profile_rate));
+        if let Some(debugger) = &debugger {
+            runtime = runtime.with_debugger(debugger)?;
+        }
+        let result = runtime
             .run(&script_display_name, strip_shebang(script_contents))
             .await;
+
+        // Let the debugger know that the script has finished running
+        if let Some(debugger) = &debugger {
+            let succeeded = matches!(&result, Ok(code) if *code == ExitCode::SUCCESS);
+            debugger.finish(u8::from(!succeeded));
+        }
+
+        // Write coverage for the script, even if it errored
+        if self.coverage {
+            let output = self.coverage_output.unwrap_or_else(|| {
+                PathBuf::from("coverage").with_extension(self.coverage_format.extension())
+            });
+            let report = runtime.coverage_report()?;
+            write(&output, report.to_format(self.coverage_format))
+                .await
+                .with_context(|| format!("Failed to write coverage to {}", output.display()))?;
+        }
+
+        // Write the profile for the script, even if it errored
+        if self.profile {
+            let output = self.profile_output.unwrap_or_else(|| {
+                PathBuf::from("profile").with_extension(self.profile_format.extension())
+            });
+            let report = runtime.profile_report();
+            write(&output, report.to_format(self.profile_format))
+                .await
+                .with_context(|| format!("Failed to write profile to {}", output.display()))?;
+        }
+
         Ok(match result {
             Err(err) => {
                 eprintln!("{err}");
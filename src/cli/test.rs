@@ -0,0 +1,200 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    process::ExitCode,
+    time::Duration,
+};
+
+use anyhow::{bail, Context, Result};
+use clap::Parser;
+use console::style;
+use tokio::fs::write;
+
+use lune::{TestFormat, TestReport, TestResult, TestRunner, TestStatus};
+
+const TEST_FILE_SUFFIXES: [&str; 4] = [".spec.luau", ".test.luau", ".spec.lua", ".test.lua"];
+
+/// Run tests in test files
+#[derive(Debug, Clone, Parser)]
+pub struct TestCommand {
+    /// Test files, or directories to search for files ending
+    /// in `.spec.luau` or `.test.luau` - defaults to the
+    /// current directory
+    paths: Vec<PathBuf>,
+    /// Only run tests with full names containing the given text,
+    /// may be given multiple times to run tests matching any of them
+    #[clap(long, short)]
+    filter: Vec<String>,
+    /// The maximum number of seconds each test may take to run, or 0 for no timeout
+    #[clap(long, default_value_t = 5.0)]
+    timeout: f64,
+    /// The format to print results in, either `text`, `tap` or `junit`
+    #[clap(long, default_value = "text")]
+    format: TestFormat,
+    /// Write results to the given path instead of printing them,
+    /// while still printing the progress of tests as they run
+    #[clap(long)]
+    output: Option<PathBuf>,
+}
+
+impl TestCommand {
+    pub async fn run(self) -> Result<ExitCode> {
+        let timeout = if self.timeout > 0.0 {
+            Some(
+                Duration::try_from_secs_f64(self.timeout)
+                    .with_context(|| format!("Invalid timeout '{}'", self.timeout))?,
+            )
+        } else {
+            None
+        };
+
+        let paths = if self.paths.is_empty() {
+            vec![PathBuf::from(".")]
+        } else {
+            self.paths
+        };
+        let files = discover_test_files(&paths)?;
+        if files.is_empty() {
+            eprintln!("No test files were found");
+            return Ok(ExitCode::FAILURE);
+        }
+
+        let runner = TestRunner::new()
+            .with_filters(self.filter)
+            .with_timeout(timeout);
+
+        // NOTE: Progress is printed as tests run, unless results are printed in
+        // a format meant for other tools, which should be the only thing printed
+        let print_progress = self.output.is_some() || self.format == TestFormat::Text;
+
+        let mut report = TestReport::default();
+        for file in files {
+            if print_progress {
+                println!("{}", style(file.display()).bold());
+            }
+            let file_report = runner
+                .run_file(&file, |result| {
+                    if print_progress {
+                        print_result(result);
+                    }
+                })
+                .await;
+            if print_progress {
+                if let Some(error) = &file_report.error {
+                    println!("  {} {}", style("ERROR").red().bold(), indent(error));
+                }
+            }
+            report.files.push(file_report);
+        }
+
+        match &self.output {
+            Some(output) => {
+                write(output, report.to_format(self.format))
+                    .await
+                    .with_context(|| format!("Failed to write results to {}", output.display()))?;
+            }
+            None if self.format != TestFormat::Text => {
+                print!("{}", report.to_format(self.format));
+            }
+            None => {}
+        }
+
+        if print_progress {
+            let summary = report.summary();
+            if report.is_success() {
+                println!("\n{}", style(summary).green());
+            } else {
+                println!("\n{}", style(summary).red());
+            }
+        }
+
+        Ok(if report.is_success() {
+            ExitCode::SUCCESS
+        } else {
+            ExitCode::FAILURE
+        })
+    }
+}
+
+fn print_result(result: &TestResult) {
+    let duration = style(format!("({}ms)", result.duration.as_millis())).dim();
+    match &result.status {
+        TestStatus::Passed => {
+            println!("  {} {} {duration}", style("PASS").green(), result.name());
+        }
+        TestStatus::Skipped => {
+            println!(
+                "  {} {}",
+                style("SKIP").yellow(),
+                style(result.name()).dim()
+            );
+        }
+        TestStatus::Failed(message) => {
+            println!(
+                "  {} {} {duration}\n    {}",
+                style("FAIL").red().bold(),
+                result.name(),
+                indent(message)
+            );
+        }
+    }
+}
+
+fn indent(text: &str) -> String {
+    text.trim_end().replace('\n', "\n    ")
+}
+
+fn is_test_file(path: &Path) -> bool {
+    path.file_name()
+        .and_then(|name| name.to_str())
+        .is_some_and(|name| TEST_FILE_SUFFIXES.iter().any(|s| name.ends_with(s)))
+}
+
+/**
+    Finds all test files in the given paths, in a consistent order.
+
+    Paths to files are always included, even if their names do not mark them as test
+    files, while directories are searched recursively for test files, skipping any
+    hidden directories and directories containing dependencies.
+*/
+fn discover_test_files(paths: &[PathBuf]) -> Result<Vec<PathBuf>> {
+    let mut files = Vec::new();
+    for path in paths {
+        if path.is_file() {
+            files.push(path.clone());
+        } else if path.is_dir() {
+            let mut found = Vec::new();
+            search_dir(path, &mut found)
+                .with_context(|| format!("Failed to search for tests in {}", path.display()))?;
+            found.sort();
+            files.extend(found);
+        } else {
+            bail!("No file or directory found at '{}'", path.display());
+        }
+    }
+    files.dedup();
+    Ok(files)
+}
+
+fn search_dir(dir: &Path, found: &mut Vec<PathBuf>) -> Result<()> {
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+        let path = entry.path();
+        let name = entry.file_name();
+        let name = name.to_string_lossy();
+        if entry.file_type()?.is_dir() {
+            if !name.starts_with('.') && name != "node_modules" && name != "target" {
+                search_dir(&path, found)?;
+            }
+        } else if is_test_file(&path) {
+            // NOTE: Paths are cleaned up so that they are nicer in results,
+            // without the leading "./" that searching the current dir gives
+            found.push(
+                path.strip_prefix(".")
+                    .map(Path::to_path_buf)
+                    .unwrap_or(path),
+            );
+        }
+    }
+    Ok(())
+}
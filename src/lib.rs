@@ -6,4 +6,8 @@ pub mod roblox;
 #[cfg(test)]
 mod tests;
 
-pub use crate::lune::{Runtime, RuntimeError};
+pub use crate::lune::{
+    CoverageFormat, CoverageReport, DebugServer, FileCoverage, FunctionCoverage, ProfileFormat,
+    ProfileFrame, ProfileReport, Runtime, RuntimeError, TestFileReport, TestFormat, TestReport,
+    TestResult, TestRunner, TestStatus,
+};
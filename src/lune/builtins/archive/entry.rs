@@ -0,0 +1,81 @@
+use std::{
+    fmt,
+    path::{Component, Path, PathBuf},
+};
+
+use mlua::prelude::*;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ArchiveEntryKind {
+    File,
+    Dir,
+    Symlink,
+    Other,
+}
+
+impl fmt::Display for ArchiveEntryKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                Self::File => "file",
+                Self::Dir => "dir",
+                Self::Symlink => "symlink",
+                Self::Other => "other",
+            }
+        )
+    }
+}
+
+/**
+    A single entry in an archive, along with its
+    contents if the entry is a file and was read.
+*/
+#[derive(Debug, Clone)]
+pub struct ArchiveEntry {
+    pub(crate) path: String,
+    pub(crate) kind: ArchiveEntryKind,
+    pub(crate) size: u64,
+    pub(crate) contents: Option<Vec<u8>>,
+}
+
+impl<'lua> IntoLua<'lua> for ArchiveEntry {
+    fn into_lua(self, lua: &'lua Lua) -> LuaResult<LuaValue<'lua>> {
+        let tab = lua.create_table_with_capacity(0, 3)?;
+        tab.set("path", self.path)?;
+        tab.set("kind", self.kind.to_string())?;
+        tab.set("size", self.size)?;
+        tab.set_readonly(true);
+        Ok(LuaValue::Table(tab))
+    }
+}
+
+/**
+    Converts a path for an entry in an archive into a relative path that
+    is guaranteed to stay inside of the directory it gets extracted to.
+
+    Errors if the path is absolute or contains any parent directory components,
+    since writing such an entry could overwrite files outside of the directory.
+*/
+pub fn enclosed_entry_path(path: impl AsRef<str>) -> LuaResult<PathBuf> {
+    let path = path.as_ref();
+    let mut enclosed = PathBuf::new();
+    for component in Path::new(path).components() {
+        match component {
+            Component::Normal(part) => enclosed.push(part),
+            Component::CurDir => {}
+            Component::ParentDir | Component::RootDir | Component::Prefix(_) => {
+                return Err(LuaError::RuntimeError(format!(
+                    "Archive entry path '{path}' is not contained within the archive"
+                )))
+            }
+        }
+    }
+    if enclosed.as_os_str().is_empty() {
+        return Err(LuaError::RuntimeError(format!(
+            "Archive entry path '{path}' is empty"
+        )));
+    }
+    Ok(enclosed)
+}
@@ -0,0 +1,67 @@
+use std::fmt;
+
+use mlua::prelude::*;
+
+use crate::lune::builtins::serde::compress_decompress::CompressDecompressFormat;
+
+#[derive(Debug, Clone, Copy)]
+pub enum ArchiveFormat {
+    Zip,
+    Tar(Option<CompressDecompressFormat>),
+}
+
+impl ArchiveFormat {
+    pub fn compression(self) -> Option<CompressDecompressFormat> {
+        match self {
+            Self::Zip => None,
+            Self::Tar(compression) => compression,
+        }
+    }
+}
+
+impl fmt::Display for ArchiveFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Zip => write!(f, "zip"),
+            Self::Tar(None) => write!(f, "tar"),
+            Self::Tar(Some(CompressDecompressFormat::Brotli)) => write!(f, "tar.br"),
+            Self::Tar(Some(CompressDecompressFormat::GZip)) => write!(f, "tar.gz"),
+            Self::Tar(Some(CompressDecompressFormat::LZ4)) => write!(f, "tar.lz4"),
+            Self::Tar(Some(CompressDecompressFormat::ZLib)) => write!(f, "tar.zlib"),
+            Self::Tar(Some(CompressDecompressFormat::Zstd)) => write!(f, "tar.zst"),
+            Self::Tar(Some(CompressDecompressFormat::Xz)) => write!(f, "tar.xz"),
+            Self::Tar(Some(CompressDecompressFormat::Bzip2)) => write!(f, "tar.bz2"),
+        }
+    }
+}
+
+impl<'lua> FromLua<'lua> for ArchiveFormat {
+    fn from_lua(value: LuaValue<'lua>, _: &'lua Lua) -> LuaResult<Self> {
+        if let LuaValue::String(s) = &value {
+            match s.to_string_lossy().to_ascii_lowercase().trim() {
+                "zip" => Ok(Self::Zip),
+                "tar" => Ok(Self::Tar(None)),
+                "tar.br" | "tar.brotli" => Ok(Self::Tar(Some(CompressDecompressFormat::Brotli))),
+                "tar.gz" | "tar.gzip" | "tgz" => Ok(Self::Tar(Some(CompressDecompressFormat::GZip))),
+                "tar.lz4" => Ok(Self::Tar(Some(CompressDecompressFormat::LZ4))),
+                "tar.zlib" => Ok(Self::Tar(Some(CompressDecompressFormat::ZLib))),
+                "tar.zst" | "tar.zstd" | "tzst" => Ok(Self::Tar(Some(CompressDecompressFormat::Zstd))),
+                "tar.xz" | "txz" => Ok(Self::Tar(Some(CompressDecompressFormat::Xz))),
+                "tar.bz2" | "tar.bzip2" | "tbz2" => Ok(Self::Tar(Some(CompressDecompressFormat::Bzip2))),
+                kind => Err(LuaError::FromLuaConversionError {
+                    from: value.type_name(),
+                    to: "ArchiveFormat",
+                    message: Some(format!(
+                        "Invalid format '{kind}', valid formats are:  zip, tar, tar.br, tar.gz, tar.lz4, tar.zlib, tar.zst, tar.xz, tar.bz2"
+                    )),
+                }),
+            }
+        } else {
+            Err(LuaError::FromLuaConversionError {
+                from: value.type_name(),
+                to: "ArchiveFormat",
+                message: None,
+            })
+        }
+    }
+}
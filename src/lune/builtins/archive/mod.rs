@@ -0,0 +1,151 @@
+use std::path::{Path, PathBuf};
+
+use bstr::BString;
+use mlua::prelude::*;
+use tokio::fs;
+
+mod entry;
+mod format;
+mod reader;
+mod writer;
+
+use entry::{enclosed_entry_path, ArchiveEntry, ArchiveEntryKind};
+use format::ArchiveFormat;
+use reader::read_entries;
+use writer::write_entries;
+
+use super::serde::compress_decompress::{compress, decompress};
+use crate::lune::util::TableBuilder;
+
+pub fn create(lua: &Lua) -> LuaResult<LuaTable> {
+    TableBuilder::new(lua)?
+        .with_async_function("list", archive_list)?
+        .with_async_function("extract", archive_extract)?
+        .with_async_function("create", archive_create)?
+        .build_readonly()
+}
+
+async fn read_archive(
+    format: ArchiveFormat,
+    bytes: BString,
+    read_contents: bool,
+) -> LuaResult<Vec<ArchiveEntry>> {
+    let bytes = match format.compression() {
+        Some(compression) => decompress(compression, bytes).await?,
+        None => bytes.into(),
+    };
+    blocking::unblock(move || read_entries(format, bytes, read_contents)).await
+}
+
+async fn archive_list(
+    _: &Lua,
+    (format, bytes): (ArchiveFormat, BString),
+) -> LuaResult<Vec<ArchiveEntry>> {
+    read_archive(format, bytes, false).await
+}
+
+async fn archive_extract(
+    lua: &Lua,
+    (format, bytes, dir): (ArchiveFormat, BString, Option<String>),
+) -> LuaResult<Option<LuaTable>> {
+    let entries = read_archive(format, bytes, true).await?;
+    match dir {
+        Some(dir) => {
+            extract_to_dir(entries, PathBuf::from(dir)).await?;
+            Ok(None)
+        }
+        None => {
+            let tab = lua.create_table()?;
+            for entry in entries {
+                if let Some(contents) = entry.contents {
+                    tab.set(entry.path, lua.create_string(contents)?)?;
+                }
+            }
+            Ok(Some(tab))
+        }
+    }
+}
+
+async fn extract_to_dir(entries: Vec<ArchiveEntry>, dir: PathBuf) -> LuaResult<()> {
+    // NOTE: We make sure that all paths are safe before writing anything,
+    // so that a malicious archive can not leave a partial extraction behind
+    let mut targets = Vec::with_capacity(entries.len());
+    for entry in entries {
+        let enclosed = enclosed_entry_path(&entry.path)?;
+        ensure_no_symlinks(&dir, &enclosed, &entry.path).await?;
+        let target = dir.join(enclosed);
+        targets.push((entry, target));
+    }
+
+    fs::create_dir_all(&dir).await?;
+
+    // NOTE: Symlinks and other special entries are skipped, since
+    // symlinks could otherwise be used to write outside of the directory
+    for (entry, target) in targets {
+        match entry.kind {
+            ArchiveEntryKind::Dir => {
+                fs::create_dir_all(&target).await?;
+            }
+            ArchiveEntryKind::File => {
+                if let Some(parent) = target.parent() {
+                    fs::create_dir_all(parent).await?;
+                }
+                fs::write(&target, entry.contents.unwrap_or_default()).await?;
+            }
+            ArchiveEntryKind::Symlink | ArchiveEntryKind::Other => {}
+        }
+    }
+
+    Ok(())
+}
+
+/**
+    Makes sure that none of the components of the given relative path,
+    including the last one, are symlinks that already exist in the directory.
+
+    Entry paths are checked to be enclosed as text only, and writing through an
+    existing symlink such as `dir/link -> /etc` would still write outside of it.
+*/
+async fn ensure_no_symlinks(dir: &Path, relative: &Path, entry_path: &str) -> LuaResult<()> {
+    let mut current = dir.to_path_buf();
+    for component in relative.components() {
+        current.push(component);
+        match fs::symlink_metadata(&current).await {
+            Ok(meta) if meta.file_type().is_symlink() => {
+                return Err(LuaError::RuntimeError(format!(
+                    "Archive entry path '{entry_path}' would be extracted through the symlink at '{}'",
+                    current.display()
+                )))
+            }
+            Ok(_) => {}
+            // NOTE: Nothing below a missing path can exist yet, and
+            // only directories and files get created while extracting
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => break,
+            Err(e) => return Err(e.into_lua_err()),
+        }
+    }
+    Ok(())
+}
+
+async fn archive_create<'lua>(
+    lua: &'lua Lua,
+    (format, files): (ArchiveFormat, LuaTable<'lua>),
+) -> LuaResult<LuaString<'lua>> {
+    let mut entries = Vec::new();
+    for pair in files.pairs::<String, BString>() {
+        let (path, contents) = pair?;
+        entries.push((path, Vec::from(contents)));
+    }
+
+    // Sort entries by path so that archives are reproducible,
+    // table iteration order is not guaranteed to be stable
+    entries.sort_by(|a, b| a.0.cmp(&b.0));
+
+    let bytes = blocking::unblock(move || write_entries(format, entries)).await?;
+    let bytes = match format.compression() {
+        Some(compression) => compress(compression, bytes).await?,
+        None => bytes,
+    };
+
+    lua.create_string(bytes)
+}
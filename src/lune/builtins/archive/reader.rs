@@ -0,0 +1,101 @@
+use std::io::{Cursor, Read};
+
+use mlua::prelude::*;
+use tar::{Archive as TarArchive, EntryType as TarEntryType};
+use zip_next::ZipArchive;
+
+use super::{
+    entry::{ArchiveEntry, ArchiveEntryKind},
+    format::ArchiveFormat,
+};
+
+const UNIX_FILE_TYPE_MASK: u32 = 0o170_000;
+const UNIX_FILE_TYPE_SYMLINK: u32 = 0o120_000;
+
+/**
+    Reads all entries from an archive, optionally reading the contents of files.
+
+    Any compression for the archive must already have been removed from `bytes`.
+
+    This will block the current thread and should be called
+    from a blocking context such as `blocking::unblock`.
+*/
+pub fn read_entries(
+    format: ArchiveFormat,
+    bytes: Vec<u8>,
+    read_contents: bool,
+) -> LuaResult<Vec<ArchiveEntry>> {
+    match format {
+        ArchiveFormat::Zip => read_zip_entries(bytes, read_contents),
+        ArchiveFormat::Tar(_) => read_tar_entries(bytes, read_contents),
+    }
+}
+
+fn read_zip_entries(bytes: Vec<u8>, read_contents: bool) -> LuaResult<Vec<ArchiveEntry>> {
+    let mut archive = ZipArchive::new(Cursor::new(bytes)).into_lua_err()?;
+    let mut entries = Vec::with_capacity(archive.len());
+    for index in 0..archive.len() {
+        let mut file = archive.by_index(index).into_lua_err()?;
+        let is_symlink = file
+            .unix_mode()
+            .is_some_and(|mode| mode & UNIX_FILE_TYPE_MASK == UNIX_FILE_TYPE_SYMLINK);
+        let kind = if file.is_dir() {
+            ArchiveEntryKind::Dir
+        } else if is_symlink {
+            ArchiveEntryKind::Symlink
+        } else {
+            ArchiveEntryKind::File
+        };
+        let contents = if read_contents && kind == ArchiveEntryKind::File {
+            let mut contents = Vec::with_capacity(file.size() as usize);
+            file.read_to_end(&mut contents)?;
+            Some(contents)
+        } else {
+            None
+        };
+        entries.push(ArchiveEntry {
+            path: file.name().trim_end_matches('/').to_string(),
+            kind,
+            size: file.size(),
+            contents,
+        });
+    }
+    Ok(entries)
+}
+
+fn read_tar_entries(bytes: Vec<u8>, read_contents: bool) -> LuaResult<Vec<ArchiveEntry>> {
+    let mut archive = TarArchive::new(Cursor::new(bytes));
+    let mut entries = Vec::new();
+    for entry in archive.entries()? {
+        let mut entry = entry?;
+        let kind = match entry.header().entry_type() {
+            TarEntryType::Regular | TarEntryType::Continuous => ArchiveEntryKind::File,
+            TarEntryType::Directory => ArchiveEntryKind::Dir,
+            TarEntryType::Symlink | TarEntryType::Link => ArchiveEntryKind::Symlink,
+            _ => ArchiveEntryKind::Other,
+        };
+        let path = match entry.path()?.to_str() {
+            Some(path) => path.trim_end_matches('/').to_string(),
+            None => {
+                return Err(LuaError::RuntimeError(
+                    "Archive entry path could not be converted into a string".to_string(),
+                ))
+            }
+        };
+        let size = entry.size();
+        let contents = if read_contents && kind == ArchiveEntryKind::File {
+            let mut contents = Vec::with_capacity(size as usize);
+            entry.read_to_end(&mut contents)?;
+            Some(contents)
+        } else {
+            None
+        };
+        entries.push(ArchiveEntry {
+            path,
+            kind,
+            size,
+            contents,
+        });
+    }
+    Ok(entries)
+}
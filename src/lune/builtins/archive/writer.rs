@@ -0,0 +1,62 @@
+use std::io::{Cursor, Write};
+
+use mlua::prelude::*;
+use tar::{Builder as TarBuilder, Header as TarHeader};
+use zip_next::{write::SimpleFileOptions, CompressionMethod, DateTime, ZipWriter};
+
+use super::{entry::enclosed_entry_path, format::ArchiveFormat};
+
+const FILE_MODE: u32 = 0o644;
+
+/**
+    Writes the given files into a new archive, without any compression for tar archives.
+
+    Timestamps for all entries are zeroed out so that creating
+    an archive from the same files is always reproducible.
+
+    This will block the current thread and should be called
+    from a blocking context such as `blocking::unblock`.
+*/
+pub fn write_entries(format: ArchiveFormat, files: Vec<(String, Vec<u8>)>) -> LuaResult<Vec<u8>> {
+    match format {
+        ArchiveFormat::Zip => write_zip_entries(files),
+        ArchiveFormat::Tar(_) => write_tar_entries(files),
+    }
+}
+
+fn write_zip_entries(files: Vec<(String, Vec<u8>)>) -> LuaResult<Vec<u8>> {
+    let options = SimpleFileOptions::default()
+        .compression_method(CompressionMethod::Deflated)
+        .last_modified_time(DateTime::default())
+        .unix_permissions(FILE_MODE);
+    let mut writer = ZipWriter::new(Cursor::new(Vec::new()));
+    for (path, contents) in files {
+        let path = entry_name(&path)?;
+        writer.start_file(path, options).into_lua_err()?;
+        writer.write_all(&contents)?;
+    }
+    Ok(writer.finish().into_lua_err()?.into_inner())
+}
+
+fn write_tar_entries(files: Vec<(String, Vec<u8>)>) -> LuaResult<Vec<u8>> {
+    let mut builder = TarBuilder::new(Vec::new());
+    for (path, contents) in files {
+        let path = entry_name(&path)?;
+        let mut header = TarHeader::new_gnu();
+        header.set_size(contents.len() as u64);
+        header.set_mode(FILE_MODE);
+        header.set_mtime(0);
+        builder.append_data(&mut header, path, contents.as_slice())?;
+    }
+    Ok(builder.into_inner()?)
+}
+
+fn entry_name(path: &str) -> LuaResult<String> {
+    // NOTE: Archives always use forward slashes as separators, regardless of platform
+    let enclosed = enclosed_entry_path(path)?;
+    let parts = enclosed
+        .components()
+        .map(|component| component.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>();
+    Ok(parts.join("/"))
+}
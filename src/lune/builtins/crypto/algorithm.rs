@@ -0,0 +1,134 @@
+use std::fmt;
+
+use hmac::{
+    digest::{DynDigest, KeyInit},
+    SimpleHmac,
+};
+use mlua::prelude::*;
+
+use md5::Md5;
+use sha1::Sha1;
+use sha2::{Sha224, Sha256, Sha384, Sha512};
+use sha3::{Sha3_224, Sha3_256, Sha3_384, Sha3_512};
+
+pub type BoxedDigest = Box<dyn DynDigest>;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HashAlgorithm {
+    Md5,
+    Sha1,
+    Sha224,
+    Sha256,
+    Sha384,
+    Sha512,
+    Sha3_224,
+    Sha3_256,
+    Sha3_384,
+    Sha3_512,
+    Blake3,
+}
+
+impl HashAlgorithm {
+    const ALL: [Self; 11] = [
+        Self::Md5,
+        Self::Sha1,
+        Self::Sha224,
+        Self::Sha256,
+        Self::Sha384,
+        Self::Sha512,
+        Self::Sha3_224,
+        Self::Sha3_256,
+        Self::Sha3_384,
+        Self::Sha3_512,
+        Self::Blake3,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Md5 => "md5",
+            Self::Sha1 => "sha1",
+            Self::Sha224 => "sha224",
+            Self::Sha256 => "sha256",
+            Self::Sha384 => "sha384",
+            Self::Sha512 => "sha512",
+            Self::Sha3_224 => "sha3-224",
+            Self::Sha3_256 => "sha3-256",
+            Self::Sha3_384 => "sha3-384",
+            Self::Sha3_512 => "sha3-512",
+            Self::Blake3 => "blake3",
+        }
+    }
+
+    pub fn create_digest(self) -> BoxedDigest {
+        match self {
+            Self::Md5 => Box::<Md5>::default(),
+            Self::Sha1 => Box::<Sha1>::default(),
+            Self::Sha224 => Box::<Sha224>::default(),
+            Self::Sha256 => Box::<Sha256>::default(),
+            Self::Sha384 => Box::<Sha384>::default(),
+            Self::Sha512 => Box::<Sha512>::default(),
+            Self::Sha3_224 => Box::<Sha3_224>::default(),
+            Self::Sha3_256 => Box::<Sha3_256>::default(),
+            Self::Sha3_384 => Box::<Sha3_384>::default(),
+            Self::Sha3_512 => Box::<Sha3_512>::default(),
+            Self::Blake3 => Box::<blake3::Hasher>::default(),
+        }
+    }
+
+    pub fn create_hmac(self, key: impl AsRef<[u8]>) -> LuaResult<BoxedDigest> {
+        fn create<D>(key: &[u8]) -> LuaResult<BoxedDigest>
+        where
+            SimpleHmac<D>: DynDigest + KeyInit + 'static,
+            D: hmac::digest::Digest + hmac::digest::core_api::BlockSizeUser,
+        {
+            let mac = <SimpleHmac<D> as KeyInit>::new_from_slice(key).into_lua_err()?;
+            Ok(Box::new(mac))
+        }
+
+        let key = key.as_ref();
+        match self {
+            Self::Md5 => create::<Md5>(key),
+            Self::Sha1 => create::<Sha1>(key),
+            Self::Sha224 => create::<Sha224>(key),
+            Self::Sha256 => create::<Sha256>(key),
+            Self::Sha384 => create::<Sha384>(key),
+            Self::Sha512 => create::<Sha512>(key),
+            Self::Sha3_224 => create::<Sha3_224>(key),
+            Self::Sha3_256 => create::<Sha3_256>(key),
+            Self::Sha3_384 => create::<Sha3_384>(key),
+            Self::Sha3_512 => create::<Sha3_512>(key),
+            Self::Blake3 => create::<blake3::Hasher>(key),
+        }
+    }
+}
+
+impl fmt::Display for HashAlgorithm {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+impl<'lua> FromLua<'lua> for HashAlgorithm {
+    fn from_lua(value: LuaValue<'lua>, _: &'lua Lua) -> LuaResult<Self> {
+        if let LuaValue::String(s) = &value {
+            let name = s.to_string_lossy().to_ascii_lowercase();
+            Self::ALL
+                .into_iter()
+                .find(|algorithm| algorithm.name() == name.trim())
+                .ok_or_else(|| LuaError::FromLuaConversionError {
+                    from: value.type_name(),
+                    to: "HashAlgorithm",
+                    message: Some(format!(
+                        "Invalid algorithm '{name}', valid algorithms are:  {}",
+                        Self::ALL.map(Self::name).join(", ")
+                    )),
+                })
+        } else {
+            Err(LuaError::FromLuaConversionError {
+                from: value.type_name(),
+                to: "HashAlgorithm",
+                message: None,
+            })
+        }
+    }
+}
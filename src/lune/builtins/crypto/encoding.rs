@@ -0,0 +1,58 @@
+use mlua::prelude::*;
+
+use crate::lune::builtins::serde::binary::{encode_base64, encode_hex, BinaryOptions};
+
+/**
+    How binary output such as digests and random bytes is returned to Luau.
+
+    Binary output is returned as a string containing raw bytes,
+    following the same conventions and encoders as the `serde` builtin.
+*/
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OutputEncoding {
+    Hex,
+    Base64,
+    Binary,
+}
+
+impl OutputEncoding {
+    pub fn encode<'lua>(
+        self,
+        lua: &'lua Lua,
+        bytes: impl AsRef<[u8]>,
+    ) -> LuaResult<LuaString<'lua>> {
+        let bytes = bytes.as_ref();
+        match self {
+            Self::Hex => lua.create_string(encode_hex(bytes)),
+            Self::Base64 => {
+                lua.create_string(encode_base64(bytes, false, BinaryOptions::default()))
+            }
+            Self::Binary => lua.create_string(bytes),
+        }
+    }
+}
+
+impl<'lua> FromLua<'lua> for OutputEncoding {
+    fn from_lua(value: LuaValue<'lua>, _: &'lua Lua) -> LuaResult<Self> {
+        if let LuaValue::String(s) = &value {
+            match s.to_string_lossy().to_ascii_lowercase().trim() {
+                "hex" => Ok(Self::Hex),
+                "base64" => Ok(Self::Base64),
+                "binary" => Ok(Self::Binary),
+                kind => Err(LuaError::FromLuaConversionError {
+                    from: value.type_name(),
+                    to: "OutputEncoding",
+                    message: Some(format!(
+                        "Invalid encoding '{kind}', valid encodings are:  hex, base64, binary"
+                    )),
+                }),
+            }
+        } else {
+            Err(LuaError::FromLuaConversionError {
+                from: value.type_name(),
+                to: "OutputEncoding",
+                message: None,
+            })
+        }
+    }
+}
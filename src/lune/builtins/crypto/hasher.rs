@@ -0,0 +1,67 @@
+use bstr::BString;
+use mlua::prelude::*;
+
+use super::{
+    algorithm::{BoxedDigest, HashAlgorithm},
+    encoding::OutputEncoding,
+};
+
+/**
+    An incremental hash or HMAC, which may be updated with
+    more data any number of times before producing a digest.
+*/
+pub struct LuaHasher {
+    algorithm: HashAlgorithm,
+    inner: Option<BoxedDigest>,
+}
+
+impl LuaHasher {
+    pub fn new(algorithm: HashAlgorithm) -> Self {
+        Self {
+            algorithm,
+            inner: Some(algorithm.create_digest()),
+        }
+    }
+
+    pub fn new_hmac(algorithm: HashAlgorithm, key: impl AsRef<[u8]>) -> LuaResult<Self> {
+        Ok(Self {
+            algorithm,
+            inner: Some(algorithm.create_hmac(key)?),
+        })
+    }
+
+    pub fn update(&mut self, data: impl AsRef<[u8]>) -> LuaResult<()> {
+        match self.inner.as_mut() {
+            Some(inner) => {
+                inner.update(data.as_ref());
+                Ok(())
+            }
+            None => Err(LuaError::runtime("Hasher has already been digested")),
+        }
+    }
+
+    pub fn digest(&mut self) -> LuaResult<Box<[u8]>> {
+        match self.inner.take() {
+            Some(inner) => Ok(inner.finalize()),
+            None => Err(LuaError::runtime("Hasher has already been digested")),
+        }
+    }
+}
+
+impl LuaUserData for LuaHasher {
+    fn add_fields<'lua, F: LuaUserDataFields<'lua, Self>>(fields: &mut F) {
+        fields.add_meta_field(LuaMetaMethod::Type, "Hasher");
+        fields.add_field_method_get("algorithm", |_, this| Ok(this.algorithm.name()));
+    }
+
+    fn add_methods<'lua, M: LuaUserDataMethods<'lua, Self>>(methods: &mut M) {
+        methods.add_function("update", |_, (this, data): (LuaAnyUserData, BString)| {
+            this.borrow_mut::<Self>()?.update(data)?;
+            Ok(this)
+        });
+        methods.add_method_mut("digest", |lua, this, encoding: Option<OutputEncoding>| {
+            let digest = this.digest()?;
+            encoding.unwrap_or(OutputEncoding::Hex).encode(lua, digest)
+        });
+    }
+}
@@ -0,0 +1,72 @@
+use bstr::BString;
+use mlua::prelude::*;
+use subtle::ConstantTimeEq;
+
+use crate::lune::util::TableBuilder;
+
+mod algorithm;
+mod encoding;
+mod hasher;
+
+use self::{algorithm::HashAlgorithm, encoding::OutputEncoding, hasher::LuaHasher};
+
+const MAX_RANDOM_BYTES: usize = 1024 * 1024;
+
+pub fn create(lua: &Lua) -> LuaResult<LuaTable> {
+    TableBuilder::new(lua)?
+        .with_function("hash", crypto_hash)?
+        .with_function("hmac", crypto_hmac)?
+        .with_function("createHash", crypto_create_hash)?
+        .with_function("createHmac", crypto_create_hmac)?
+        .with_function("timingSafeEqual", crypto_timing_safe_equal)?
+        .with_function("randomBytes", crypto_random_bytes)?
+        .build_readonly()
+}
+
+fn crypto_hash<'lua>(
+    lua: &'lua Lua,
+    (algorithm, data, encoding): (HashAlgorithm, BString, Option<OutputEncoding>),
+) -> LuaResult<LuaString<'lua>> {
+    let mut hasher = LuaHasher::new(algorithm);
+    hasher.update(data)?;
+    let digest = hasher.digest()?;
+    encoding.unwrap_or(OutputEncoding::Hex).encode(lua, digest)
+}
+
+fn crypto_hmac<'lua>(
+    lua: &'lua Lua,
+    (algorithm, key, data, encoding): (HashAlgorithm, BString, BString, Option<OutputEncoding>),
+) -> LuaResult<LuaString<'lua>> {
+    let mut hasher = LuaHasher::new_hmac(algorithm, key)?;
+    hasher.update(data)?;
+    let digest = hasher.digest()?;
+    encoding.unwrap_or(OutputEncoding::Hex).encode(lua, digest)
+}
+
+fn crypto_create_hash(_: &Lua, algorithm: HashAlgorithm) -> LuaResult<LuaHasher> {
+    Ok(LuaHasher::new(algorithm))
+}
+
+fn crypto_create_hmac(_: &Lua, (algorithm, key): (HashAlgorithm, BString)) -> LuaResult<LuaHasher> {
+    LuaHasher::new_hmac(algorithm, key)
+}
+
+fn crypto_timing_safe_equal(_: &Lua, (a, b): (BString, BString)) -> LuaResult<bool> {
+    Ok(a.as_slice().ct_eq(b.as_slice()).into())
+}
+
+fn crypto_random_bytes<'lua>(
+    lua: &'lua Lua,
+    (length, encoding): (usize, Option<OutputEncoding>),
+) -> LuaResult<LuaString<'lua>> {
+    if length > MAX_RANDOM_BYTES {
+        return Err(LuaError::RuntimeError(format!(
+            "Invalid length - expected at most {MAX_RANDOM_BYTES} bytes, got {length}"
+        )));
+    }
+    let mut bytes = vec![0; length];
+    getrandom::getrandom(&mut bytes).into_lua_err()?;
+    encoding
+        .unwrap_or(OutputEncoding::Binary)
+        .encode(lua, bytes)
+}
@@ -1,4 +1,5 @@
 use std::cmp::Ordering;
+use std::time::SystemTime;
 
 use mlua::prelude::*;
 
@@ -78,6 +79,14 @@ impl DateTime {
         Ok(Self { inner })
     }
 
+    /**
+        Converts this `DateTime` into a [`SystemTime`], for use
+        with standard library filesystem and time APIs.
+    */
+    pub fn to_system_time(self) -> SystemTime {
+        SystemTime::from(self.inner)
+    }
+
     /**
         Transforms individual date & time values into a new
         `DateTime` struct, using the universal (UTC) time zone.
@@ -0,0 +1,140 @@
+use std::fs::Metadata;
+use std::io::{Error as IoError, ErrorKind as IoErrorKind, Result as IoResult};
+use std::path::{Path, PathBuf};
+use std::process;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use mlua::prelude::*;
+use tokio::fs;
+use tokio::io::AsyncWriteExt;
+
+static TEMP_FILE_COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+const MAX_SYMLINK_DEPTH: usize = 40;
+
+/**
+    Creates a path for a temporary file in the same directory as `path`.
+
+    The temporary file must be in the same directory so that the
+    final rename happens on the same mount point and is atomic.
+*/
+fn temp_path_for(path: &Path) -> LuaResult<PathBuf> {
+    let file_name = path.file_name().ok_or_else(|| {
+        LuaError::RuntimeError(format!(
+            "The path '{}' does not point to a file",
+            path.display()
+        ))
+    })?;
+    let count = TEMP_FILE_COUNTER.fetch_add(1, Ordering::Relaxed);
+    let temp_name = format!(
+        ".{}.{}.{}.tmp",
+        file_name.to_string_lossy(),
+        process::id(),
+        count
+    );
+    Ok(path.with_file_name(temp_name))
+}
+
+/**
+    Follows any symlinks at `path` to the file that they point to,
+    which does not have to exist yet, so that it can be written to
+    without replacing the links themselves with regular files.
+*/
+async fn resolve_symlinks(path: &Path) -> IoResult<PathBuf> {
+    let mut path = path.to_path_buf();
+    for _ in 0..MAX_SYMLINK_DEPTH {
+        match fs::symlink_metadata(&path).await {
+            Ok(meta) if meta.file_type().is_symlink() => {
+                let target = fs::read_link(&path).await?;
+                // NOTE: Relative link targets are relative to the directory of the link,
+                // and joining an absolute target replaces the directory entirely
+                path = match path.parent() {
+                    Some(parent) => parent.join(target),
+                    None => target,
+                };
+            }
+            Err(e) if e.kind() != IoErrorKind::NotFound => return Err(e),
+            _ => return Ok(path),
+        }
+    }
+    Err(IoError::other(format!(
+        "Too many levels of symbolic links at '{}'",
+        path.display()
+    )))
+}
+
+/**
+    Copies the permissions, and the owner where possible, of an existing file to a new file.
+*/
+async fn copy_permissions(meta: &Metadata, path: &Path) -> IoResult<()> {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::MetadataExt;
+        // NOTE: Only privileged users may give files away to other owners,
+        // and in that case the new file keeps the owner of the current user
+        std::os::unix::fs::chown(path, Some(meta.uid()), Some(meta.gid())).ok();
+    }
+    fs::set_permissions(path, meta.permissions()).await
+}
+
+/**
+    Writes `contents` to the file at `path` atomically.
+
+    The contents are first written and flushed to a temporary file in
+    the same directory, which is then renamed over the target path, so
+    that readers will only ever see either the old or new file contents.
+
+    An existing file keeps its permissions and owner, and symlinks are
+    followed, so that the file they point to is written instead of the
+    links being replaced.
+*/
+pub async fn write_atomic(path: impl AsRef<Path>, contents: impl AsRef<[u8]>) -> LuaResult<()> {
+    let path = resolve_symlinks(path.as_ref()).await?;
+    let path = path.as_path();
+    let temp_path = temp_path_for(path)?;
+    let existing = match fs::metadata(path).await {
+        Ok(meta) => Some(meta),
+        Err(e) if e.kind() == IoErrorKind::NotFound => None,
+        Err(e) => return Err(e.into_lua_err()),
+    };
+
+    let result = async {
+        let mut options = fs::OpenOptions::new();
+        options.write(true).create_new(true);
+        // NOTE: The contents of an existing file may be secret, so the temporary
+        // file must not be readable by anyone else before permissions are copied
+        #[cfg(unix)]
+        if existing.is_some() {
+            options.mode(0o600);
+        }
+        let mut file = options.open(&temp_path).await?;
+        if let Some(meta) = &existing {
+            copy_permissions(meta, &temp_path).await?;
+        }
+        file.write_all(contents.as_ref()).await?;
+        file.sync_all().await?;
+        drop(file);
+        fs::rename(&temp_path, path).await
+    }
+    .await;
+
+    if let Err(e) = result {
+        // NOTE: We don't care if removing fails here, the temp
+        // file may not have been created in the first place
+        fs::remove_file(&temp_path).await.ok();
+        return Err(e.into_lua_err());
+    }
+
+    // Make sure the rename itself is persisted, this is
+    // only possible on unix where directories can be opened
+    #[cfg(unix)]
+    {
+        let dir = match path.parent() {
+            Some(parent) if !parent.as_os_str().is_empty() => parent,
+            _ => Path::new("."),
+        };
+        fs::File::open(dir).await?.sync_all().await?;
+    }
+
+    Ok(())
+}
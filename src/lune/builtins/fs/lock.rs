@@ -0,0 +1,67 @@
+use std::fs::{File, OpenOptions};
+
+use mlua::prelude::*;
+
+/**
+    An advisory lock held on a file.
+
+    The lock is released when `unlock` is called, or when the
+    lock is dropped, such as when it gets garbage collected.
+*/
+#[derive(Debug)]
+pub struct FsLock {
+    path: String,
+    exclusive: bool,
+    file: Option<File>,
+}
+
+impl FsLock {
+    /**
+        Acquires an advisory lock on the file at `path`, creating the file if it does not exist.
+
+        This will block the current thread until the lock can be acquired,
+        and should be called from a blocking context such as `blocking::unblock`.
+    */
+    pub fn acquire(path: String, exclusive: bool) -> LuaResult<Self> {
+        let file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(false)
+            .open(&path)
+            .into_lua_err()?;
+        if exclusive {
+            file.lock().into_lua_err()?;
+        } else {
+            file.lock_shared().into_lua_err()?;
+        }
+        Ok(Self {
+            path,
+            exclusive,
+            file: Some(file),
+        })
+    }
+
+    fn unlock(&mut self) -> LuaResult<()> {
+        match self.file.take() {
+            Some(file) => file.unlock().into_lua_err(),
+            None => Ok(()),
+        }
+    }
+}
+
+impl LuaUserData for FsLock {
+    fn add_fields<'lua, F: LuaUserDataFields<'lua, Self>>(fields: &mut F) {
+        fields.add_meta_field(LuaMetaMethod::Type, "FsLock");
+        fields.add_field_method_get("path", |_, this| Ok(this.path.clone()));
+        fields.add_field_method_get("exclusive", |_, this| Ok(this.exclusive));
+        fields.add_field_method_get("locked", |_, this| Ok(this.file.is_some()));
+    }
+
+    fn add_methods<'lua, M: LuaUserDataMethods<'lua, Self>>(methods: &mut M) {
+        methods.add_method_mut("unlock", |_, this, ()| this.unlock());
+        methods.add_meta_method(LuaMetaMethod::ToString, |_, this, ()| {
+            Ok(format!("FsLock({})", this.path))
+        });
+    }
+}
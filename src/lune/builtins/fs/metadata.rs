@@ -6,6 +6,9 @@ use std::{
     time::SystemTime,
 };
 
+#[cfg(unix)]
+use std::os::unix::fs::{MetadataExt, PermissionsExt};
+
 use mlua::prelude::*;
 
 use crate::lune::builtins::datetime::DateTime;
@@ -74,20 +77,26 @@ impl<'lua> IntoLua<'lua> for FsMetadataKind {
 #[derive(Debug, Clone)]
 pub struct FsPermissions {
     pub(crate) read_only: bool,
+    pub(crate) mode: Option<u32>,
 }
 
 impl From<StdPermissions> for FsPermissions {
     fn from(value: StdPermissions) -> Self {
         Self {
             read_only: value.readonly(),
+            #[cfg(unix)]
+            mode: Some(value.mode() & 0o7777),
+            #[cfg(not(unix))]
+            mode: None,
         }
     }
 }
 
 impl<'lua> IntoLua<'lua> for FsPermissions {
     fn into_lua(self, lua: &'lua Lua) -> LuaResult<LuaValue<'lua>> {
-        let tab = lua.create_table_with_capacity(0, 1)?;
+        let tab = lua.create_table_with_capacity(0, 2)?;
         tab.set("readOnly", self.read_only)?;
+        tab.set("mode", self.mode)?;
         tab.set_readonly(true);
         Ok(LuaValue::Table(tab))
     }
@@ -101,6 +110,12 @@ pub struct FsMetadata {
     pub(crate) modified_at: Option<DateTime>,
     pub(crate) accessed_at: Option<DateTime>,
     pub(crate) permissions: Option<FsPermissions>,
+    pub(crate) size: Option<u64>,
+    pub(crate) size_on_disk: Option<u64>,
+    pub(crate) uid: Option<u32>,
+    pub(crate) gid: Option<u32>,
+    pub(crate) inode: Option<u64>,
+    pub(crate) link_count: Option<u64>,
 }
 
 impl FsMetadata {
@@ -112,19 +127,31 @@ impl FsMetadata {
             modified_at: None,
             accessed_at: None,
             permissions: None,
+            size: None,
+            size_on_disk: None,
+            uid: None,
+            gid: None,
+            inode: None,
+            link_count: None,
         }
     }
 }
 
 impl<'lua> IntoLua<'lua> for FsMetadata {
     fn into_lua(self, lua: &'lua Lua) -> LuaResult<LuaValue<'lua>> {
-        let tab = lua.create_table_with_capacity(0, 6)?;
+        let tab = lua.create_table_with_capacity(0, 12)?;
         tab.set("kind", self.kind)?;
         tab.set("exists", self.exists)?;
         tab.set("createdAt", self.created_at)?;
         tab.set("modifiedAt", self.modified_at)?;
         tab.set("accessedAt", self.accessed_at)?;
         tab.set("permissions", self.permissions)?;
+        tab.set("size", self.size)?;
+        tab.set("sizeOnDisk", self.size_on_disk)?;
+        tab.set("uid", self.uid)?;
+        tab.set("gid", self.gid)?;
+        tab.set("inode", self.inode)?;
+        tab.set("linkCount", self.link_count)?;
         tab.set_readonly(true);
         Ok(LuaValue::Table(tab))
     }
@@ -139,6 +166,29 @@ impl From<StdMetadata> for FsMetadata {
             modified_at: system_time_to_timestamp(value.modified()),
             accessed_at: system_time_to_timestamp(value.accessed()),
             permissions: Some(FsPermissions::from(value.permissions())),
+            size: Some(value.len()),
+            // NOTE: Block counts are always given in units of 512 bytes on unix,
+            // regardless of the actual block size used by the filesystem
+            #[cfg(unix)]
+            size_on_disk: Some(value.blocks() * 512),
+            #[cfg(unix)]
+            uid: Some(value.uid()),
+            #[cfg(unix)]
+            gid: Some(value.gid()),
+            #[cfg(unix)]
+            inode: Some(value.ino()),
+            #[cfg(unix)]
+            link_count: Some(value.nlink()),
+            #[cfg(not(unix))]
+            size_on_disk: None,
+            #[cfg(not(unix))]
+            uid: None,
+            #[cfg(not(unix))]
+            gid: None,
+            #[cfg(not(unix))]
+            inode: None,
+            #[cfg(not(unix))]
+            link_count: None,
         }
     }
 }
@@ -1,5 +1,6 @@
+use std::fs::FileTimes;
 use std::io::ErrorKind as IoErrorKind;
-use std::path::{PathBuf, MAIN_SEPARATOR};
+use std::path::{Path, PathBuf, MAIN_SEPARATOR};
 
 use bstr::{BString, ByteSlice};
 use mlua::prelude::*;
@@ -7,13 +8,22 @@ use tokio::fs;
 
 use crate::lune::util::TableBuilder;
 
+mod atomic;
 mod copy;
+mod lock;
 mod metadata;
 mod options;
+mod temp;
 
+use atomic::write_atomic;
 use copy::copy;
+use lock::FsLock;
 use metadata::FsMetadata;
-use options::FsWriteOptions;
+use options::{
+    FsLockOptions, FsPermissionsOptions, FsTempOptions, FsTimesOptions, FsWriteFileOptions,
+    FsWriteOptions,
+};
+use temp::FsTempPath;
 
 pub fn create(lua: &Lua) -> LuaResult<LuaTable> {
     TableBuilder::new(lua)?
@@ -28,6 +38,14 @@ pub fn create(lua: &Lua) -> LuaResult<LuaTable> {
         .with_async_function("isDir", fs_is_dir)?
         .with_async_function("move", fs_move)?
         .with_async_function("copy", fs_copy)?
+        .with_async_function("setPermissions", fs_set_permissions)?
+        .with_async_function("setTimes", fs_set_times)?
+        .with_async_function("createSymlink", fs_create_symlink)?
+        .with_async_function("createHardLink", fs_create_hard_link)?
+        .with_async_function("readLink", fs_read_link)?
+        .with_async_function("lock", fs_lock)?
+        .with_async_function("tempDir", fs_temp_dir)?
+        .with_async_function("tempFile", fs_temp_file)?
         .build_readonly()
 }
 
@@ -66,8 +84,15 @@ async fn fs_read_dir(_: &Lua, path: String) -> LuaResult<Vec<String>> {
     Ok(dir_strings_no_prefix)
 }
 
-async fn fs_write_file(_: &Lua, (path, contents): (String, BString)) -> LuaResult<()> {
-    fs::write(&path, contents.as_bytes()).await.into_lua_err()
+async fn fs_write_file(
+    _: &Lua,
+    (path, contents, options): (String, BString, FsWriteFileOptions),
+) -> LuaResult<()> {
+    if options.atomic {
+        write_atomic(&path, contents.as_bytes()).await
+    } else {
+        fs::write(&path, contents.as_bytes()).await.into_lua_err()
+    }
 }
 
 async fn fs_write_dir(_: &Lua, path: String) -> LuaResult<()> {
@@ -128,3 +153,126 @@ async fn fs_move(_: &Lua, (from, to, options): (String, String, FsWriteOptions))
 async fn fs_copy(_: &Lua, (from, to, options): (String, String, FsWriteOptions)) -> LuaResult<()> {
     copy(from, to, options).await
 }
+
+async fn fs_set_permissions(
+    _: &Lua,
+    (path, options): (String, FsPermissionsOptions),
+) -> LuaResult<()> {
+    let mut permissions = fs::metadata(&path).await.into_lua_err()?.permissions();
+    #[cfg(unix)]
+    if let Some(mode) = options.mode {
+        use std::os::unix::fs::PermissionsExt;
+        permissions.set_mode(mode);
+    }
+    #[cfg(not(unix))]
+    if options.mode.is_some() {
+        return Err(LuaError::RuntimeError(
+            "Permission modes are only supported on unix platforms".to_string(),
+        ));
+    }
+    if let Some(read_only) = options.read_only {
+        permissions.set_readonly(read_only);
+    }
+    fs::set_permissions(&path, permissions).await.into_lua_err()
+}
+
+async fn fs_set_times(_: &Lua, (path, options): (String, FsTimesOptions)) -> LuaResult<()> {
+    let mut times = FileTimes::new();
+    if let Some(accessed_at) = options.accessed_at {
+        times = times.set_accessed(accessed_at);
+    }
+    if let Some(modified_at) = options.modified_at {
+        times = times.set_modified(modified_at);
+    }
+    let file = open_for_set_times(&path)
+        .await
+        .into_lua_err()?
+        .into_std()
+        .await;
+    blocking::unblock(move || file.set_times(times))
+        .await
+        .into_lua_err()
+}
+
+/**
+    Opens a file or directory so that its times can be set.
+
+    Setting times needs write access to the file on Windows, where directories
+    can only be opened using backup semantics, while on unix any open handle
+    that we own works, so files that are read-only and directories, which can
+    not be opened for writing there, fall back to being opened for reading.
+*/
+async fn open_for_set_times(path: &str) -> std::io::Result<fs::File> {
+    let is_dir = fs::metadata(path).await?.is_dir();
+    #[cfg(unix)]
+    {
+        if !is_dir {
+            match fs::OpenOptions::new().write(true).open(path).await {
+                Err(e) if e.kind() == IoErrorKind::PermissionDenied => {}
+                result => return result,
+            }
+        }
+        fs::File::open(path).await
+    }
+    #[cfg(windows)]
+    {
+        const FILE_FLAG_BACKUP_SEMANTICS: u32 = 0x0200_0000;
+        let mut options = fs::OpenOptions::new();
+        options.write(true);
+        if is_dir {
+            options.custom_flags(FILE_FLAG_BACKUP_SEMANTICS);
+        }
+        options.open(path).await
+    }
+}
+
+async fn fs_create_symlink(_: &Lua, (target, link): (String, String)) -> LuaResult<()> {
+    #[cfg(unix)]
+    {
+        fs::symlink(&target, &link).await.into_lua_err()
+    }
+    #[cfg(windows)]
+    {
+        // Windows needs to know if the link points to a file or a directory,
+        // and relative targets are resolved from the directory of the link
+        let link_dir = Path::new(&link).parent().unwrap_or(Path::new(""));
+        let is_dir = match fs::metadata(link_dir.join(&target)).await {
+            Ok(meta) => meta.is_dir(),
+            Err(_) => false,
+        };
+        if is_dir {
+            fs::symlink_dir(&target, &link).await.into_lua_err()
+        } else {
+            fs::symlink_file(&target, &link).await.into_lua_err()
+        }
+    }
+}
+
+async fn fs_create_hard_link(_: &Lua, (from, to): (String, String)) -> LuaResult<()> {
+    fs::hard_link(&from, &to).await.into_lua_err()
+}
+
+async fn fs_read_link(_: &Lua, path: String) -> LuaResult<String> {
+    let target = fs::read_link(&path).await.into_lua_err()?;
+    match target.to_str() {
+        Some(target_str) => Ok(target_str.to_owned()),
+        None => Err(LuaError::RuntimeError(format!(
+            "Link target could not be converted into a string: '{}'",
+            Path::new(&path).display()
+        ))),
+    }
+}
+
+async fn fs_lock(_: &Lua, (path, options): (String, FsLockOptions)) -> LuaResult<FsLock> {
+    blocking::unblock(move || FsLock::acquire(path, options.exclusive)).await
+}
+
+async fn fs_temp_dir(lua: &Lua, options: FsTempOptions) -> LuaResult<FsTempPath> {
+    let temp = blocking::unblock(move || FsTempPath::create_dir(options)).await?;
+    Ok(temp.remove_on_exit(lua))
+}
+
+async fn fs_temp_file(lua: &Lua, options: FsTempOptions) -> LuaResult<FsTempPath> {
+    let temp = blocking::unblock(move || FsTempPath::create_file(options)).await?;
+    Ok(temp.remove_on_exit(lua))
+}
@@ -1,5 +1,9 @@
+use std::time::SystemTime;
+
 use mlua::prelude::*;
 
+use crate::lune::builtins::datetime::DateTime;
+
 #[derive(Debug, Clone, Copy)]
 pub struct FsWriteOptions {
     pub(crate) overwrite: bool,
@@ -29,3 +33,181 @@ impl<'lua> FromLua<'lua> for FsWriteOptions {
         })
     }
 }
+
+#[derive(Debug, Clone, Copy)]
+pub struct FsWriteFileOptions {
+    pub(crate) atomic: bool,
+}
+
+impl<'lua> FromLua<'lua> for FsWriteFileOptions {
+    fn from_lua(value: LuaValue<'lua>, _: &'lua Lua) -> LuaResult<Self> {
+        Ok(match value {
+            LuaValue::Nil => Self { atomic: false },
+            LuaValue::Table(t) => {
+                let atomic: Option<bool> = t.get("atomic")?;
+                Self {
+                    atomic: atomic.unwrap_or(false),
+                }
+            }
+            _ => {
+                return Err(LuaError::FromLuaConversionError {
+                    from: value.type_name(),
+                    to: "FsWriteFileOptions",
+                    message: Some(format!(
+                        "Invalid write file options - expected table, got {}",
+                        value.type_name()
+                    )),
+                })
+            }
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct FsLockOptions {
+    pub(crate) exclusive: bool,
+}
+
+impl<'lua> FromLua<'lua> for FsLockOptions {
+    fn from_lua(value: LuaValue<'lua>, _: &'lua Lua) -> LuaResult<Self> {
+        Ok(match value {
+            LuaValue::Nil => Self { exclusive: true },
+            LuaValue::Boolean(b) => Self { exclusive: b },
+            LuaValue::Table(t) => {
+                let exclusive: Option<bool> = t.get("exclusive")?;
+                Self {
+                    exclusive: exclusive.unwrap_or(true),
+                }
+            }
+            _ => {
+                return Err(LuaError::FromLuaConversionError {
+                    from: value.type_name(),
+                    to: "FsLockOptions",
+                    message: Some(format!(
+                        "Invalid lock options - expected boolean or table, got {}",
+                        value.type_name()
+                    )),
+                })
+            }
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct FsPermissionsOptions {
+    pub(crate) read_only: Option<bool>,
+    pub(crate) mode: Option<u32>,
+}
+
+impl<'lua> FromLua<'lua> for FsPermissionsOptions {
+    fn from_lua(value: LuaValue<'lua>, _: &'lua Lua) -> LuaResult<Self> {
+        Ok(match value {
+            LuaValue::Integer(i) => Self {
+                read_only: None,
+                mode: Some(parse_mode(i as f64)?),
+            },
+            LuaValue::Number(n) => Self {
+                read_only: None,
+                mode: Some(parse_mode(n)?),
+            },
+            LuaValue::Table(t) => {
+                let read_only: Option<bool> = t.get("readOnly")?;
+                let mode: Option<f64> = t.get("mode")?;
+                Self {
+                    read_only,
+                    mode: mode.map(parse_mode).transpose()?,
+                }
+            }
+            _ => {
+                return Err(LuaError::FromLuaConversionError {
+                    from: value.type_name(),
+                    to: "FsPermissionsOptions",
+                    message: Some(format!(
+                        "Invalid permissions - expected number or table, got {}",
+                        value.type_name()
+                    )),
+                })
+            }
+        })
+    }
+}
+
+fn parse_mode(mode: f64) -> LuaResult<u32> {
+    if mode.fract() != 0.0 || !(0.0..=f64::from(0o7777)).contains(&mode) {
+        Err(LuaError::RuntimeError(format!(
+            "Invalid permissions mode - expected an integer between 0 and 0o7777, got {mode}"
+        )))
+    } else {
+        Ok(mode as u32)
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct FsTimesOptions {
+    pub(crate) accessed_at: Option<SystemTime>,
+    pub(crate) modified_at: Option<SystemTime>,
+}
+
+impl<'lua> FromLua<'lua> for FsTimesOptions {
+    fn from_lua(value: LuaValue<'lua>, _: &'lua Lua) -> LuaResult<Self> {
+        match value {
+            LuaValue::Table(t) => Ok(Self {
+                accessed_at: parse_time(t.get("accessedAt")?)?,
+                modified_at: parse_time(t.get("modifiedAt")?)?,
+            }),
+            _ => Err(LuaError::FromLuaConversionError {
+                from: value.type_name(),
+                to: "FsTimesOptions",
+                message: Some(format!(
+                    "Invalid times - expected table, got {}",
+                    value.type_name()
+                )),
+            }),
+        }
+    }
+}
+
+fn parse_time(value: LuaValue) -> LuaResult<Option<SystemTime>> {
+    match value {
+        LuaValue::Nil => Ok(None),
+        LuaValue::UserData(ud) => Ok(Some(ud.borrow::<DateTime>()?.to_system_time())),
+        LuaValue::Integer(i) => Ok(Some(
+            DateTime::from_unix_timestamp_float(i as f64)?.to_system_time(),
+        )),
+        LuaValue::Number(n) => Ok(Some(
+            DateTime::from_unix_timestamp_float(n)?.to_system_time(),
+        )),
+        _ => Err(LuaError::RuntimeError(format!(
+            "Invalid timestamp - expected DateTime or number, got {}",
+            value.type_name()
+        ))),
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct FsTempOptions {
+    pub(crate) prefix: Option<String>,
+    pub(crate) suffix: Option<String>,
+    pub(crate) dir: Option<String>,
+}
+
+impl<'lua> FromLua<'lua> for FsTempOptions {
+    fn from_lua(value: LuaValue<'lua>, _: &'lua Lua) -> LuaResult<Self> {
+        match value {
+            LuaValue::Nil => Ok(Self::default()),
+            LuaValue::Table(t) => Ok(Self {
+                prefix: t.get("prefix")?,
+                suffix: t.get("suffix")?,
+                dir: t.get("dir")?,
+            }),
+            _ => Err(LuaError::FromLuaConversionError {
+                from: value.type_name(),
+                to: "FsTempOptions",
+                message: Some(format!(
+                    "Invalid temp options - expected table, got {}",
+                    value.type_name()
+                )),
+            }),
+        }
+    }
+}
@@ -0,0 +1,157 @@
+use std::{
+    path::Path,
+    sync::{Arc, Mutex},
+};
+
+use mlua::prelude::*;
+use tempfile::{Builder as TempBuilder, TempDir, TempPath};
+
+use crate::lune::builtins::ProcessLifecycle;
+
+use super::options::FsTempOptions;
+
+#[derive(Debug)]
+enum FsTempInner {
+    Dir(TempDir),
+    File(TempPath),
+}
+
+/**
+    A temporary file or directory that is removed when it is dropped,
+    such as when it gets garbage collected or when the runtime exits,
+    or when the script exits using `process.exit`, see [`Self::remove_on_exit`].
+
+    Calling `keep` will disable automatic removal.
+*/
+#[derive(Debug)]
+pub struct FsTempPath {
+    path: String,
+    is_dir: bool,
+    inner: Arc<Mutex<Option<FsTempInner>>>,
+}
+
+impl FsTempPath {
+    fn builder(options: &FsTempOptions) -> TempBuilder<'_, '_> {
+        let mut builder = TempBuilder::new();
+        if let Some(prefix) = &options.prefix {
+            builder.prefix(prefix);
+        }
+        if let Some(suffix) = &options.suffix {
+            builder.suffix(suffix);
+        }
+        builder
+    }
+
+    /**
+        Creates a new temporary directory.
+
+        This will block the current thread and should be called
+        from a blocking context such as `blocking::unblock`.
+    */
+    pub fn create_dir(options: FsTempOptions) -> LuaResult<Self> {
+        let builder = Self::builder(&options);
+        let dir = match &options.dir {
+            Some(dir) => builder.tempdir_in(dir),
+            None => builder.tempdir(),
+        }
+        .into_lua_err()?;
+        Ok(Self {
+            path: path_to_string(dir.path())?,
+            is_dir: true,
+            inner: Arc::new(Mutex::new(Some(FsTempInner::Dir(dir)))),
+        })
+    }
+
+    /**
+        Creates a new, empty, temporary file.
+
+        This will block the current thread and should be called
+        from a blocking context such as `blocking::unblock`.
+    */
+    pub fn create_file(options: FsTempOptions) -> LuaResult<Self> {
+        let builder = Self::builder(&options);
+        let file = match &options.dir {
+            Some(dir) => builder.tempfile_in(dir),
+            None => builder.tempfile(),
+        }
+        .into_lua_err()?;
+        // NOTE: We only keep the path around here, the file
+        // handle is closed so that it can be freely used in Lua
+        let path = file.into_temp_path();
+        Ok(Self {
+            path: path_to_string(&path)?,
+            is_dir: false,
+            inner: Arc::new(Mutex::new(Some(FsTempInner::File(path)))),
+        })
+    }
+
+    /**
+        Makes sure that this temporary path is removed if the script exits using
+        `process.exit`, since the exiting thread may still be referencing it.
+    */
+    pub fn remove_on_exit(self, lua: &Lua) -> Self {
+        let inner = Arc::downgrade(&self.inner);
+        ProcessLifecycle::add_exit_cleanup(lua, move || {
+            if let Some(inner) = inner.upgrade() {
+                // NOTE: Dropping the temporary path is what removes it
+                drop(inner.lock().expect("Temp path lock was poisoned").take());
+            }
+        });
+        self
+    }
+
+    fn keep(&self) -> LuaResult<()> {
+        match self.take_inner() {
+            Some(FsTempInner::Dir(dir)) => {
+                let _ = dir.into_path();
+            }
+            Some(FsTempInner::File(path)) => {
+                path.keep().into_lua_err()?;
+            }
+            None => {}
+        }
+        Ok(())
+    }
+
+    fn remove(&self) -> LuaResult<()> {
+        match self.take_inner() {
+            Some(FsTempInner::Dir(dir)) => dir.close().into_lua_err(),
+            Some(FsTempInner::File(path)) => path.close().into_lua_err(),
+            None => Ok(()),
+        }
+    }
+
+    fn take_inner(&self) -> Option<FsTempInner> {
+        self.inner
+            .lock()
+            .expect("Temp path lock was poisoned")
+            .take()
+    }
+}
+
+impl LuaUserData for FsTempPath {
+    fn add_fields<'lua, F: LuaUserDataFields<'lua, Self>>(fields: &mut F) {
+        fields.add_meta_field(LuaMetaMethod::Type, "FsTempPath");
+        fields.add_field_method_get("path", |_, this| Ok(this.path.clone()));
+        fields.add_field_method_get("isDir", |_, this| Ok(this.is_dir));
+    }
+
+    fn add_methods<'lua, M: LuaUserDataMethods<'lua, Self>>(methods: &mut M) {
+        methods.add_method("keep", |_, this, ()| {
+            this.keep()?;
+            Ok(this.path.clone())
+        });
+        methods.add_method("remove", |_, this, ()| this.remove());
+        methods.add_meta_method(LuaMetaMethod::ToString, |_, this, ()| Ok(this.path.clone()));
+    }
+}
+
+fn path_to_string(path: &Path) -> LuaResult<String> {
+    match path.to_str() {
+        Some(path_str) => Ok(path_str.to_owned()),
+        None => Err(LuaError::RuntimeError(format!(
+            "Temporary path could not be converted into a string: '{}'",
+            path.display()
+        ))),
+    }
+}
@@ -0,0 +1,44 @@
+use mlua::prelude::*;
+
+use crate::lune::{coverage::Coverage, util::TableBuilder, CoverageFormat};
+
+pub fn create(lua: &Lua) -> LuaResult<LuaTable> {
+    TableBuilder::new(lua)?
+        .with_function("start", coverage_start)?
+        .with_function("stop", coverage_stop)?
+        .with_function("collect", coverage_collect)?
+        .with_function("report", coverage_report)?
+        .build_readonly()
+}
+
+fn coverage_start(lua: &Lua, level: Option<u8>) -> LuaResult<()> {
+    match level.unwrap_or(1) {
+        level @ (1 | 2) => {
+            Coverage::enable(lua, level);
+            Ok(())
+        }
+        level => Err(LuaError::RuntimeError(format!(
+            "Invalid coverage level - expected 1 or 2, got {level}"
+        ))),
+    }
+}
+
+fn coverage_stop(lua: &Lua, (): ()) -> LuaResult<()> {
+    Coverage::disable(lua);
+    Ok(())
+}
+
+fn coverage_collect(lua: &Lua, (): ()) -> LuaResult<LuaValue> {
+    match Coverage::get(lua) {
+        Some(coverage) => lua.to_value(&coverage.report(lua)?),
+        None => lua.create_table().map(LuaValue::Table),
+    }
+}
+
+fn coverage_report(lua: &Lua, format: CoverageFormat) -> LuaResult<String> {
+    let report = match Coverage::get(lua) {
+        Some(coverage) => coverage.report(lua)?,
+        None => Default::default(),
+    };
+    Ok(report.to_format(format))
+}
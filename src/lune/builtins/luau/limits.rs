@@ -0,0 +1,171 @@
+use std::{
+    cell::Cell,
+    rc::Rc,
+    time::{Duration, Instant},
+};
+
+use mlua::prelude::*;
+
+use crate::lune::util::interrupts::Interrupts;
+
+const LIMITS_WRAPPER: &str = r#"
+local enter, exit, fn = ...
+
+local function finish(id, ok, ...)
+	exit(id)
+	if not ok then
+		error((...), 0)
+	end
+	return ...
+end
+
+return function(...)
+	return finish(enter(), pcall(fn, ...))
+end
+"#;
+
+/**
+    Resource limits for functions loaded using `luau.load`.
+
+    Limits are checked using interrupts, which Luau calls at function calls
+    and loop iterations, and apply to the thread calling the function for as
+    long as the call is running. Exceeding a limit errors in the called
+    function, and this error may be caught by the caller.
+*/
+#[derive(Debug, Clone, Copy, Default)]
+pub struct LuauLoadLimits {
+    pub(crate) interrupts: Option<u64>,
+    pub(crate) time: Option<Duration>,
+    pub(crate) memory: Option<usize>,
+}
+
+impl LuauLoadLimits {
+    pub fn is_empty(&self) -> bool {
+        self.interrupts.is_none() && self.time.is_none() && self.memory.is_none()
+    }
+
+    /**
+        Wraps the given function, applying these limits to each call of it.
+    */
+    pub fn wrap<'lua>(
+        self,
+        lua: &'lua Lua,
+        debug_name: &str,
+        function: LuaFunction<'lua>,
+    ) -> LuaResult<LuaFunction<'lua>> {
+        let enter = lua.create_function(move |lua, ()| LimitsBudget::start(lua, self))?;
+        let exit = lua.create_function(|lua, id: usize| {
+            Interrupts::remove(lua, id);
+            Ok(())
+        })?;
+
+        // NOTE: The wrapper uses the same name as the loaded
+        // chunk, so that it shows up the same in debug info
+        lua.load(LIMITS_WRAPPER)
+            .set_name(debug_name)
+            .call((enter, exit, function))
+    }
+}
+
+/**
+    The resources used by a single call to a function with limits.
+*/
+struct LimitsBudget {
+    limits: LuauLoadLimits,
+    thread: LuaRegistryKey,
+    thread_ptr: usize,
+    stack_depth: usize,
+    started: Instant,
+    memory_base: usize,
+    interrupts: Cell<u64>,
+}
+
+impl LimitsBudget {
+    /**
+        Starts tracking resources used by the current thread, returning
+        the id of the interrupt handler that enforces the limits.
+    */
+    fn start(lua: &Lua, limits: LuauLoadLimits) -> LuaResult<usize> {
+        let thread = lua.current_thread();
+        let budget = Self {
+            limits,
+            thread_ptr: thread.to_pointer() as usize,
+            thread: lua.create_registry_value(thread)?,
+            // NOTE: The current stack contains the wrapper and this
+            // function, which is as deep as the wrapper itself goes
+            stack_depth: stack_depth(lua),
+            started: Instant::now(),
+            memory_base: lua.used_memory(),
+            interrupts: Cell::new(0),
+        };
+
+        let id = Rc::new(Cell::new(0));
+        let id_inner = Rc::clone(&id);
+        id.set(Interrupts::add(lua, move |lua| {
+            if lua.current_thread().to_pointer() as usize == budget.thread_ptr {
+                return budget.check(lua);
+            }
+            // NOTE: Threads that were cancelled while calling the function never
+            // finish the call, so we also need to stop tracking them here
+            let thread = lua.registry_value::<LuaThread>(&budget.thread)?;
+            if thread.status() != LuaThreadStatus::Resumable {
+                Interrupts::remove(lua, id_inner.get());
+            }
+            Ok(LuaVmState::Continue)
+        }));
+
+        Ok(id.get())
+    }
+
+    fn check(&self, lua: &Lua) -> LuaResult<LuaVmState> {
+        match self.exceeded(lua) {
+            // NOTE: The wrapper also calls functions after the loaded function has
+            // errored, and erroring there would skip the cleanup that it does
+            Some(message) if stack_depth(lua) > self.stack_depth => {
+                Err(LuaError::RuntimeError(message))
+            }
+            _ => Ok(LuaVmState::Continue),
+        }
+    }
+
+    fn exceeded(&self, lua: &Lua) -> Option<String> {
+        let interrupts = self.interrupts.get() + 1;
+        self.interrupts.set(interrupts);
+
+        // NOTE: Luau only interrupts at function calls and loop iterations,
+        // so this limit does not count the instructions ran in between
+        if let Some(limit) = self.limits.interrupts {
+            if interrupts > limit {
+                return Some(format!("Exceeded interrupt limit of {limit}"));
+            }
+        }
+
+        if let Some(limit) = self.limits.time {
+            if self.started.elapsed() > limit {
+                return Some(format!(
+                    "Exceeded time limit of {} seconds",
+                    limit.as_secs_f64()
+                ));
+            }
+        }
+
+        // NOTE: Memory used is tracked for the whole Luau VM, and also
+        // includes any garbage that has not yet been collected, so
+        // this limit is only an approximation of the memory used
+        if let Some(limit) = self.limits.memory {
+            if lua.used_memory().saturating_sub(self.memory_base) > limit {
+                return Some(format!("Exceeded memory limit of {limit} bytes"));
+            }
+        }
+
+        None
+    }
+}
+
+fn stack_depth(lua: &Lua) -> usize {
+    let mut depth = 0;
+    while lua.inspect_stack(depth).is_some() {
+        depth += 1;
+    }
+    depth
+}
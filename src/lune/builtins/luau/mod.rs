@@ -1,8 +1,13 @@
 use mlua::prelude::*;
 
-use crate::lune::util::TableBuilder;
+use crate::lune::{coverage::Coverage, debugger::Debugger, util::TableBuilder};
 
+mod coverage;
+mod limits;
 mod options;
+mod profiler;
+mod sandbox;
+
 use options::{LuauCompileOptions, LuauLoadOptions};
 
 const BYTECODE_ERROR_BYTE: u8 = 0;
@@ -11,6 +16,8 @@ pub fn create(lua: &Lua) -> LuaResult<LuaTable> {
     TableBuilder::new(lua)?
         .with_function("compile", compile_source)?
         .with_function("load", load_source)?
+        .with_value("coverage", coverage::create(lua)?)?
+        .with_value("profiler", profiler::create(lua)?)?
         .build_readonly()
 }
 
@@ -31,29 +38,54 @@ fn compile_source<'lua>(
 
 fn load_source<'lua>(
     lua: &'lua Lua,
-    (source, options): (LuaString<'lua>, LuauLoadOptions),
+    (source, options): (LuaString<'lua>, LuauLoadOptions<'lua>),
 ) -> LuaResult<LuaFunction<'lua>> {
-    let mut chunk = lua.load(source.as_bytes()).set_name(options.debug_name);
+    let mut chunk = lua.load(source.as_bytes()).set_name(&options.debug_name);
+
+    if let Some(environment) = sandbox::create_environment(lua, &options)? {
+        chunk = chunk.set_environment(environment);
+    }
 
-    if let Some(environment) = options.environment {
-        let environment_with_globals = lua.create_table()?;
+    // NOTE: Native code generation is toggled for the whole Lua state and only affects
+    // chunks loaded while it is toggled, so it is disabled only for this one load, using
+    // a guard that restores it even if loading fails. It is only ever disabled by an
+    // attached debugger, which needs it to stay that way for breakpoints and stepping
+    // to work, so native code is never enabled again while debugging
+    let function = {
+        let disable_native = Debugger::get(lua).is_none() && options.native == Some(false);
+        let _guard = disable_native.then(|| NativeCodegenDisabled::new(lua));
+        chunk.into_function()?
+    };
 
-        if let Some(meta) = environment.get_metatable() {
-            environment_with_globals.set_metatable(Some(meta));
-        }
+    Coverage::track(lua, &options.debug_name, &function)?;
+    Debugger::track(lua, &options.debug_name, &function)?;
+
+    if options.limits.is_empty() {
+        Ok(function)
+    } else {
+        options.limits.wrap(lua, &options.debug_name, function)
+    }
+}
 
-        for pair in lua.globals().pairs() {
-            let (key, value): (LuaValue, LuaValue) = pair?;
-            environment_with_globals.set(key, value)?;
-        }
+/**
+    Disables native code generation for the Lua state until dropped.
 
-        for pair in environment.pairs() {
-            let (key, value): (LuaValue, LuaValue) = pair?;
-            environment_with_globals.set(key, value)?;
-        }
+    Must only be created while native code generation is enabled,
+    since it is unconditionally enabled again once this is dropped.
+*/
+struct NativeCodegenDisabled<'lua> {
+    lua: &'lua Lua,
+}
 
-        chunk = chunk.set_environment(environment_with_globals);
+impl<'lua> NativeCodegenDisabled<'lua> {
+    fn new(lua: &'lua Lua) -> Self {
+        lua.enable_jit(false);
+        Self { lua }
     }
+}
 
-    chunk.into_function()
+impl Drop for NativeCodegenDisabled<'_> {
+    fn drop(&mut self) {
+        self.lua.enable_jit(true);
+    }
 }
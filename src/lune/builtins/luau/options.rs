@@ -1,20 +1,41 @@
+use std::time::Duration;
+
 use mlua::prelude::*;
 use mlua::Compiler as LuaCompiler;
 
+use crate::lune::builtins::LuneBuiltin;
+
+use super::limits::LuauLoadLimits;
+
 const DEFAULT_DEBUG_NAME: &str = "luau.load(...)";
 
 pub struct LuauCompileOptions {
     pub(crate) optimization_level: u8,
     pub(crate) coverage_level: u8,
     pub(crate) debug_level: u8,
+    pub(crate) vector_lib: Option<String>,
+    pub(crate) vector_ctor: Option<String>,
+    pub(crate) vector_type: Option<String>,
+    pub(crate) mutable_globals: Vec<String>,
 }
 
 impl LuauCompileOptions {
     pub fn into_compiler(self) -> LuaCompiler {
-        LuaCompiler::default()
+        let mut compiler = LuaCompiler::default()
             .set_optimization_level(self.optimization_level)
             .set_coverage_level(self.coverage_level)
             .set_debug_level(self.debug_level)
+            .set_mutable_globals(self.mutable_globals);
+        if let Some(vector_lib) = self.vector_lib {
+            compiler = compiler.set_vector_lib(vector_lib);
+        }
+        if let Some(vector_ctor) = self.vector_ctor {
+            compiler = compiler.set_vector_ctor(vector_ctor);
+        }
+        if let Some(vector_type) = self.vector_type {
+            compiler = compiler.set_vector_type(vector_type);
+        }
+        compiler
     }
 }
 
@@ -26,6 +47,10 @@ impl Default for LuauCompileOptions {
             optimization_level: 1,
             coverage_level: 0,
             debug_level: 1,
+            vector_lib: None,
+            vector_ctor: None,
+            vector_type: None,
+            mutable_globals: Vec::new(),
         }
     }
 }
@@ -57,6 +82,28 @@ impl<'lua> FromLua<'lua> for LuauCompileOptions {
                     options.debug_level = debug_level;
                 }
 
+                let get_name = |name: &'static str| -> LuaResult<Option<String>> {
+                    match t.get::<_, Option<String>>(name)? {
+                        Some(s) if s.is_empty() || s.contains('\0') => Err(LuaError::runtime(
+                            format!("'{name}' must be a non-empty string without null bytes"),
+                        )),
+                        s => Ok(s),
+                    }
+                };
+
+                options.vector_lib = get_name("vectorLib")?;
+                options.vector_ctor = get_name("vectorCtor")?;
+                options.vector_type = get_name("vectorType")?;
+                if options.vector_lib.is_some() && options.vector_ctor.is_none() {
+                    return Err(LuaError::runtime(
+                        "'vectorLib' must be used together with 'vectorCtor'",
+                    ));
+                }
+
+                if let Some(mutable_globals) = t.get::<_, Option<Vec<String>>>("mutableGlobals")? {
+                    options.mutable_globals = mutable_globals;
+                }
+
                 options
             }
             _ => {
@@ -76,6 +123,11 @@ impl<'lua> FromLua<'lua> for LuauCompileOptions {
 pub struct LuauLoadOptions<'lua> {
     pub(crate) debug_name: String,
     pub(crate) environment: Option<LuaTable<'lua>>,
+    pub(crate) sandbox: bool,
+    pub(crate) readonly_globals: bool,
+    pub(crate) builtins: Option<Vec<LuneBuiltin>>,
+    pub(crate) limits: LuauLoadLimits,
+    pub(crate) native: Option<bool>,
 }
 
 impl Default for LuauLoadOptions<'_> {
@@ -83,6 +135,11 @@ impl Default for LuauLoadOptions<'_> {
         Self {
             debug_name: DEFAULT_DEBUG_NAME.to_string(),
             environment: None,
+            sandbox: false,
+            readonly_globals: false,
+            builtins: None,
+            limits: LuauLoadLimits::default(),
+            native: None,
         }
     }
 }
@@ -102,11 +159,40 @@ impl<'lua> FromLua<'lua> for LuauLoadOptions<'lua> {
                     options.environment = Some(environment);
                 }
 
+                if let Some(sandbox) = t.get("sandbox")? {
+                    options.sandbox = sandbox;
+                }
+
+                if let Some(readonly_globals) = t.get("readonlyGlobals")? {
+                    options.readonly_globals = readonly_globals;
+                }
+
+                if let Some(builtins) = t.get::<_, Option<Vec<String>>>("builtins")? {
+                    options.builtins = Some(
+                        builtins
+                            .iter()
+                            .map(|name| name.parse().map_err(LuaError::runtime))
+                            .collect::<LuaResult<_>>()?,
+                    );
+                }
+
+                options.native = t.get("native")?;
+
+                if let Some(limit) = get_positive(&t, "interruptLimit")? {
+                    options.limits.interrupts = Some(limit as u64);
+                }
+                if let Some(limit) = get_positive(&t, "timeLimit")? {
+                    options.limits.time = Some(Duration::from_secs_f64(limit));
+                }
+                if let Some(limit) = get_positive(&t, "memoryLimit")? {
+                    options.limits.memory = Some(limit as usize);
+                }
+
                 options
             }
             LuaValue::String(s) => Self {
                 debug_name: s.to_string_lossy().to_string(),
-                environment: None,
+                ..Self::default()
             },
             _ => {
                 return Err(LuaError::FromLuaConversionError {
@@ -121,3 +207,13 @@ impl<'lua> FromLua<'lua> for LuauLoadOptions<'lua> {
         })
     }
 }
+
+fn get_positive(t: &LuaTable, name: &'static str) -> LuaResult<Option<f64>> {
+    match t.get::<_, Option<f64>>(name)? {
+        Some(n) if n > 0.0 && n.is_finite() => Ok(Some(n)),
+        Some(n) => Err(LuaError::RuntimeError(format!(
+            "Invalid option '{name}' - expected a positive number, got {n}"
+        ))),
+        None => Ok(None),
+    }
+}
@@ -0,0 +1,39 @@
+use mlua::prelude::*;
+
+use crate::lune::{
+    profiler::{Profiler, DEFAULT_SAMPLE_RATE},
+    util::TableBuilder,
+    ProfileFormat,
+};
+
+pub fn create(lua: &Lua) -> LuaResult<LuaTable> {
+    TableBuilder::new(lua)?
+        .with_function("start", profiler_start)?
+        .with_function("stop", profiler_stop)?
+        .with_function("report", profiler_report)?
+        .build_readonly()
+}
+
+fn profiler_start(lua: &Lua, sample_rate: Option<u32>) -> LuaResult<()> {
+    match sample_rate.unwrap_or(DEFAULT_SAMPLE_RATE) {
+        0 => Err(LuaError::RuntimeError(
+            "Invalid sample rate - expected a positive integer, got 0".to_string(),
+        )),
+        sample_rate => {
+            Profiler::start(lua, sample_rate);
+            Ok(())
+        }
+    }
+}
+
+fn profiler_stop(lua: &Lua, (): ()) -> LuaResult<()> {
+    Profiler::stop(lua);
+    Ok(())
+}
+
+fn profiler_report(lua: &Lua, format: ProfileFormat) -> LuaResult<String> {
+    let report = Profiler::get(lua)
+        .map(|profiler| profiler.report())
+        .unwrap_or_default();
+    Ok(report.to_format(format))
+}
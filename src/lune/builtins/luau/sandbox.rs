@@ -0,0 +1,187 @@
+use std::{collections::HashMap, ffi::c_void};
+
+use mlua::prelude::*;
+
+use crate::lune::{builtins::LuneBuiltin, globals::require_builtin, util::TableBuilder};
+
+use super::options::LuauLoadOptions;
+
+// NOTE: These functions can get and set the environments of functions
+// outside of the sandbox, such as the function that called the chunk
+const SANDBOX_REMOVED_GLOBALS: &[&str] = &["getfenv", "setfenv"];
+
+// NOTE: The metatable for strings is shared by all chunks, and its __index is
+// the string library, so sandboxed chunks are given a read-only copy instead
+const SANDBOX_GETMETATABLE_IMPL_LUA: &str = r#"
+local getmetatable, stringMetatable = ...
+return function(value)
+    if type(value) == "string" then
+        return stringMetatable
+    end
+    return getmetatable(value)
+end
+"#;
+
+/**
+    Creates the environment for a chunk loaded using `luau.load`, if the
+    given options need one other than the default global environment.
+*/
+pub fn create_environment<'lua>(
+    lua: &'lua Lua,
+    options: &LuauLoadOptions<'lua>,
+) -> LuaResult<Option<LuaTable<'lua>>> {
+    if options.environment.is_none()
+        && options.builtins.is_none()
+        && !options.sandbox
+        && !options.readonly_globals
+    {
+        return Ok(None);
+    }
+
+    let environment = lua.create_table()?;
+
+    if options.sandbox {
+        let mut copies = SandboxCopies::default();
+        for pair in lua.globals().pairs() {
+            let (key, value): (LuaValue, LuaValue) = pair?;
+            if let Some(value) = sandbox_global(lua, &mut copies, &key, value)? {
+                environment.set(key, value)?;
+            }
+        }
+        environment.set("_G", lua.create_table()?)?;
+        environment.set("getmetatable", create_getmetatable(lua, &mut copies)?)?;
+    } else {
+        for pair in lua.globals().pairs() {
+            let (key, value): (LuaValue, LuaValue) = pair?;
+            environment.set(key, value)?;
+        }
+    }
+
+    if let Some(custom) = &options.environment {
+        if let Some(meta) = custom.get_metatable() {
+            environment.set_metatable(Some(meta));
+        }
+        for pair in custom.clone().pairs() {
+            let (key, value): (LuaValue, LuaValue) = pair?;
+            environment.set(key, value)?;
+        }
+    }
+
+    // NOTE: This is set after any custom environment, since allowing the
+    // environment to override it would make the list of builtins pointless
+    if let Some(builtins) = &options.builtins {
+        environment.set("require", create_require(lua, builtins.clone())?)?;
+    }
+
+    if options.readonly_globals {
+        environment.set_readonly(true);
+    }
+
+    Ok(Some(environment))
+}
+
+/**
+    Read-only copies of tables given to sandboxed chunks.
+
+    Nested tables are copied as well, and tables that are seen more than once, such
+    as a library that is also reachable through a metatable, share the same copy.
+*/
+#[derive(Default)]
+struct SandboxCopies<'lua> {
+    copies: HashMap<*const c_void, LuaTable<'lua>>,
+}
+
+impl<'lua> SandboxCopies<'lua> {
+    fn copy(&mut self, lua: &'lua Lua, table: LuaTable<'lua>) -> LuaResult<LuaTable<'lua>> {
+        if let Some(copy) = self.copies.get(&table.to_pointer()) {
+            return Ok(copy.clone());
+        }
+
+        let copy = lua.create_table()?;
+        self.copies.insert(table.to_pointer(), copy.clone());
+        for pair in table.pairs() {
+            let (key, value): (LuaValue, LuaValue) = pair?;
+            match value {
+                LuaValue::Table(nested) => copy.set(key, self.copy(lua, nested)?)?,
+                value => copy.set(key, value)?,
+            }
+        }
+
+        copy.set_readonly(true);
+        Ok(copy)
+    }
+}
+
+/**
+    Gets the value of a global to give to a sandboxed chunk, if any.
+
+    Library tables are given as deep read-only copies, so that
+    sandboxed chunks can not modify them for any other code.
+*/
+fn sandbox_global<'lua>(
+    lua: &'lua Lua,
+    copies: &mut SandboxCopies<'lua>,
+    key: &LuaValue<'lua>,
+    value: LuaValue<'lua>,
+) -> LuaResult<Option<LuaValue<'lua>>> {
+    if let LuaValue::String(name) = key {
+        let name = name.to_str().unwrap_or_default();
+        // NOTE: The global table is replaced with an empty one
+        // afterwards, so there is no need to copy all of it here
+        if name == "_G" || SANDBOX_REMOVED_GLOBALS.contains(&name) {
+            return Ok(None);
+        }
+    }
+    match value {
+        LuaValue::Table(library) => Ok(Some(LuaValue::Table(copies.copy(lua, library)?))),
+        value => Ok(Some(value)),
+    }
+}
+
+/**
+    Creates a `getmetatable` function for sandboxed chunks, which
+    gives back a read-only copy of the metatable for strings.
+*/
+fn create_getmetatable<'lua>(
+    lua: &'lua Lua,
+    copies: &mut SandboxCopies<'lua>,
+) -> LuaResult<LuaFunction<'lua>> {
+    let getmetatable = lua.globals().get::<_, LuaFunction>("getmetatable")?;
+    let string_metatable = match getmetatable.call::<_, Option<LuaTable>>("")? {
+        Some(metatable) => Some(copies.copy(lua, metatable)?),
+        None => None,
+    };
+    let env = TableBuilder::new(lua)?
+        .with_value("type", lua.globals().get::<_, LuaFunction>("type")?)?
+        .build_readonly()?;
+    lua.load(SANDBOX_GETMETATABLE_IMPL_LUA)
+        .set_name("=__luau_sandbox")
+        .set_environment(env)
+        .call((getmetatable, string_metatable))
+}
+
+/**
+    Creates a `require` function that may only require the given builtins.
+*/
+fn create_require(lua: &Lua, builtins: Vec<LuneBuiltin>) -> LuaResult<LuaFunction> {
+    lua.create_function(move |lua, path: String| {
+        let builtin = path
+            .strip_prefix("@luneweb/")
+            .and_then(|name| name.to_ascii_lowercase().parse::<LuneBuiltin>().ok())
+            .filter(|builtin| builtins.contains(builtin));
+        match builtin {
+            Some(builtin) => require_builtin(lua, builtin.name()),
+            None if builtins.is_empty() => Err(LuaError::RuntimeError(format!(
+                "Requiring '{path}' is not allowed - no builtins may be required"
+            ))),
+            None => Err(LuaError::RuntimeError(format!(
+                "Requiring '{path}' is not allowed - expected one of {}",
+                builtins
+                    .iter()
+                    .map(|builtin| format!("'@luneweb/{}'", builtin.name()))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ))),
+        }
+    })
+}
@@ -2,31 +2,43 @@ use std::str::FromStr;
 
 use mlua::prelude::*;
 
+mod archive;
+mod crypto;
 mod datetime;
 mod fs;
 mod luau;
 mod net;
+mod path;
 mod process;
 mod regex;
 mod serde;
 mod stdio;
+mod sync;
 mod task;
+mod worker;
 mod wry;
 
 #[cfg(feature = "roblox")]
 mod roblox;
 
+pub use process::{ProcessLifecycle, PROCESS_START_TIME};
+
 #[derive(Debug, Clone, Copy, Hash, PartialEq, Eq)]
 pub enum LuneBuiltin {
+    Archive,
+    Crypto,
     DateTime,
     Fs,
     Luau,
     Net,
+    Path,
     Task,
     Process,
     Regex,
     Serde,
     Stdio,
+    Sync,
+    Worker,
     Wry,
     #[cfg(feature = "roblox")]
     Roblox,
@@ -35,15 +47,20 @@ pub enum LuneBuiltin {
 impl LuneBuiltin {
     pub fn name(&self) -> &'static str {
         match self {
+            Self::Archive => "archive",
+            Self::Crypto => "crypto",
             Self::DateTime => "datetime",
             Self::Fs => "fs",
             Self::Luau => "luau",
             Self::Net => "net",
+            Self::Path => "path",
             Self::Task => "task",
             Self::Process => "process",
             Self::Regex => "regex",
             Self::Serde => "serde",
             Self::Stdio => "stdio",
+            Self::Sync => "sync",
+            Self::Worker => "worker",
             Self::Wry => "wry",
             #[cfg(feature = "roblox")]
             Self::Roblox => "roblox",
@@ -52,15 +69,20 @@ impl LuneBuiltin {
 
     pub fn create<'lua>(&self, lua: &'lua Lua) -> LuaResult<LuaMultiValue<'lua>> {
         let res = match self {
+            Self::Archive => archive::create(lua),
+            Self::Crypto => crypto::create(lua),
             Self::DateTime => datetime::create(lua),
             Self::Fs => fs::create(lua),
             Self::Luau => luau::create(lua),
             Self::Net => net::create(lua),
+            Self::Path => path::create(lua),
             Self::Task => task::create(lua),
             Self::Process => process::create(lua),
             Self::Regex => regex::create(lua),
             Self::Serde => serde::create(lua),
             Self::Stdio => stdio::create(lua),
+            Self::Sync => sync::create(lua),
+            Self::Worker => worker::create(lua),
             Self::Wry => wry::create(lua),
             #[cfg(feature = "roblox")]
             Self::Roblox => roblox::create(lua),
@@ -79,15 +101,20 @@ impl FromStr for LuneBuiltin {
     type Err = String;
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         match s.trim().to_ascii_lowercase().as_str() {
+            "archive" => Ok(Self::Archive),
+            "crypto" => Ok(Self::Crypto),
             "datetime" => Ok(Self::DateTime),
             "fs" => Ok(Self::Fs),
             "luau" => Ok(Self::Luau),
             "net" => Ok(Self::Net),
+            "path" => Ok(Self::Path),
             "task" => Ok(Self::Task),
             "process" => Ok(Self::Process),
             "regex" => Ok(Self::Regex),
             "serde" => Ok(Self::Serde),
             "stdio" => Ok(Self::Stdio),
+            "sync" => Ok(Self::Sync),
+            "worker" => Ok(Self::Worker),
             "wry" => Ok(Self::Wry),
             #[cfg(feature = "roblox")]
             "roblox" => Ok(Self::Roblox),
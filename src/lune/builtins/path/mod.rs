@@ -0,0 +1,109 @@
+use std::path::{Component, Path, PathBuf, MAIN_SEPARATOR_STR};
+
+use mlua::{prelude::*, Variadic};
+use path_clean::PathClean;
+
+use crate::lune::util::{
+    paths::{current_working_dir, make_absolute_and_clean_from, make_relative},
+    TableBuilder,
+};
+
+pub fn create(lua: &Lua) -> LuaResult<LuaTable> {
+    TableBuilder::new(lua)?
+        .with_value("separator", MAIN_SEPARATOR_STR)?
+        .with_function("join", path_join)?
+        .with_function("normalize", path_normalize)?
+        .with_function("absolute", path_absolute)?
+        .with_function("relative", path_relative)?
+        .with_function("dirname", path_dirname)?
+        .with_function("basename", path_basename)?
+        .with_function("extension", path_extension)?
+        .with_function("split", path_split)?
+        .with_function("isAbsolute", path_is_absolute)?
+        .build_readonly()
+}
+
+fn path_to_string(path: impl AsRef<Path>) -> String {
+    path.as_ref().to_string_lossy().to_string()
+}
+
+fn path_join(_: &Lua, parts: Variadic<String>) -> LuaResult<String> {
+    let mut path = PathBuf::new();
+    for part in parts.iter() {
+        path.push(part);
+    }
+    Ok(path_to_string(path.clean()))
+}
+
+fn path_normalize(_: &Lua, path: String) -> LuaResult<String> {
+    Ok(path_to_string(PathBuf::from(path).clean()))
+}
+
+fn path_absolute(_: &Lua, path: String) -> LuaResult<String> {
+    Ok(path_to_string(make_absolute_and_clean_from(
+        current_working_dir(),
+        path,
+    )))
+}
+
+fn path_relative(_: &Lua, (from, to): (String, String)) -> LuaResult<String> {
+    let cwd = current_working_dir();
+    let from = make_absolute_and_clean_from(&cwd, from);
+    let to = make_absolute_and_clean_from(&cwd, to);
+    let relative = make_relative(&to, &from).ok_or_else(|| {
+        LuaError::RuntimeError(format!(
+            "No relative path exists from '{}' to '{}'",
+            from.display(),
+            to.display()
+        ))
+    })?;
+    // NOTE: An empty relative path means both paths are
+    // the same, which we represent as the current directory
+    if relative.as_os_str().is_empty() {
+        Ok(".".to_string())
+    } else {
+        Ok(path_to_string(relative))
+    }
+}
+
+fn path_dirname(_: &Lua, path: String) -> LuaResult<String> {
+    let path = PathBuf::from(path);
+    match path.parent() {
+        Some(parent) if parent.as_os_str().is_empty() => Ok(".".to_string()),
+        Some(parent) => Ok(path_to_string(parent)),
+        None => Ok(path_to_string(path)),
+    }
+}
+
+fn path_basename(_: &Lua, (path, suffix): (String, Option<String>)) -> LuaResult<Option<String>> {
+    let path = PathBuf::from(path);
+    Ok(path.file_name().map(|name| {
+        let name = name.to_string_lossy();
+        match suffix.as_deref() {
+            Some(suffix) if name != suffix => {
+                name.strip_suffix(suffix).unwrap_or(&name).to_string()
+            }
+            _ => name.to_string(),
+        }
+    }))
+}
+
+fn path_extension(_: &Lua, path: String) -> LuaResult<Option<String>> {
+    Ok(PathBuf::from(path)
+        .extension()
+        .map(|ext| ext.to_string_lossy().to_string()))
+}
+
+fn path_split(_: &Lua, path: String) -> LuaResult<Vec<String>> {
+    Ok(PathBuf::from(path)
+        .components()
+        .map(|component| match component {
+            Component::RootDir => MAIN_SEPARATOR_STR.to_string(),
+            component => component.as_os_str().to_string_lossy().to_string(),
+        })
+        .collect())
+}
+
+fn path_is_absolute(_: &Lua, path: String) -> LuaResult<bool> {
+    Ok(PathBuf::from(path).is_absolute())
+}
@@ -0,0 +1,164 @@
+use std::{future, sync::Arc};
+
+use mlua::prelude::*;
+use tokio::{
+    process::Child,
+    sync::{mpsc, oneshot, watch},
+    task, time,
+};
+
+use super::{
+    options::ProcessKillOptions,
+    signal::ProcessSignal,
+    status::ProcessExitStatus,
+    stream::{ChildProcessReader, ChildProcessWriter},
+    terminate::{signal_child, terminate_child},
+};
+
+type KillRequest = (Option<ProcessSignal>, oneshot::Sender<LuaResult<()>>);
+
+/**
+    A handle to a running child process, with streams for its
+    standard input & output that can be used while it is running.
+
+    The child process is owned by a background task that waits for it
+    to exit, and any signals sent to the process are forwarded to that
+    task, which guarantees that we never signal an already reaped process.
+
+    The handle and its streams all share a [`ChildProcessGuard`], once the
+    last one of those is dropped the background task kills the process,
+    and then keeps waiting for it to exit so that it is always reaped.
+*/
+pub struct ChildProcess {
+    pid: Option<u32>,
+    stdin: Option<ChildProcessWriter>,
+    stdout: Option<ChildProcessReader>,
+    stderr: Option<ChildProcessReader>,
+    status_rx: watch::Receiver<Option<LuaResult<ProcessExitStatus>>>,
+    kill_tx: mpsc::UnboundedSender<KillRequest>,
+    _guard: ChildProcessGuard,
+}
+
+/**
+    Keeps a child process alive while any handle or stream for it exists.
+*/
+#[derive(Clone)]
+pub struct ChildProcessGuard {
+    _dropped_tx: Arc<oneshot::Sender<()>>,
+}
+
+impl ChildProcess {
+    pub fn new(mut child: Child, kill_options: ProcessKillOptions) -> Self {
+        let (dropped_tx, dropped_rx) = oneshot::channel();
+        let guard = ChildProcessGuard {
+            _dropped_tx: Arc::new(dropped_tx),
+        };
+
+        let pid = child.id();
+        let stdin = child
+            .stdin
+            .take()
+            .map(|stdin| ChildProcessWriter::new(stdin, guard.clone()));
+        let stdout = child
+            .stdout
+            .take()
+            .map(|stdout| ChildProcessReader::new(stdout, guard.clone()));
+        let stderr = child
+            .stderr
+            .take()
+            .map(|stderr| ChildProcessReader::new(stderr, guard.clone()));
+
+        let (status_tx, status_rx) = watch::channel(None);
+        let (kill_tx, kill_rx) = mpsc::unbounded_channel();
+        task::spawn(watch_child(
+            child,
+            kill_options,
+            status_tx,
+            kill_rx,
+            dropped_rx,
+        ));
+
+        Self {
+            pid,
+            stdin,
+            stdout,
+            stderr,
+            status_rx,
+            kill_tx,
+            _guard: guard,
+        }
+    }
+
+    pub fn stdin(&self) -> Option<&ChildProcessWriter> {
+        self.stdin.as_ref()
+    }
+
+    pub async fn status(&self) -> LuaResult<ProcessExitStatus> {
+        let mut status_rx = self.status_rx.clone();
+        let status = status_rx.wait_for(Option::is_some).await.into_lua_err()?;
+        status
+            .clone()
+            .expect("status should be set after waiting for it")
+    }
+
+    pub async fn kill(&self, signal: Option<ProcessSignal>) -> LuaResult<()> {
+        let (reply_tx, reply_rx) = oneshot::channel();
+        if self.kill_tx.send((signal, reply_tx)).is_err() {
+            // The process has already exited, there is nothing to kill
+            return Ok(());
+        }
+        reply_rx.await.unwrap_or(Ok(()))
+    }
+}
+
+async fn watch_child(
+    mut child: Child,
+    kill_options: ProcessKillOptions,
+    status_tx: watch::Sender<Option<LuaResult<ProcessExitStatus>>>,
+    mut kill_rx: mpsc::UnboundedReceiver<KillRequest>,
+    mut dropped_rx: oneshot::Receiver<()>,
+) {
+    let deadline = async move {
+        match kill_options.timeout {
+            Some(timeout) => time::sleep(timeout).await,
+            None => future::pending().await,
+        }
+    };
+    tokio::pin!(deadline);
+
+    let mut dropped = false;
+    let status = loop {
+        tokio::select! {
+            status = child.wait() => break status.map(ProcessExitStatus::from).into_lua_err(),
+            Some((signal, reply_tx)) = kill_rx.recv() => {
+                reply_tx.send(signal_child(&mut child, signal, kill_options.group)).ok();
+            }
+            () = &mut deadline => break terminate_child(&mut child, kill_options).await,
+            _ = &mut dropped_rx, if !dropped => {
+                // NOTE: Nothing can interact with the process anymore, so it
+                // gets killed here, and reaped once it exits in the next loop
+                dropped = true;
+                signal_child(&mut child, None, kill_options.group).ok();
+            }
+        }
+    };
+    status_tx.send_replace(Some(status));
+}
+
+impl LuaUserData for ChildProcess {
+    fn add_fields<'lua, F: LuaUserDataFields<'lua, Self>>(fields: &mut F) {
+        fields.add_meta_field(LuaMetaMethod::Type, "ChildProcess");
+        fields.add_field_method_get("pid", |_, this| Ok(this.pid));
+        fields.add_field_method_get("stdin", |_, this| Ok(this.stdin.clone()));
+        fields.add_field_method_get("stdout", |_, this| Ok(this.stdout.clone()));
+        fields.add_field_method_get("stderr", |_, this| Ok(this.stderr.clone()));
+    }
+
+    fn add_methods<'lua, M: LuaUserDataMethods<'lua, Self>>(methods: &mut M) {
+        methods.add_async_method(
+            "kill",
+            |_, this, signal: Option<ProcessSignal>| async move { this.kill(signal).await },
+        );
+        methods.add_async_method("status", |_, this, ()| async move { this.status().await });
+    }
+}
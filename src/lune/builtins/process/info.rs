@@ -0,0 +1,140 @@
+use std::{
+    env,
+    time::{Duration, Instant},
+};
+
+use mlua::prelude::*;
+use once_cell::sync::Lazy;
+use sysinfo::{Pid, ProcessRefreshKind, System};
+
+/**
+    The time at which the current process started.
+
+    Forced when creating a new runtime, which happens as early as possible.
+*/
+pub static PROCESS_START_TIME: Lazy<Instant> = Lazy::new(Instant::now);
+
+/**
+    Gets the id of the parent process of the current process, if it has one.
+*/
+pub fn parent_pid() -> Option<u32> {
+    #[cfg(unix)]
+    {
+        Some(std::os::unix::process::parent_id())
+    }
+    #[cfg(not(unix))]
+    {
+        let pid = Pid::from_u32(std::process::id());
+        let mut system = System::new();
+        system.refresh_process_specifics(pid, ProcessRefreshKind::new());
+        system
+            .process(pid)
+            .and_then(|process| process.parent())
+            .map(|parent| parent.as_u32())
+    }
+}
+
+/**
+    Gets the path to the executable of the current process.
+*/
+pub fn exec_path() -> LuaResult<String> {
+    let path = env::current_exe().map_err(|e| {
+        LuaError::RuntimeError(format!("Failed to get path to current executable - {e}"))
+    })?;
+    Ok(path.to_string_lossy().to_string())
+}
+
+pub fn host_name() -> Option<String> {
+    System::host_name()
+}
+
+pub fn user_name() -> String {
+    whoami::username()
+}
+
+pub fn process_uptime(_: &Lua, _: ()) -> LuaResult<f64> {
+    Ok(PROCESS_START_TIME.elapsed().as_secs_f64())
+}
+
+pub fn process_memory_usage<'lua>(lua: &'lua Lua, _: ()) -> LuaResult<LuaTable<'lua>> {
+    let pid = Pid::from_u32(std::process::id());
+    let mut system = System::new();
+    system.refresh_process_specifics(pid, ProcessRefreshKind::new().with_memory());
+    let process = system
+        .process(pid)
+        .ok_or_else(|| LuaError::runtime("Failed to get memory usage of current process"))?;
+
+    let tab = lua.create_table_with_capacity(0, 2)?;
+    tab.set("resident", process.memory())?;
+    tab.set("virtual", process.virtual_memory())?;
+    tab.set_readonly(true);
+    Ok(tab)
+}
+
+pub fn process_cpu_usage<'lua>(lua: &'lua Lua, _: ()) -> LuaResult<LuaTable<'lua>> {
+    let (user, system) = cpu_times()?;
+
+    let tab = lua.create_table_with_capacity(0, 2)?;
+    tab.set("user", user.as_secs_f64())?;
+    tab.set("system", system.as_secs_f64())?;
+    tab.set_readonly(true);
+    Ok(tab)
+}
+
+/**
+    Gets the total user & system CPU time used by the current process.
+*/
+#[cfg(unix)]
+fn cpu_times() -> LuaResult<(Duration, Duration)> {
+    let to_duration =
+        |tv: libc::timeval| Duration::new(tv.tv_sec as u64, tv.tv_usec as u32 * 1_000);
+
+    // SAFETY: getrusage only writes to the struct we give it, which is zeroed
+    let usage = unsafe {
+        let mut usage: libc::rusage = std::mem::zeroed();
+        if libc::getrusage(libc::RUSAGE_SELF, &mut usage) != 0 {
+            return Err(std::io::Error::last_os_error().into_lua_err());
+        }
+        usage
+    };
+
+    Ok((to_duration(usage.ru_utime), to_duration(usage.ru_stime)))
+}
+
+#[cfg(windows)]
+fn cpu_times() -> LuaResult<(Duration, Duration)> {
+    use winapi::shared::minwindef::FILETIME;
+    use winapi::um::processthreadsapi::{GetCurrentProcess, GetProcessTimes};
+
+    // NOTE: File times are given in 100 nanosecond intervals
+    let to_duration = |ft: FILETIME| {
+        let intervals = (u64::from(ft.dwHighDateTime) << 32) | u64::from(ft.dwLowDateTime);
+        Duration::from_nanos(intervals * 100)
+    };
+
+    // SAFETY: GetProcessTimes only writes to the structs we give it, which are zeroed
+    unsafe {
+        let mut creation: FILETIME = std::mem::zeroed();
+        let mut exit: FILETIME = std::mem::zeroed();
+        let mut kernel: FILETIME = std::mem::zeroed();
+        let mut user: FILETIME = std::mem::zeroed();
+        if GetProcessTimes(
+            GetCurrentProcess(),
+            &mut creation,
+            &mut exit,
+            &mut kernel,
+            &mut user,
+        ) == 0
+        {
+            return Err(std::io::Error::last_os_error().into_lua_err());
+        }
+        Ok((to_duration(user), to_duration(kernel)))
+    }
+}
+
+#[cfg(not(any(unix, windows)))]
+fn cpu_times() -> LuaResult<(Duration, Duration)> {
+    Err(LuaError::runtime(
+        "Getting CPU usage is not supported on this platform",
+    ))
+}
@@ -0,0 +1,423 @@
+use std::{
+    cell::{Cell, RefCell},
+    collections::HashMap,
+    process::ExitCode,
+    rc::{Rc, Weak},
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+};
+
+use mlua::prelude::*;
+use mlua_luau_scheduler::{LuaSchedulerExt, LuaSpawnExt};
+use tokio::sync::{mpsc, watch};
+
+use super::signal::ProcessSignal;
+
+const EXIT_IMPL_LUA: &str = r"
+exit(...)
+yield()
+";
+
+type Handlers = Vec<(usize, LuaRegistryKey)>;
+type ExitCleanup = Box<dyn FnOnce()>;
+
+/**
+    Signal handlers connected for a single signal.
+
+    The number of connected handlers is shared with the background task
+    that listens for the signal, so that it knows when to fall back to
+    the default behavior of the signal, such as terminating the process.
+*/
+struct SignalHandlers {
+    connected: Arc<AtomicUsize>,
+    handlers: Handlers,
+}
+
+/**
+    Lifecycle state for the current process - exit hooks, signal handlers,
+    cleanups for `process.exit`, and the exit code set using it, if any.
+
+    Stored in Lua app data, and shared with the runtime, which
+    runs any exit hooks once the main scheduler has finished.
+*/
+pub struct ProcessLifecycle {
+    next_id: Cell<usize>,
+    exit_code: Cell<Option<u8>>,
+    exit_hooks: RefCell<Handlers>,
+    exit_cleanups: RefCell<Vec<ExitCleanup>>,
+    signals: RefCell<HashMap<ProcessSignal, SignalHandlers>>,
+    signal_tx: mpsc::UnboundedSender<ProcessSignal>,
+    signal_rx: RefCell<Option<mpsc::UnboundedReceiver<ProcessSignal>>>,
+    connected_tx: watch::Sender<usize>,
+}
+
+impl ProcessLifecycle {
+    fn new() -> Self {
+        let (signal_tx, signal_rx) = mpsc::unbounded_channel();
+        let (connected_tx, _) = watch::channel(0);
+        Self {
+            next_id: Cell::new(0),
+            exit_code: Cell::new(None),
+            exit_hooks: RefCell::new(Vec::new()),
+            exit_cleanups: RefCell::new(Vec::new()),
+            signals: RefCell::new(HashMap::new()),
+            signal_tx,
+            signal_rx: RefCell::new(Some(signal_rx)),
+            connected_tx,
+        }
+    }
+
+    /**
+        Gets the lifecycle state for the given Lua state, if any has been created.
+    */
+    pub fn get(lua: &Lua) -> Option<Rc<Self>> {
+        lua.app_data_ref::<Rc<Self>>().map(|this| Rc::clone(&this))
+    }
+
+    fn get_or_create(lua: &Lua) -> Rc<Self> {
+        if let Some(this) = Self::get(lua) {
+            return this;
+        }
+        let this = Rc::new(Self::new());
+        lua.set_app_data(Rc::clone(&this));
+        this
+    }
+
+    fn next_id(&self) -> usize {
+        let id = self.next_id.get();
+        self.next_id.set(id + 1);
+        id
+    }
+
+    /**
+        Takes the exit code set using `process.exit`, if any.
+    */
+    pub fn take_exit_code(&self) -> Option<u8> {
+        self.exit_code.take()
+    }
+
+    /**
+        Takes all currently connected exit hooks, in the order they were connected.
+    */
+    pub fn take_exit_hooks<'lua>(&self, lua: &'lua Lua) -> LuaResult<Vec<LuaFunction<'lua>>> {
+        self.exit_hooks
+            .take()
+            .into_iter()
+            .map(|(_, key)| lua.registry_value(&key))
+            .collect()
+    }
+
+    /**
+        Adds a cleanup function to call if the script exits using `process.exit`.
+
+        Exiting leaves the calling thread suspended, which keeps anything it references
+        alive, so resources that are normally cleaned up when dropped should use this.
+    */
+    pub fn add_exit_cleanup(lua: &Lua, cleanup: impl FnOnce() + 'static) {
+        Self::get_or_create(lua)
+            .exit_cleanups
+            .borrow_mut()
+            .push(Box::new(cleanup));
+    }
+
+    /**
+        Calls and removes all cleanup functions, in the order they were added.
+    */
+    pub fn run_exit_cleanups(&self) {
+        for cleanup in self.exit_cleanups.take() {
+            cleanup();
+        }
+    }
+
+    fn connect_exit(
+        self: &Rc<Self>,
+        lua: &Lua,
+        callback: LuaFunction,
+    ) -> LuaResult<ProcessConnection> {
+        let id = self.next_id();
+        let key = lua.create_registry_value(callback)?;
+        self.exit_hooks.borrow_mut().push((id, key));
+        Ok(ProcessConnection::new(self, id, None))
+    }
+
+    fn connect_signal(
+        self: &Rc<Self>,
+        lua: &Lua,
+        signal: ProcessSignal,
+        callback: LuaFunction,
+    ) -> LuaResult<ProcessConnection> {
+        let id = self.next_id();
+        let key = lua.create_registry_value(callback)?;
+
+        let mut signals = self.signals.borrow_mut();
+        let handlers = match signals.get_mut(&signal) {
+            Some(handlers) => handlers,
+            None => {
+                let connected = Arc::new(AtomicUsize::new(0));
+                listen_for_signal(signal, Arc::clone(&connected), self.signal_tx.clone())?;
+                signals.entry(signal).or_insert(SignalHandlers {
+                    connected,
+                    handlers: Vec::new(),
+                })
+            }
+        };
+        handlers.handlers.push((id, key));
+        handlers.connected.fetch_add(1, Ordering::SeqCst);
+        drop(signals);
+
+        self.connected_tx.send_modify(|connected| *connected += 1);
+        self.start_dispatching(lua);
+
+        Ok(ProcessConnection::new(self, id, Some(signal)))
+    }
+
+    fn disconnect(&self, id: usize, signal: Option<ProcessSignal>) {
+        match signal {
+            None => self.exit_hooks.borrow_mut().retain(|(i, _)| *i != id),
+            Some(signal) => {
+                let mut signals = self.signals.borrow_mut();
+                if let Some(handlers) = signals.get_mut(&signal) {
+                    let before = handlers.handlers.len();
+                    handlers.handlers.retain(|(i, _)| *i != id);
+                    if handlers.handlers.len() < before {
+                        handlers.connected.fetch_sub(1, Ordering::SeqCst);
+                        self.connected_tx.send_modify(|connected| *connected -= 1);
+                    }
+                }
+            }
+        }
+    }
+
+    /**
+        Starts dispatching received signals to their handlers, unless already started.
+
+        Dispatching stops once all signal handlers have been disconnected, which lets
+        the scheduler finish - while any handlers are connected, the script keeps running.
+    */
+    fn start_dispatching(self: &Rc<Self>, lua: &Lua) {
+        let Some(signal_rx) = self.signal_rx.take() else {
+            return;
+        };
+
+        let inner_lua = lua
+            .app_data_ref::<Weak<Lua>>()
+            .expect("Missing weak lua ref")
+            .upgrade()
+            .expect("Lua was dropped unexpectedly");
+
+        let mut guard = DispatchGuard {
+            lifecycle: Rc::clone(self),
+            signal_rx: Some(signal_rx),
+        };
+        let mut connected_rx = self.connected_tx.subscribe();
+
+        lua.spawn_local(async move {
+            let signal_rx = guard.signal_rx.as_mut().unwrap();
+            loop {
+                tokio::select! {
+                    Some(signal) = signal_rx.recv() => {
+                        guard.lifecycle.dispatch(&inner_lua, signal);
+                    }
+                    _ = connected_rx.wait_for(|connected| *connected == 0) => break,
+                }
+            }
+        });
+    }
+
+    fn dispatch(&self, lua: &Lua, signal: ProcessSignal) {
+        let callbacks = match self.signals.borrow().get(&signal) {
+            Some(handlers) => handlers
+                .handlers
+                .iter()
+                .filter_map(|(_, key)| lua.registry_value::<LuaFunction>(key).ok())
+                .collect::<Vec<_>>(),
+            None => return,
+        };
+        for callback in callbacks {
+            // NOTE: Errors in handlers are reported by the scheduler, and pushing
+            // threads can only fail when the scheduler is no longer running
+            lua.push_thread_back(callback, signal).ok();
+        }
+    }
+}
+
+/**
+    Hands the signal receiver back to the lifecycle state once dispatching stops,
+    even if the dispatching task was dropped because the scheduler was stopped.
+*/
+struct DispatchGuard {
+    lifecycle: Rc<ProcessLifecycle>,
+    signal_rx: Option<mpsc::UnboundedReceiver<ProcessSignal>>,
+}
+
+impl Drop for DispatchGuard {
+    fn drop(&mut self) {
+        self.lifecycle.signal_rx.replace(self.signal_rx.take());
+    }
+}
+
+/**
+    Spawns a background task listening for the given signal.
+
+    Received signals are forwarded to Lua while any handlers are connected,
+    otherwise the default behavior of the signal is emulated, since the
+    default handler can not be restored once a signal has been listened to.
+*/
+#[cfg(unix)]
+fn listen_for_signal(
+    signal: ProcessSignal,
+    connected: Arc<AtomicUsize>,
+    signal_tx: mpsc::UnboundedSender<ProcessSignal>,
+) -> LuaResult<()> {
+    use tokio::signal::unix::{signal as listen, SignalKind};
+
+    let mut stream = listen(SignalKind::from_raw(signal.number())).map_err(|e| {
+        LuaError::RuntimeError(format!("Failed to listen for signal {signal} - {e}"))
+    })?;
+
+    tokio::spawn(async move {
+        while stream.recv().await.is_some() {
+            if connected.load(Ordering::SeqCst) == 0 {
+                emulate_default_handler(signal);
+            } else if signal_tx.send(signal).is_err() {
+                break;
+            }
+        }
+    });
+
+    Ok(())
+}
+
+#[cfg(unix)]
+fn emulate_default_handler(signal: ProcessSignal) {
+    let number = signal.number();
+    // SAFETY: Raising signals and resetting them to their
+    // default handlers has no memory safety implications
+    unsafe {
+        match number {
+            libc::SIGCHLD | libc::SIGCONT | libc::SIGURG | libc::SIGWINCH => {}
+            libc::SIGTSTP | libc::SIGTTIN | libc::SIGTTOU => {
+                libc::raise(libc::SIGSTOP);
+            }
+            _ => {
+                libc::signal(number, libc::SIG_DFL);
+                libc::raise(number);
+            }
+        }
+    }
+}
+
+#[cfg(not(unix))]
+fn listen_for_signal(
+    signal: ProcessSignal,
+    connected: Arc<AtomicUsize>,
+    signal_tx: mpsc::UnboundedSender<ProcessSignal>,
+) -> LuaResult<()> {
+    // NOTE: The only signal that has an equivalent on
+    // other platforms is SIGINT, which is sent on Ctrl+C
+    if signal.name() != Some("INT") {
+        return Err(LuaError::RuntimeError(format!(
+            "Listening for the signal {signal} is only supported on unix platforms"
+        )));
+    }
+
+    tokio::spawn(async move {
+        while tokio::signal::ctrl_c().await.is_ok() {
+            if connected.load(Ordering::SeqCst) == 0 {
+                std::process::exit(128 + signal.number());
+            } else if signal_tx.send(signal).is_err() {
+                break;
+            }
+        }
+    });
+
+    Ok(())
+}
+
+/**
+    A connected signal handler or exit hook, which can be disconnected.
+*/
+pub struct ProcessConnection {
+    lifecycle: Weak<ProcessLifecycle>,
+    id: usize,
+    signal: Option<ProcessSignal>,
+    connected: Cell<bool>,
+}
+
+impl ProcessConnection {
+    fn new(lifecycle: &Rc<ProcessLifecycle>, id: usize, signal: Option<ProcessSignal>) -> Self {
+        Self {
+            lifecycle: Rc::downgrade(lifecycle),
+            id,
+            signal,
+            connected: Cell::new(true),
+        }
+    }
+
+    fn disconnect(&self) {
+        if self.connected.replace(false) {
+            if let Some(lifecycle) = self.lifecycle.upgrade() {
+                lifecycle.disconnect(self.id, self.signal);
+            }
+        }
+    }
+}
+
+impl LuaUserData for ProcessConnection {
+    fn add_fields<'lua, F: LuaUserDataFields<'lua, Self>>(fields: &mut F) {
+        fields.add_meta_field(LuaMetaMethod::Type, "ProcessConnection");
+        fields.add_field_method_get("connected", |_, this| Ok(this.connected.get()));
+    }
+
+    fn add_methods<'lua, M: LuaUserDataMethods<'lua, Self>>(methods: &mut M) {
+        methods.add_method("disconnect", |_, this, ()| {
+            this.disconnect();
+            Ok(())
+        });
+    }
+}
+
+pub fn process_on_exit(lua: &Lua, callback: LuaFunction) -> LuaResult<ProcessConnection> {
+    ProcessLifecycle::get_or_create(lua).connect_exit(lua, callback)
+}
+
+pub fn process_on_signal(
+    lua: &Lua,
+    (signal, callback): (ProcessSignal, LuaFunction),
+) -> LuaResult<ProcessConnection> {
+    ProcessLifecycle::get_or_create(lua).connect_signal(lua, signal, callback)
+}
+
+/**
+    Creates the `process.exit` function.
+
+    This records the exit code given, so that it can be passed to exit
+    hooks, sets it for the scheduler, and then yields the calling thread.
+*/
+pub fn create_exit_function<'lua>(lua: &'lua Lua) -> LuaResult<LuaFunction<'lua>> {
+    let exit_env = lua.create_table_from(vec![
+        (
+            "exit",
+            lua.create_function(|lua, code: Option<u8>| {
+                let code = code.unwrap_or_default();
+                ProcessLifecycle::get_or_create(lua)
+                    .exit_code
+                    .set(Some(code));
+                lua.set_exit_code(ExitCode::from(code));
+                Ok(())
+            })?,
+        ),
+        (
+            "yield",
+            lua.globals()
+                .get::<_, LuaTable>("coroutine")?
+                .get::<_, LuaFunction>("yield")?,
+        ),
+    ])?;
+    lua.load(EXIT_IMPL_LUA)
+        .set_name("=process.exit")
+        .set_environment(exit_env)
+        .into_function()
+}
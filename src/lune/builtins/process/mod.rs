@@ -5,29 +5,37 @@ use std::{
 };
 
 use mlua::prelude::*;
-use mlua_luau_scheduler::{Functions, LuaSpawnExt};
+use mlua_luau_scheduler::LuaSpawnExt;
 use os_str_bytes::RawOsString;
-use tokio::io::AsyncWriteExt;
 
-use crate::lune::util::{paths::CWD, TableBuilder};
+use crate::lune::util::{paths::current_working_dir, TableBuilder};
 
 mod tee_writer;
 
 mod options;
-use options::ProcessSpawnOptions;
+use options::{ProcessSpawnOptions, ProcessSpawnOptionsStdin, ProcessSpawnOptionsStdioKind};
+
+mod pipeline;
+use pipeline::{run_pipeline, ProcessPipelineStage};
+
+mod child;
+mod info;
+mod lifecycle;
+mod signal;
+mod status;
+mod stream;
+mod terminate;
+use child::ChildProcess;
+use info::{process_cpu_usage, process_memory_usage, process_uptime};
+use lifecycle::{create_exit_function, process_on_exit, process_on_signal};
+
+pub use info::PROCESS_START_TIME;
+pub use lifecycle::ProcessLifecycle;
 
 mod wait_for_child;
 use wait_for_child::{wait_for_child, WaitForChildResult};
 
 pub fn create(lua: &Lua) -> LuaResult<LuaTable> {
-    let cwd_str = {
-        let cwd_str = CWD.to_string_lossy().to_string();
-        if !cwd_str.ends_with(path::MAIN_SEPARATOR) {
-            format!("{cwd_str}{}", path::MAIN_SEPARATOR)
-        } else {
-            cwd_str
-        }
-    };
     // Create constants for OS & processor architecture
     let os = lua.create_string(&consts::OS.to_lowercase())?;
     let arch = lua.create_string(&consts::ARCH.to_lowercase())?;
@@ -49,21 +57,66 @@ pub fn create(lua: &Lua) -> LuaResult<LuaTable> {
                 .build_readonly()?,
         )?
         .build_readonly()?;
-    // Create our process exit function, the scheduler crate provides this
-    let fns = Functions::new(lua)?;
-    let process_exit = fns.exit;
+    // Create our process exit function, which also records the
+    // exit code so that it can be passed to any exit hooks
+    let process_exit = create_exit_function(lua)?;
     // Create the full process table
     TableBuilder::new(lua)?
         .with_value("os", os)?
         .with_value("arch", arch)?
         .with_value("args", args_tab)?
-        .with_value("cwd", cwd_str)?
         .with_value("env", env_tab)?
+        .with_value("pid", std::process::id())?
+        .with_value("ppid", info::parent_pid())?
+        .with_value("execPath", info::exec_path()?)?
+        .with_value("hostname", info::host_name())?
+        .with_value("username", info::user_name())?
         .with_value("exit", process_exit)?
+        .with_function("chdir", process_chdir)?
+        .with_function("onExit", process_on_exit)?
+        .with_function("onSignal", process_on_signal)?
+        .with_function("uptime", process_uptime)?
+        .with_function("memoryUsage", process_memory_usage)?
+        .with_function("cpuUsage", process_cpu_usage)?
         .with_async_function("spawn", process_spawn)?
+        .with_async_function("create", process_create)?
+        .with_async_function("pipeline", process_pipeline)?
+        // NOTE: The working directory may change while the
+        // script is running, so we look it up when indexed
+        .with_metatable(
+            TableBuilder::new(lua)?
+                .with_function(LuaMetaMethod::Index.name(), process_index)?
+                .build_readonly()?,
+        )?
         .build_readonly()
 }
 
+fn process_index<'lua>(
+    lua: &'lua Lua,
+    (_, key): (LuaValue<'lua>, String),
+) -> LuaResult<LuaValue<'lua>> {
+    match key.as_str() {
+        "cwd" => {
+            let cwd_str = current_working_dir().to_string_lossy().to_string();
+            let cwd_str = if !cwd_str.ends_with(path::MAIN_SEPARATOR) {
+                format!("{cwd_str}{}", path::MAIN_SEPARATOR)
+            } else {
+                cwd_str
+            };
+            cwd_str.into_lua(lua)
+        }
+        _ => Ok(LuaValue::Nil),
+    }
+}
+
+fn process_chdir(_: &Lua, path: String) -> LuaResult<()> {
+    env::set_current_dir(&path).map_err(|e| {
+        LuaError::RuntimeError(format!(
+            "Failed to change working directory to '{path}' - {e}"
+        ))
+    })
+}
+
 fn process_env_get<'lua>(
     lua: &'lua Lua,
     (_, key): (LuaValue<'lua>, String),
@@ -142,22 +195,12 @@ async fn process_spawn(
         .await
         .expect("Failed to receive result of spawned process");
 
-    /*
-        NOTE: If an exit code was not given by the child process,
-        we default to 1 if it yielded any error output, otherwise 0
-
-        An exit code may be missing if the process was terminated by
-        some external signal, which is the only time we use this default
-    */
-    let code = res.status.code().unwrap_or(match res.stderr.is_empty() {
-        true => 0,
-        false => 1,
-    });
-
     // Construct and return a readonly lua table with results
     TableBuilder::new(lua)?
-        .with_value("ok", code == 0)?
-        .with_value("code", code)?
+        .with_value("ok", res.status.ok())?
+        .with_value("code", res.status.code)?
+        .with_value("signal", res.status.signal)?
+        .with_value("timedOut", res.status.timed_out)?
         .with_value("stdout", lua.create_string(&res.stdout)?)?
         .with_value("stderr", lua.create_string(&res.stderr)?)?
         .build_readonly()
@@ -168,24 +211,103 @@ async fn spawn_command(
     args: Option<Vec<String>>,
     mut options: ProcessSpawnOptions,
 ) -> LuaResult<WaitForChildResult> {
-    let stdout = options.stdio.stdout;
-    let stderr = options.stdio.stderr;
+    let stdout = options.stdio.stdout.clone();
+    let stderr = options.stdio.stderr.clone();
     let stdin = options.stdio.stdin.take();
+    let kill = options.kill;
 
     let mut child = options
         .into_command(program, args)
-        .stdin(match stdin.is_some() {
-            true => Stdio::piped(),
-            false => Stdio::null(),
+        .stdin(match &stdin {
+            Some(stdin) => stdin.as_stdio()?,
+            None => Stdio::null(),
         })
-        .stdout(stdout.as_stdio())
-        .stderr(stderr.as_stdio())
+        .stdout(stdout.as_stdio()?)
+        .stderr(stderr.as_stdio()?)
         .spawn()?;
 
     if let Some(stdin) = stdin {
-        let mut child_stdin = child.stdin.take().unwrap();
-        child_stdin.write_all(&stdin).await.into_lua_err()?;
+        stdin.write_to(child.stdin.take());
+    }
+
+    wait_for_child(child, stdout, stderr, kill).await
+}
+
+async fn process_create(
+    _: &Lua,
+    (program, args, mut options): (String, Option<Vec<String>>, ProcessSpawnOptions),
+) -> LuaResult<ChildProcess> {
+    // NOTE: Output streams are readable from Lua by default, and
+    // the tee-like "inherit" kind is treated the same as "forward"
+    let as_stdio = |kind: &ProcessSpawnOptionsStdioKind| match kind {
+        ProcessSpawnOptionsStdioKind::Default => Ok(Stdio::piped()),
+        ProcessSpawnOptionsStdioKind::Inherit => Ok(Stdio::inherit()),
+        kind => kind.as_stdio(),
+    };
+
+    let stdout = as_stdio(&options.stdio.stdout)?;
+    let stderr = as_stdio(&options.stdio.stderr)?;
+    let stdin = options.stdio.stdin.take();
+    let kill = options.kill;
+
+    let mut child = options
+        .into_command(program, args)
+        .stdin(match &stdin {
+            Some(stdin) => stdin.as_stdio()?,
+            None => Stdio::piped(),
+        })
+        .stdout(stdout)
+        .stderr(stderr)
+        .spawn()?;
+
+    // Initial input is written through the writer of the child process, which
+    // keeps its input stream open, while other input sources take it over
+    let initial_stdin = match stdin {
+        Some(ProcessSpawnOptionsStdin::Bytes(bytes)) => Some(bytes),
+        Some(stdin) => {
+            stdin.write_to(child.stdin.take());
+            None
+        }
+        None => None,
+    };
+
+    let child = ChildProcess::new(child, kill);
+    if let (Some(bytes), Some(writer)) = (initial_stdin, child.stdin()) {
+        writer.write(bytes).await?;
     }
 
-    wait_for_child(child, stdout, stderr).await
+    Ok(child)
+}
+
+async fn process_pipeline<'lua>(
+    lua: &'lua Lua,
+    stages: Vec<ProcessPipelineStage>,
+) -> LuaResult<LuaTable<'lua>> {
+    let res = lua
+        .spawn(run_pipeline(stages))
+        .await
+        .expect("Failed to receive result of spawned pipeline");
+
+    let code = res
+        .stages
+        .last()
+        .map(|(status, _)| status.code)
+        .unwrap_or(0);
+    let ok = res.stages.iter().all(|(status, _)| status.ok());
+
+    let stages_tab = lua.create_table_with_capacity(res.stages.len(), 0)?;
+    for (status, stderr) in res.stages {
+        let stage_tab = status.into_lua_table(lua)?;
+        stage_tab.set("stderr", lua.create_string(&stderr)?)?;
+        stage_tab.set_readonly(true);
+        stages_tab.push(stage_tab)?;
+    }
+    stages_tab.set_readonly(true);
+
+    TableBuilder::new(lua)?
+        .with_value("ok", ok)?
+        .with_value("code", code)?
+        .with_value("stdout", lua.create_string(&res.stdout)?)?
+        .with_value("stages", stages_tab)?
+        .build_readonly()
 }
@@ -0,0 +1,59 @@
+use std::time::Duration;
+
+use mlua::prelude::*;
+
+/**
+    Options for when and how a child process should be killed.
+*/
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ProcessKillOptions {
+    pub timeout: Option<Duration>,
+    pub grace_period: Duration,
+    pub group: bool,
+}
+
+fn parse_seconds(value: LuaValue, option: &str) -> LuaResult<Option<Duration>> {
+    match value {
+        LuaValue::Nil => Ok(None),
+        LuaValue::Integer(i) if i >= 0 => Ok(Some(Duration::from_secs(i as u64))),
+        LuaValue::Number(n) if n.is_finite() && n >= 0.0 => Ok(Some(Duration::from_secs_f64(n))),
+        value => Err(LuaError::RuntimeError(format!(
+            "Invalid value for option '{option}' - expected a positive number of seconds, got '{}'",
+            value.type_name()
+        ))),
+    }
+}
+
+impl ProcessKillOptions {
+    /**
+        Parses kill options from the `timeout`, `killGracePeriod`
+        and `processGroup` keys of a table of spawn options.
+    */
+    pub fn from_options_table(options: &LuaTable) -> LuaResult<Self> {
+        let timeout = parse_seconds(options.get("timeout")?, "timeout")?;
+        let grace_period =
+            parse_seconds(options.get("killGracePeriod")?, "killGracePeriod")?.unwrap_or_default();
+
+        let group = match options.get("processGroup")? {
+            LuaValue::Nil => false,
+            LuaValue::Boolean(b) => b,
+            value => {
+                return Err(LuaError::RuntimeError(format!(
+                    "Invalid type for option 'processGroup' - expected boolean, got '{}'",
+                    value.type_name()
+                )))
+            }
+        };
+        if group && cfg!(not(unix)) {
+            return Err(LuaError::runtime(
+                "Invalid value for option 'processGroup' - process groups are only supported on unix platforms",
+            ));
+        }
+
+        Ok(Self {
+            timeout,
+            grace_period,
+            group,
+        })
+    }
+}
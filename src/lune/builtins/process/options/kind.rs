@@ -1,9 +1,11 @@
-use std::{fmt, process::Stdio, str::FromStr};
+use std::{fmt, fs::OpenOptions, path::PathBuf, process::Stdio, str::FromStr};
 
 use itertools::Itertools;
 use mlua::prelude::*;
 
-#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+const NAMED_KINDS: [&str; 4] = ["default", "forward", "inherit", "none"];
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
 pub enum ProcessSpawnOptionsStdioKind {
     // TODO: We need better more obvious names
     // for these, but that is a breaking change
@@ -12,19 +14,34 @@ pub enum ProcessSpawnOptionsStdioKind {
     Forward,
     Inherit,
     None,
+    File {
+        path: PathBuf,
+        append: bool,
+    },
 }
 
 impl ProcessSpawnOptionsStdioKind {
-    pub fn all() -> &'static [Self] {
-        &[Self::Default, Self::Forward, Self::Inherit, Self::None]
-    }
-
-    pub fn as_stdio(self) -> Stdio {
-        match self {
+    pub fn as_stdio(&self) -> LuaResult<Stdio> {
+        Ok(match self {
             Self::None => Stdio::null(),
             Self::Forward => Stdio::inherit(),
+            Self::File { path, append } => {
+                let file = OpenOptions::new()
+                    .write(true)
+                    .create(true)
+                    .append(*append)
+                    .truncate(!*append)
+                    .open(path)
+                    .map_err(|e| {
+                        LuaError::RuntimeError(format!(
+                            "Failed to open '{}' for redirecting output - {e}",
+                            path.display()
+                        ))
+                    })?;
+                Stdio::from(file)
+            }
             _ => Stdio::piped(),
-        }
+        })
     }
 }
 
@@ -35,6 +52,7 @@ impl fmt::Display for ProcessSpawnOptionsStdioKind {
             Self::Forward => "forward",
             Self::Inherit => "inherit",
             Self::None => "none",
+            Self::File { .. } => "file",
         };
         f.write_str(s)
     }
@@ -52,10 +70,7 @@ impl FromStr for ProcessSpawnOptionsStdioKind {
                 return Err(LuaError::RuntimeError(format!(
                     "Invalid spawn options stdio kind - got '{}', expected one of {}",
                     s,
-                    ProcessSpawnOptionsStdioKind::all()
-                        .iter()
-                        .map(|k| format!("'{k}'"))
-                        .join(", ")
+                    NAMED_KINDS.iter().map(|k| format!("'{k}'")).join(", ")
                 )))
             }
         })
@@ -67,11 +82,24 @@ impl<'lua> FromLua<'lua> for ProcessSpawnOptionsStdioKind {
         match value {
             LuaValue::Nil => Ok(Self::default()),
             LuaValue::String(s) => s.to_str()?.parse(),
+            LuaValue::Table(t) => {
+                let path = match t.get("path")? {
+                    LuaValue::String(s) => PathBuf::from(s.to_str()?),
+                    value => {
+                        return Err(LuaError::RuntimeError(format!(
+                            "Invalid type for stdio file 'path' - expected string, got '{}'",
+                            value.type_name()
+                        )))
+                    }
+                };
+                let append = t.get::<_, Option<bool>>("append")?.unwrap_or_default();
+                Ok(Self::File { path, append })
+            }
             _ => Err(LuaError::FromLuaConversionError {
                 from: value.type_name(),
                 to: "ProcessSpawnOptionsStdioKind",
                 message: Some(format!(
-                    "Invalid spawn options stdio kind - expected string, got {}",
+                    "Invalid spawn options stdio kind - expected string or table, got {}",
                     value.type_name()
                 )),
             }),
@@ -0,0 +1,100 @@
+use mlua::prelude::*;
+use tokio::process::Command;
+
+/**
+    Resource limits for a child process.
+
+    Limits are only supported on unix platforms, where they
+    are applied using `setrlimit` before the program is started.
+*/
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ProcessSpawnOptionsLimits {
+    pub cpu: Option<u64>,
+    pub memory: Option<u64>,
+    pub open_files: Option<u64>,
+}
+
+impl ProcessSpawnOptionsLimits {
+    pub fn is_empty(&self) -> bool {
+        self.cpu.is_none() && self.memory.is_none() && self.open_files.is_none()
+    }
+
+    #[cfg(unix)]
+    pub fn apply(self, cmd: &mut Command) {
+        if self.is_empty() {
+            return;
+        }
+
+        let limits = [
+            (libc::RLIMIT_CPU, self.cpu),
+            (libc::RLIMIT_AS, self.memory),
+            (libc::RLIMIT_NOFILE, self.open_files),
+        ];
+
+        // SAFETY: The closure only calls setrlimit, which is async-signal-safe,
+        // and does not allocate or touch any state shared with the parent process
+        unsafe {
+            cmd.pre_exec(move || {
+                for (resource, limit) in limits {
+                    if let Some(limit) = limit {
+                        let rlimit = libc::rlimit {
+                            rlim_cur: limit as libc::rlim_t,
+                            rlim_max: limit as libc::rlim_t,
+                        };
+                        if libc::setrlimit(resource, &rlimit) != 0 {
+                            return Err(std::io::Error::last_os_error());
+                        }
+                    }
+                }
+                Ok(())
+            });
+        }
+    }
+
+    #[cfg(not(unix))]
+    pub fn apply(self, _: &mut Command) {}
+}
+
+impl<'lua> FromLua<'lua> for ProcessSpawnOptionsLimits {
+    fn from_lua(value: LuaValue<'lua>, _: &'lua Lua) -> LuaResult<Self> {
+        let tab = match value {
+            LuaValue::Nil => return Ok(Self::default()),
+            LuaValue::Table(t) => t,
+            _ => {
+                return Err(LuaError::FromLuaConversionError {
+                    from: value.type_name(),
+                    to: "ProcessSpawnOptionsLimits",
+                    message: Some(format!(
+                        "Invalid spawn options limits - expected table, got {}",
+                        value.type_name()
+                    )),
+                })
+            }
+        };
+
+        let get_limit = |key: &str| -> LuaResult<Option<u64>> {
+            match tab.get(key)? {
+                LuaValue::Nil => Ok(None),
+                LuaValue::Integer(i) if i >= 0 => Ok(Some(i as u64)),
+                LuaValue::Number(n) if n.fract() == 0.0 && n >= 0.0 => Ok(Some(n as u64)),
+                value => Err(LuaError::RuntimeError(format!(
+                    "Invalid value for limit '{key}' - expected a positive integer, got '{}'",
+                    value.type_name()
+                ))),
+            }
+        };
+
+        let this = Self {
+            cpu: get_limit("cpu")?,
+            memory: get_limit("memory")?,
+            open_files: get_limit("openFiles")?,
+        };
+        if !this.is_empty() && cfg!(not(unix)) {
+            return Err(LuaError::runtime(
+                "Invalid value for option 'limits' - resource limits are only supported on unix platforms",
+            ));
+        }
+
+        Ok(this)
+    }
+}
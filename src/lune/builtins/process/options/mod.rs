@@ -8,18 +8,26 @@ use directories::UserDirs;
 use mlua::prelude::*;
 use tokio::process::Command;
 
+mod kill;
 mod kind;
+mod limits;
+mod stdin;
 mod stdio;
 
+pub(super) use kill::*;
 pub(super) use kind::*;
+pub(super) use limits::*;
+pub(super) use stdin::*;
 pub(super) use stdio::*;
 
-#[derive(Debug, Clone, Default)]
+#[derive(Clone, Default)]
 pub(super) struct ProcessSpawnOptions {
     pub cwd: Option<PathBuf>,
     pub envs: HashMap<String, String>,
     pub shell: Option<String>,
     pub stdio: ProcessSpawnOptionsStdio,
+    pub kill: ProcessKillOptions,
+    pub limits: ProcessSpawnOptionsLimits,
 }
 
 impl<'lua> FromLua<'lua> for ProcessSpawnOptions {
@@ -122,17 +130,17 @@ impl<'lua> FromLua<'lua> for ProcessSpawnOptions {
             scripts, but the user should preferrably pass it in the stdio table
         */
         this.stdio = value.get("stdio")?;
-        match value.get("stdin")? {
-            LuaValue::Nil => {}
-            LuaValue::String(s) => this.stdio.stdin = Some(s.as_bytes().to_vec()),
-            value => {
-                return Err(LuaError::RuntimeError(format!(
-                    "Invalid type for option 'stdin' - expected 'string', got '{}'",
-                    value.type_name()
-                )))
-            }
+        if let Some(stdin) = value.get("stdin")? {
+            this.stdio.stdin = Some(stdin);
         }
 
+        /*
+            If we got a timeout or options for how to kill the process,
+            and any resource limits, parse and validate those as well
+        */
+        this.kill = ProcessKillOptions::from_options_table(&value)?;
+        this.limits = value.get("limits")?;
+
         Ok(this)
     }
 }
@@ -172,6 +180,20 @@ impl ProcessSpawnOptions {
             cmd.envs(self.envs);
         }
 
+        // Put the process in its own group if wanted, so that
+        // the whole tree of processes can be signalled at once
+        #[cfg(unix)]
+        if self.kill.group {
+            // SAFETY: The closure only calls setpgid, which is async-signal-safe
+            unsafe {
+                cmd.pre_exec(|| match libc::setpgid(0, 0) {
+                    0 => Ok(()),
+                    _ => Err(std::io::Error::last_os_error()),
+                });
+            }
+        }
+        self.limits.apply(&mut cmd);
+
         cmd
     }
 }
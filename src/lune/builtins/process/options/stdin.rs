@@ -0,0 +1,87 @@
+use std::{fs::File, path::PathBuf, process::Stdio};
+
+use mlua::prelude::*;
+use tokio::{io::AsyncWriteExt, process::ChildStdin, task};
+
+use super::super::stream::ChildProcessReader;
+
+/**
+    A source of standard input for a child process.
+*/
+#[derive(Clone)]
+pub enum ProcessSpawnOptionsStdin {
+    Bytes(Vec<u8>),
+    File(PathBuf),
+    Reader(ChildProcessReader),
+}
+
+impl ProcessSpawnOptionsStdin {
+    pub fn as_stdio(&self) -> LuaResult<Stdio> {
+        Ok(match self {
+            Self::File(path) => {
+                let file = File::open(path).map_err(|e| {
+                    LuaError::RuntimeError(format!(
+                        "Failed to open '{}' for redirecting input - {e}",
+                        path.display()
+                    ))
+                })?;
+                Stdio::from(file)
+            }
+            _ => Stdio::piped(),
+        })
+    }
+
+    /**
+        Starts writing this input to the given stdin of a child process.
+
+        Writing happens in the background, so that a child process that
+        produces output before it has read all of its input can not deadlock.
+    */
+    pub fn write_to(self, stdin: Option<ChildStdin>) {
+        let Some(mut stdin) = stdin else {
+            return;
+        };
+        match self {
+            Self::Bytes(bytes) => {
+                task::spawn(async move {
+                    // NOTE: The child process may exit without reading all of
+                    // its input, which is not an error, so we ignore failures
+                    stdin.write_all(&bytes).await.ok();
+                    stdin.shutdown().await.ok();
+                });
+            }
+            Self::Reader(reader) => {
+                task::spawn(async move {
+                    reader.pipe_to(stdin).await.ok();
+                });
+            }
+            Self::File(_) => {}
+        }
+    }
+}
+
+impl<'lua> FromLua<'lua> for ProcessSpawnOptionsStdin {
+    fn from_lua(value: LuaValue<'lua>, _: &'lua Lua) -> LuaResult<Self> {
+        match value {
+            LuaValue::String(s) => Ok(Self::Bytes(s.as_bytes().to_vec())),
+            LuaValue::Table(t) => match t.get("path")? {
+                LuaValue::String(s) => Ok(Self::File(PathBuf::from(s.to_str()?))),
+                value => Err(LuaError::RuntimeError(format!(
+                    "Invalid type for stdin file 'path' - expected string, got '{}'",
+                    value.type_name()
+                ))),
+            },
+            LuaValue::UserData(ud) if ud.is::<ChildProcessReader>() => {
+                Ok(Self::Reader(ud.borrow::<ChildProcessReader>()?.clone()))
+            }
+            _ => Err(LuaError::FromLuaConversionError {
+                from: value.type_name(),
+                to: "ProcessSpawnOptionsStdin",
+                message: Some(format!(
+                    "Invalid spawn options stdin - expected string, table or ChildProcessReader, got {}",
+                    value.type_name()
+                )),
+            }),
+        }
+    }
+}
@@ -1,18 +1,18 @@
 use mlua::prelude::*;
 
-use super::kind::ProcessSpawnOptionsStdioKind;
+use super::{kind::ProcessSpawnOptionsStdioKind, stdin::ProcessSpawnOptionsStdin};
 
-#[derive(Debug, Clone, Default)]
+#[derive(Clone, Default)]
 pub struct ProcessSpawnOptionsStdio {
     pub stdout: ProcessSpawnOptionsStdioKind,
     pub stderr: ProcessSpawnOptionsStdioKind,
-    pub stdin: Option<Vec<u8>>,
+    pub stdin: Option<ProcessSpawnOptionsStdin>,
 }
 
 impl From<ProcessSpawnOptionsStdioKind> for ProcessSpawnOptionsStdio {
     fn from(value: ProcessSpawnOptionsStdioKind) -> Self {
         Self {
-            stdout: value,
+            stdout: value.clone(),
             stderr: value,
             ..Default::default()
         }
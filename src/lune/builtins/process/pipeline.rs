@@ -0,0 +1,184 @@
+use std::process::Stdio;
+
+use mlua::prelude::*;
+use tokio::{process::Child, task};
+
+use super::{
+    options::{ProcessKillOptions, ProcessSpawnOptions, ProcessSpawnOptionsStdioKind},
+    status::ProcessExitStatus,
+    wait_for_child::wait_for_child,
+};
+
+/**
+    A single stage of a process pipeline.
+
+    Stages accept the same options as `process.spawn`, together
+    with the program to run and any arguments to pass to it.
+*/
+#[derive(Clone)]
+pub(super) struct ProcessPipelineStage {
+    pub program: String,
+    pub args: Option<Vec<String>>,
+    pub options: ProcessSpawnOptions,
+}
+
+impl<'lua> FromLua<'lua> for ProcessPipelineStage {
+    fn from_lua(value: LuaValue<'lua>, lua: &'lua Lua) -> LuaResult<Self> {
+        let tab = match value {
+            LuaValue::Table(t) => t,
+            _ => {
+                return Err(LuaError::FromLuaConversionError {
+                    from: value.type_name(),
+                    to: "ProcessPipelineStage",
+                    message: Some(format!(
+                        "Invalid pipeline stage - expected table, got {}",
+                        value.type_name()
+                    )),
+                })
+            }
+        };
+
+        let program = match tab.get("program")? {
+            LuaValue::String(s) => s.to_str()?.to_string(),
+            value => {
+                return Err(LuaError::RuntimeError(format!(
+                    "Invalid type for pipeline stage 'program' - expected string, got '{}'",
+                    value.type_name()
+                )))
+            }
+        };
+        let args = tab.get("args")?;
+        let options = ProcessSpawnOptions::from_lua(LuaValue::Table(tab), lua)?;
+
+        Ok(Self {
+            program,
+            args,
+            options,
+        })
+    }
+}
+
+#[derive(Debug, Clone)]
+pub(super) struct ProcessPipelineResult {
+    pub stages: Vec<(ProcessExitStatus, Vec<u8>)>,
+    pub stdout: Vec<u8>,
+}
+
+/**
+    Spawns all stages of a pipeline, connecting the output of each
+    stage directly to the input of the next, and waits for all of them.
+
+    Only the first stage may be given input, and only the
+    last stage may have its output redirected somewhere else.
+*/
+pub(super) async fn run_pipeline(
+    stages: Vec<ProcessPipelineStage>,
+) -> LuaResult<ProcessPipelineResult> {
+    if stages.is_empty() {
+        return Err(LuaError::runtime(
+            "Pipeline must contain at least one stage",
+        ));
+    }
+
+    let mut children = Vec::with_capacity(stages.len());
+    if let Err(e) = spawn_stages(stages, &mut children).await {
+        for (child, _, _, _) in children.iter_mut() {
+            child.start_kill().ok();
+        }
+        return Err(e);
+    }
+
+    let waiters = children
+        .into_iter()
+        .map(|(child, stdout, stderr, kill)| {
+            task::spawn(wait_for_child(child, stdout, stderr, kill))
+        })
+        .collect::<Vec<_>>();
+
+    let mut result = ProcessPipelineResult {
+        stages: Vec::with_capacity(waiters.len()),
+        stdout: Vec::new(),
+    };
+    for waiter in waiters {
+        let res = waiter.await.into_lua_err()??;
+        result.stages.push((res.status, res.stderr));
+        result.stdout = res.stdout;
+    }
+
+    Ok(result)
+}
+
+type SpawnedStage = (
+    Child,
+    ProcessSpawnOptionsStdioKind,
+    ProcessSpawnOptionsStdioKind,
+    ProcessKillOptions,
+);
+
+async fn spawn_stages(
+    stages: Vec<ProcessPipelineStage>,
+    children: &mut Vec<SpawnedStage>,
+) -> LuaResult<()> {
+    let last = stages.len() - 1;
+    let mut previous_stdout: Option<Stdio> = None;
+
+    for (index, mut stage) in stages.into_iter().enumerate() {
+        let stdin = stage.options.stdio.stdin.take();
+        let stdout = stage.options.stdio.stdout.clone();
+        let stderr = stage.options.stdio.stderr.clone();
+        let kill = stage.options.kill;
+
+        if index > 0 && stdin.is_some() {
+            return Err(LuaError::RuntimeError(format!(
+                "Pipeline stage #{} can not be given stdin, only the first stage can",
+                index + 1
+            )));
+        }
+        if index < last && stdout != ProcessSpawnOptionsStdioKind::Default {
+            return Err(LuaError::RuntimeError(format!(
+                "Pipeline stage #{} can not redirect stdout, only the last stage can",
+                index + 1
+            )));
+        }
+
+        let stdin_stdio = match (previous_stdout.take(), &stdin) {
+            (Some(previous), _) => previous,
+            (None, Some(stdin)) => stdin.as_stdio()?,
+            (None, None) => Stdio::null(),
+        };
+        let stdout_stdio = match index < last {
+            true => Stdio::piped(),
+            false => stdout.as_stdio()?,
+        };
+
+        let mut child = stage
+            .options
+            .into_command(stage.program, stage.args)
+            .stdin(stdin_stdio)
+            .stdout(stdout_stdio)
+            .stderr(stderr.as_stdio()?)
+            .spawn()?;
+
+        if let Some(stdin) = stdin {
+            stdin.write_to(child.stdin.take());
+        }
+
+        // Output of all stages except the last is
+        // handed over to the next stage, and not read
+        let stdout = match index < last {
+            true => {
+                let piped = child
+                    .stdout
+                    .take()
+                    .expect("stdout must be piped for intermediate stages");
+                previous_stdout = Some(piped.try_into().into_lua_err()?);
+                ProcessSpawnOptionsStdioKind::None
+            }
+            false => stdout,
+        };
+
+        children.push((child, stdout, stderr, kill));
+    }
+
+    Ok(())
+}
@@ -0,0 +1,150 @@
+use std::fmt;
+
+use mlua::prelude::*;
+
+#[cfg(unix)]
+const SIGNALS: &[(&str, i32)] = &[
+    ("HUP", libc::SIGHUP),
+    ("INT", libc::SIGINT),
+    ("QUIT", libc::SIGQUIT),
+    ("ILL", libc::SIGILL),
+    ("TRAP", libc::SIGTRAP),
+    ("ABRT", libc::SIGABRT),
+    ("BUS", libc::SIGBUS),
+    ("FPE", libc::SIGFPE),
+    ("KILL", libc::SIGKILL),
+    ("USR1", libc::SIGUSR1),
+    ("SEGV", libc::SIGSEGV),
+    ("USR2", libc::SIGUSR2),
+    ("PIPE", libc::SIGPIPE),
+    ("ALRM", libc::SIGALRM),
+    ("TERM", libc::SIGTERM),
+    ("CHLD", libc::SIGCHLD),
+    ("CONT", libc::SIGCONT),
+    ("STOP", libc::SIGSTOP),
+    ("TSTP", libc::SIGTSTP),
+    ("TTIN", libc::SIGTTIN),
+    ("TTOU", libc::SIGTTOU),
+    ("URG", libc::SIGURG),
+    ("XCPU", libc::SIGXCPU),
+    ("XFSZ", libc::SIGXFSZ),
+    ("VTALRM", libc::SIGVTALRM),
+    ("PROF", libc::SIGPROF),
+    ("WINCH", libc::SIGWINCH),
+    ("IO", libc::SIGIO),
+    ("SYS", libc::SIGSYS),
+];
+
+// NOTE: Signals do not exist on Windows, but we still accept the most common
+// ones here so that the same scripts can parse them, they can not be sent though
+#[cfg(not(unix))]
+const SIGNALS: &[(&str, i32)] = &[("INT", 2), ("KILL", 9), ("TERM", 15)];
+
+/**
+    A process signal, such as `SIGTERM` or `SIGKILL`.
+
+    Signals can be created from their name, with or without the `SIG`
+    prefix, or from their raw number on the current platform.
+*/
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ProcessSignal(i32);
+
+impl ProcessSignal {
+    pub const KILL: Self = Self(9);
+    pub const TERM: Self = Self(15);
+
+    pub fn from_number(number: i32) -> Self {
+        Self(number)
+    }
+
+    pub fn from_name(name: impl AsRef<str>) -> Option<Self> {
+        let name = name.as_ref().trim().to_ascii_uppercase();
+        let name = name.strip_prefix("SIG").unwrap_or(&name);
+        SIGNALS
+            .iter()
+            .find(|(signal_name, _)| *signal_name == name)
+            .map(|(_, number)| Self(*number))
+    }
+
+    pub fn number(self) -> i32 {
+        self.0
+    }
+
+    pub fn name(self) -> Option<&'static str> {
+        SIGNALS
+            .iter()
+            .find(|(_, number)| *number == self.0)
+            .map(|(name, _)| *name)
+    }
+}
+
+impl fmt::Display for ProcessSignal {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.name() {
+            Some(name) => write!(f, "SIG{name}"),
+            None => write!(f, "{}", self.0),
+        }
+    }
+}
+
+impl<'lua> FromLua<'lua> for ProcessSignal {
+    fn from_lua(value: LuaValue<'lua>, _: &'lua Lua) -> LuaResult<Self> {
+        match &value {
+            LuaValue::Integer(i) => Ok(Self(*i)),
+            LuaValue::Number(n) if n.fract() == 0.0 => Ok(Self(*n as i32)),
+            LuaValue::String(s) => {
+                let name = s.to_str()?;
+                Self::from_name(name).ok_or_else(|| {
+                    LuaError::RuntimeError(format!("Unknown process signal '{name}'"))
+                })
+            }
+            _ => Err(LuaError::FromLuaConversionError {
+                from: value.type_name(),
+                to: "ProcessSignal",
+                message: Some(format!(
+                    "Invalid signal - expected string or integer, got {}",
+                    value.type_name()
+                )),
+            }),
+        }
+    }
+}
+
+impl<'lua> IntoLua<'lua> for ProcessSignal {
+    fn into_lua(self, lua: &'lua Lua) -> LuaResult<LuaValue<'lua>> {
+        self.to_string().into_lua(lua)
+    }
+}
+
+/**
+    Sends a signal to the process with the given id.
+
+    Only supported on unix platforms, other platforms should
+    use the kill method on their child process handle instead.
+*/
+#[cfg(unix)]
+pub fn send_signal(pid: u32, signal: ProcessSignal) -> LuaResult<()> {
+    // SAFETY: Sending a signal has no memory safety implications
+    let result = unsafe { libc::kill(pid as libc::pid_t, signal.number()) };
+    if result == 0 {
+        Ok(())
+    } else {
+        Err(std::io::Error::last_os_error().into_lua_err())
+    }
+}
+
+/**
+    Sends a signal to every process in the process group with the given id.
+
+    Only supported on unix platforms, same as `send_signal`.
+*/
+#[cfg(unix)]
+pub fn send_signal_to_group(pgid: u32, signal: ProcessSignal) -> LuaResult<()> {
+    // SAFETY: Sending a signal has no memory safety implications
+    let result = unsafe { libc::killpg(pgid as libc::pid_t, signal.number()) };
+    if result == 0 {
+        Ok(())
+    } else {
+        Err(std::io::Error::last_os_error().into_lua_err())
+    }
+}
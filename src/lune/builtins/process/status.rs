@@ -0,0 +1,74 @@
+use std::process::ExitStatus;
+
+use mlua::prelude::*;
+
+use super::signal::ProcessSignal;
+
+/**
+    The final status of a child process, after it has exited.
+*/
+#[derive(Debug, Clone, Copy)]
+pub(super) struct ProcessExitStatus {
+    pub code: i32,
+    pub signal: Option<ProcessSignal>,
+    pub timed_out: bool,
+}
+
+impl ProcessExitStatus {
+    pub fn ok(&self) -> bool {
+        self.code == 0 && self.signal.is_none() && !self.timed_out
+    }
+
+    pub fn with_timed_out(self) -> Self {
+        Self {
+            timed_out: true,
+            ..self
+        }
+    }
+
+    pub fn into_lua_table<'lua>(self, lua: &'lua Lua) -> LuaResult<LuaTable<'lua>> {
+        let tab = lua.create_table_with_capacity(0, 4)?;
+        tab.set("ok", self.ok())?;
+        tab.set("code", self.code)?;
+        tab.set("signal", self.signal)?;
+        tab.set("timedOut", self.timed_out)?;
+        Ok(tab)
+    }
+}
+
+impl From<ExitStatus> for ProcessExitStatus {
+    fn from(value: ExitStatus) -> Self {
+        #[cfg(unix)]
+        let signal = {
+            use std::os::unix::process::ExitStatusExt;
+            value.signal().map(ProcessSignal::from_number)
+        };
+        #[cfg(not(unix))]
+        let signal = None;
+
+        /*
+            NOTE: An exit code may be missing if the process was terminated
+            by a signal, in which case we follow the convention that shells
+            use, and report the exit code as 128 + the signal number
+        */
+        let code = match (value.code(), signal) {
+            (Some(code), _) => code,
+            (None, Some(signal)) => 128 + signal.number(),
+            (None, None) => 1,
+        };
+
+        Self {
+            code,
+            signal,
+            timed_out: false,
+        }
+    }
+}
+
+impl<'lua> IntoLua<'lua> for ProcessExitStatus {
+    fn into_lua(self, lua: &'lua Lua) -> LuaResult<LuaValue<'lua>> {
+        let tab = self.into_lua_table(lua)?;
+        tab.set_readonly(true);
+        Ok(LuaValue::Table(tab))
+    }
+}
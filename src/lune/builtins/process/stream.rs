@@ -0,0 +1,203 @@
+use std::sync::Arc;
+
+use bstr::{BString, ByteSlice};
+use mlua::prelude::*;
+use mlua_luau_scheduler::LuaSchedulerExt;
+use tokio::{
+    io::{self, AsyncBufReadExt, AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt, BufReader},
+    process::ChildStdin,
+    sync::Mutex as AsyncMutex,
+};
+
+use super::child::ChildProcessGuard;
+
+const DEFAULT_CHUNK_SIZE: usize = 8192;
+
+type BoxedReader = Box<dyn AsyncRead + Send + Unpin>;
+
+/**
+    A readable output stream of a child process, such as stdout or stderr.
+*/
+#[derive(Clone)]
+pub struct ChildProcessReader {
+    inner: Arc<AsyncMutex<BufReader<BoxedReader>>>,
+    _guard: ChildProcessGuard,
+}
+
+impl ChildProcessReader {
+    pub fn new(reader: impl AsyncRead + Send + Unpin + 'static, guard: ChildProcessGuard) -> Self {
+        let reader: BoxedReader = Box::new(reader);
+        Self {
+            inner: Arc::new(AsyncMutex::new(BufReader::new(reader))),
+            _guard: guard,
+        }
+    }
+
+    pub async fn read_chunk(&self, size: usize) -> LuaResult<Option<Vec<u8>>> {
+        let mut reader = self.inner.lock().await;
+        let mut buffer = vec![0; size];
+        let read = reader.read(&mut buffer).await?;
+        if read == 0 {
+            Ok(None)
+        } else {
+            buffer.truncate(read);
+            Ok(Some(buffer))
+        }
+    }
+
+    pub async fn read_line(&self, keep_newline: bool) -> LuaResult<Option<Vec<u8>>> {
+        let mut reader = self.inner.lock().await;
+        let mut buffer = Vec::new();
+        let read = reader.read_until(b'\n', &mut buffer).await?;
+        if read == 0 {
+            return Ok(None);
+        }
+        if !keep_newline {
+            if buffer.ends_with(b"\n") {
+                buffer.pop();
+            }
+            if buffer.ends_with(b"\r") {
+                buffer.pop();
+            }
+        }
+        Ok(Some(buffer))
+    }
+
+    pub async fn read_to_end(&self) -> LuaResult<Vec<u8>> {
+        let mut reader = self.inner.lock().await;
+        let mut buffer = Vec::new();
+        reader.read_to_end(&mut buffer).await?;
+        Ok(buffer)
+    }
+
+    pub async fn pipe_to(&self, mut writer: impl AsyncWrite + Unpin) -> LuaResult<()> {
+        let mut reader = self.inner.lock().await;
+        io::copy_buf(&mut *reader, &mut writer).await?;
+        writer.shutdown().await?;
+        Ok(())
+    }
+}
+
+impl LuaUserData for ChildProcessReader {
+    fn add_fields<'lua, F: LuaUserDataFields<'lua, Self>>(fields: &mut F) {
+        fields.add_meta_field(LuaMetaMethod::Type, "ChildProcessReader");
+    }
+
+    fn add_methods<'lua, M: LuaUserDataMethods<'lua, Self>>(methods: &mut M) {
+        methods.add_async_method("read", |lua, this, size: Option<usize>| async move {
+            let size = size.unwrap_or(DEFAULT_CHUNK_SIZE).max(1);
+            match this.read_chunk(size).await? {
+                Some(chunk) => Ok(Some(lua.create_string(chunk)?)),
+                None => Ok(None),
+            }
+        });
+        methods.add_async_method(
+            "readLine",
+            |lua, this, keep_newline: Option<bool>| async move {
+                match this.read_line(keep_newline.unwrap_or_default()).await? {
+                    Some(line) => Ok(Some(lua.create_string(line)?)),
+                    None => Ok(None),
+                }
+            },
+        );
+        methods.add_async_method("readToEnd", |lua, this, ()| async move {
+            lua.create_string(this.read_to_end().await?)
+        });
+        methods.add_async_method(
+            "eachChunk",
+            |lua, this, (callback, size): (LuaFunction, Option<usize>)| async move {
+                let size = size.unwrap_or(DEFAULT_CHUNK_SIZE).max(1);
+                while let Some(chunk) = this.read_chunk(size).await? {
+                    if !call_for_each(lua, &callback, lua.create_string(chunk)?).await? {
+                        break;
+                    }
+                }
+                Ok(())
+            },
+        );
+        methods.add_async_method(
+            "eachLine",
+            |lua, this, (callback, keep_newline): (LuaFunction, Option<bool>)| async move {
+                let keep_newline = keep_newline.unwrap_or_default();
+                while let Some(line) = this.read_line(keep_newline).await? {
+                    if !call_for_each(lua, &callback, lua.create_string(line)?).await? {
+                        break;
+                    }
+                }
+                Ok(())
+            },
+        );
+    }
+}
+
+/**
+    Calls an iteration callback in its own thread, so that it may yield,
+    and returns `false` if the callback asked to stop iterating.
+*/
+async fn call_for_each<'lua>(
+    lua: &'lua Lua,
+    callback: &LuaFunction<'lua>,
+    value: LuaString<'lua>,
+) -> LuaResult<bool> {
+    let thread_id = lua.push_thread_back(callback.clone(), value)?;
+    lua.track_thread(thread_id);
+    lua.wait_for_thread(thread_id).await;
+    let values = lua
+        .get_thread_result(thread_id)
+        .expect("Missing iteration callback thread result")?;
+    Ok(!matches!(
+        values.into_iter().next(),
+        Some(LuaValue::Boolean(false))
+    ))
+}
+
+/**
+    The writable input stream of a child process.
+*/
+#[derive(Clone)]
+pub struct ChildProcessWriter {
+    inner: Arc<AsyncMutex<Option<ChildStdin>>>,
+    _guard: ChildProcessGuard,
+}
+
+impl ChildProcessWriter {
+    pub fn new(stdin: ChildStdin, guard: ChildProcessGuard) -> Self {
+        Self {
+            inner: Arc::new(AsyncMutex::new(Some(stdin))),
+            _guard: guard,
+        }
+    }
+
+    pub async fn write(&self, contents: impl AsRef<[u8]>) -> LuaResult<()> {
+        let mut stdin = self.inner.lock().await;
+        match stdin.as_mut() {
+            Some(stdin) => {
+                stdin.write_all(contents.as_ref()).await?;
+                stdin.flush().await?;
+                Ok(())
+            }
+            None => Err(LuaError::runtime("Input stream has already been closed")),
+        }
+    }
+
+    pub async fn close(&self) -> LuaResult<()> {
+        let mut stdin = self.inner.lock().await;
+        if let Some(mut stdin) = stdin.take() {
+            stdin.shutdown().await?;
+        }
+        Ok(())
+    }
+}
+
+impl LuaUserData for ChildProcessWriter {
+    fn add_fields<'lua, F: LuaUserDataFields<'lua, Self>>(fields: &mut F) {
+        fields.add_meta_field(LuaMetaMethod::Type, "ChildProcessWriter");
+    }
+
+    fn add_methods<'lua, M: LuaUserDataMethods<'lua, Self>>(methods: &mut M) {
+        methods.add_async_method("write", |_, this, contents: BString| async move {
+            this.write(contents.as_bytes()).await
+        });
+        methods.add_async_method("close", |_, this, ()| async move { this.close().await });
+    }
+}
@@ -0,0 +1,76 @@
+use mlua::prelude::*;
+use tokio::{process::Child, time};
+
+use super::{options::ProcessKillOptions, signal::ProcessSignal, status::ProcessExitStatus};
+
+/**
+    Sends a signal to a child process that has not yet been waited
+    for, defaulting to killing it, and to its whole process group
+    if it was spawned in one.
+*/
+pub(super) fn signal_child(
+    child: &mut Child,
+    signal: Option<ProcessSignal>,
+    group: bool,
+) -> LuaResult<()> {
+    #[cfg(unix)]
+    {
+        use super::signal::{send_signal, send_signal_to_group};
+
+        let Some(pid) = child.id() else {
+            return Ok(());
+        };
+        let signal = signal.unwrap_or(ProcessSignal::KILL);
+        if group {
+            send_signal_to_group(pid, signal)
+        } else {
+            send_signal(pid, signal)
+        }
+    }
+    #[cfg(not(unix))]
+    {
+        let _ = group;
+        match signal {
+            None | Some(ProcessSignal::KILL) => child.start_kill().into_lua_err(),
+            Some(signal) => Err(LuaError::RuntimeError(format!(
+                "Sending the signal {signal} is only supported on unix platforms"
+            ))),
+        }
+    }
+}
+
+/**
+    Terminates a child process that has run past its timeout.
+
+    If a grace period was given, the process is first asked to exit using
+    `SIGTERM`, and only killed if it has not exited once the grace period is over.
+*/
+pub(super) async fn terminate_child(
+    child: &mut Child,
+    options: ProcessKillOptions,
+) -> LuaResult<ProcessExitStatus> {
+    if cfg!(unix) && !options.grace_period.is_zero() {
+        signal_child(child, Some(ProcessSignal::TERM), options.group)?;
+        if let Ok(status) = time::timeout(options.grace_period, child.wait()).await {
+            return Ok(ProcessExitStatus::from(status?).with_timed_out());
+        }
+    }
+    signal_child(child, None, options.group)?;
+    Ok(ProcessExitStatus::from(child.wait().await?).with_timed_out())
+}
+
+/**
+    Waits for a child process to exit, terminating it if it runs past its timeout.
+*/
+pub(super) async fn wait_or_terminate_child(
+    child: &mut Child,
+    options: ProcessKillOptions,
+) -> LuaResult<ProcessExitStatus> {
+    match options.timeout {
+        None => Ok(child.wait().await?.into()),
+        Some(timeout) => match time::timeout(timeout, child.wait()).await {
+            Ok(status) => Ok(status?.into()),
+            Err(_) => terminate_child(child, options).await,
+        },
+    }
+}
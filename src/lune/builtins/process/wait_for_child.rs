@@ -1,5 +1,3 @@
-use std::process::ExitStatus;
-
 use mlua::prelude::*;
 use tokio::{
     io::{self, AsyncRead, AsyncReadExt},
@@ -7,11 +5,16 @@ use tokio::{
     task,
 };
 
-use super::{options::ProcessSpawnOptionsStdioKind, tee_writer::AsyncTeeWriter};
+use super::{
+    options::{ProcessKillOptions, ProcessSpawnOptionsStdioKind},
+    status::ProcessExitStatus,
+    tee_writer::AsyncTeeWriter,
+    terminate::wait_or_terminate_child,
+};
 
 #[derive(Debug, Clone)]
 pub(super) struct WaitForChildResult {
-    pub status: ExitStatus,
+    pub status: ProcessExitStatus,
     pub stdout: Vec<u8>,
     pub stderr: Vec<u8>,
 }
@@ -26,6 +29,7 @@ where
     Ok(match kind {
         ProcessSpawnOptionsStdioKind::None => Vec::new(),
         ProcessSpawnOptionsStdioKind::Forward => Vec::new(),
+        ProcessSpawnOptionsStdioKind::File { .. } => Vec::new(),
         ProcessSpawnOptionsStdioKind::Default => {
             let mut read_from =
                 read_from.expect("read_from must be Some when stdio kind is Default");
@@ -54,6 +58,7 @@ pub(super) async fn wait_for_child(
     mut child: Child,
     stdout_kind: ProcessSpawnOptionsStdioKind,
     stderr_kind: ProcessSpawnOptionsStdioKind,
+    kill_options: ProcessKillOptions,
 ) -> LuaResult<WaitForChildResult> {
     let stdout_opt = child.stdout.take();
     let stderr_opt = child.stderr.take();
@@ -61,7 +66,7 @@ pub(super) async fn wait_for_child(
     let stdout_task = task::spawn(read_with_stdio_kind(stdout_opt, stdout_kind));
     let stderr_task = task::spawn(read_with_stdio_kind(stderr_opt, stderr_kind));
 
-    let status = child.wait().await.expect("Child process failed to start");
+    let status = wait_or_terminate_child(&mut child, kill_options).await?;
 
     let stdout_buffer = stdout_task.await.into_lua_err()??;
     let stderr_buffer = stderr_task.await.into_lua_err()??;
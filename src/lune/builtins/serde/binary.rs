@@ -0,0 +1,89 @@
+use std::borrow::Cow;
+
+use base64::{
+    alphabet::{Alphabet, STANDARD, URL_SAFE},
+    engine::{DecodePaddingMode, GeneralPurpose, GeneralPurposeConfig},
+    Engine as _,
+};
+use mlua::prelude::*;
+
+/**
+    Options for encoding and decoding binary data as text.
+
+    When `strict` is disabled, decoding ignores whitespace, and accepts
+    base64 both with and without padding. When enabled, input must be
+    exactly what encoding with the same options would have produced.
+*/
+#[derive(Debug, Clone, Copy, Default)]
+pub struct BinaryOptions {
+    pub padding: Option<bool>,
+    pub strict: bool,
+}
+
+impl BinaryOptions {
+    pub fn from_table(table: &LuaTable) -> LuaResult<Self> {
+        Ok(Self {
+            padding: table.get("padding")?,
+            strict: table.get::<_, Option<bool>>("strict")?.unwrap_or_default(),
+        })
+    }
+
+    fn base64_engine(self, url_safe: bool) -> GeneralPurpose {
+        // Url-safe base64 is commonly used without padding, eg. in
+        // tokens and urls, so padding is only enabled by default
+        // for the standard alphabet, matching most other tools
+        let padding = self.padding.unwrap_or(!url_safe);
+        let alphabet: &Alphabet = if url_safe { &URL_SAFE } else { &STANDARD };
+        let padding_mode = match (self.strict, padding) {
+            (false, _) => DecodePaddingMode::Indifferent,
+            (true, true) => DecodePaddingMode::RequireCanonical,
+            (true, false) => DecodePaddingMode::RequireNone,
+        };
+        let config = GeneralPurposeConfig::new()
+            .with_encode_padding(padding)
+            .with_decode_padding_mode(padding_mode)
+            .with_decode_allow_trailing_bits(!self.strict);
+        GeneralPurpose::new(alphabet, config)
+    }
+
+    fn prepare_input<'a>(self, input: &'a [u8]) -> Cow<'a, [u8]> {
+        if self.strict {
+            input.into()
+        } else {
+            input
+                .iter()
+                .copied()
+                .filter(|byte| !byte.is_ascii_whitespace())
+                .collect::<Vec<_>>()
+                .into()
+        }
+    }
+}
+
+pub fn encode_base64(bytes: impl AsRef<[u8]>, url_safe: bool, options: BinaryOptions) -> Vec<u8> {
+    options
+        .base64_engine(url_safe)
+        .encode(bytes.as_ref())
+        .into_bytes()
+}
+
+pub fn decode_base64(
+    input: impl AsRef<[u8]>,
+    url_safe: bool,
+    options: BinaryOptions,
+) -> LuaResult<Vec<u8>> {
+    let input = options.prepare_input(input.as_ref());
+    options
+        .base64_engine(url_safe)
+        .decode(input)
+        .map_err(|e| LuaError::RuntimeError(format!("Invalid base64 - {e}")))
+}
+
+pub fn encode_hex(bytes: impl AsRef<[u8]>) -> Vec<u8> {
+    hex::encode(bytes.as_ref()).into_bytes()
+}
+
+pub fn decode_hex(input: impl AsRef<[u8]>, options: BinaryOptions) -> LuaResult<Vec<u8>> {
+    let input = options.prepare_input(input.as_ref());
+    hex::decode(input).map_err(|e| LuaError::RuntimeError(format!("Invalid hex - {e}")))
+}
@@ -5,9 +5,10 @@ use tokio::io::{copy, BufReader};
 
 use async_compression::{
     tokio::bufread::{
-        BrotliDecoder, BrotliEncoder, GzipDecoder, GzipEncoder, ZlibDecoder, ZlibEncoder,
+        BrotliDecoder, BrotliEncoder, BzDecoder, BzEncoder, GzipDecoder, GzipEncoder, XzDecoder,
+        XzEncoder, ZlibDecoder, ZlibEncoder, ZstdDecoder, ZstdEncoder,
     },
-    Level::Best as CompressionQuality,
+    Level,
 };
 
 #[derive(Debug, Clone, Copy)]
@@ -16,10 +17,25 @@ pub enum CompressDecompressFormat {
     GZip,
     LZ4,
     ZLib,
+    Zstd,
+    Xz,
+    Bzip2,
 }
 
 #[allow(dead_code)]
 impl CompressDecompressFormat {
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Brotli => "brotli",
+            Self::GZip => "gzip",
+            Self::LZ4 => "lz4",
+            Self::ZLib => "zlib",
+            Self::Zstd => "zstd",
+            Self::Xz => "xz",
+            Self::Bzip2 => "bzip2",
+        }
+    }
+
     pub fn detect_from_bytes(bytes: impl AsRef<[u8]>) -> Option<Self> {
         match bytes.as_ref() {
             // https://github.com/PSeitz/lz4_flex/blob/main/src/frame/header.rs#L28
@@ -40,6 +56,16 @@ impl CompressDecompressFormat {
             {
                 Some(Self::Brotli)
             }
+            // https://datatracker.ietf.org/doc/html/rfc8878#section-3.1.1
+            b if b.len() >= 4 && matches!(b[0..4], [0x28, 0xB5, 0x2F, 0xFD]) => Some(Self::Zstd),
+            // https://tukaani.org/xz/xz-file-format.txt
+            b if b.len() >= 6 && matches!(b[0..6], [0xFD, 0x37, 0x7A, 0x58, 0x5A, 0x00]) => {
+                Some(Self::Xz)
+            }
+            // https://en.wikipedia.org/wiki/Bzip2#File_format
+            b if b.len() >= 4 && matches!(b[0..4], [b'B', b'Z', b'h', b'1'..=b'9']) => {
+                Some(Self::Bzip2)
+            }
             // https://github.com/rust-lang/flate2-rs/blob/main/src/gz/mod.rs#L135
             b if b.len() >= 3 && matches!(b[0..3], [0x1F, 0x8B, 0x08]) => Some(Self::GZip),
             // https://stackoverflow.com/a/43170354
@@ -61,6 +87,9 @@ impl CompressDecompressFormat {
             "br" | "brotli" => Some(Self::Brotli),
             "deflate" => Some(Self::ZLib),
             "gz" | "gzip" => Some(Self::GZip),
+            "zstd" => Some(Self::Zstd),
+            "xz" => Some(Self::Xz),
+            "bz2" | "bzip2" => Some(Self::Bzip2),
             _ => None,
         }
     }
@@ -74,11 +103,14 @@ impl<'lua> FromLua<'lua> for CompressDecompressFormat {
                 "gzip" => Ok(Self::GZip),
                 "lz4" => Ok(Self::LZ4),
                 "zlib" => Ok(Self::ZLib),
+                "zstd" => Ok(Self::Zstd),
+                "xz" => Ok(Self::Xz),
+                "bzip2" => Ok(Self::Bzip2),
                 kind => Err(LuaError::FromLuaConversionError {
                     from: value.type_name(),
                     to: "CompressDecompressFormat",
                     message: Some(format!(
-                        "Invalid format '{kind}', valid formats are:  brotli, gzip, lz4, zlib"
+                        "Invalid format '{kind}', valid formats are:  brotli, gzip, lz4, zlib, zstd, xz, bzip2"
                     )),
                 }),
             }
@@ -92,10 +124,78 @@ impl<'lua> FromLua<'lua> for CompressDecompressFormat {
     }
 }
 
-pub async fn compress<'lua>(
+/**
+    Options for compressing and decompressing.
+
+    The compression level is interpreted by each format, and is
+    clamped to the range that format supports. Dictionaries are
+    currently only supported by the zstd format.
+*/
+#[derive(Debug, Clone, Default)]
+pub struct CompressDecompressOptions {
+    pub level: Option<i32>,
+    pub dictionary: Option<Vec<u8>>,
+}
+
+impl CompressDecompressOptions {
+    pub fn level(&self) -> Level {
+        match self.level {
+            Some(level) => Level::Precise(level),
+            None => Level::Best,
+        }
+    }
+
+    pub fn validate_for(&self, format: CompressDecompressFormat) -> LuaResult<()> {
+        if self.dictionary.is_some() && !matches!(format, CompressDecompressFormat::Zstd) {
+            return Err(LuaError::RuntimeError(format!(
+                "Invalid option 'dictionary' - dictionaries are only supported for zstd, got {}",
+                format.name()
+            )));
+        }
+        Ok(())
+    }
+}
+
+impl<'lua> FromLua<'lua> for CompressDecompressOptions {
+    fn from_lua(value: LuaValue<'lua>, lua: &'lua Lua) -> LuaResult<Self> {
+        match value {
+            LuaValue::Nil => Ok(Self::default()),
+            LuaValue::Integer(_) | LuaValue::Number(_) => Ok(Self {
+                level: Some(i32::from_lua(value, lua)?),
+                dictionary: None,
+            }),
+            LuaValue::Table(t) => Ok(Self {
+                level: t.get("level")?,
+                dictionary: t
+                    .get::<_, Option<LuaString>>("dictionary")?
+                    .map(|s| s.as_bytes().to_vec()),
+            }),
+            value => Err(LuaError::FromLuaConversionError {
+                from: value.type_name(),
+                to: "CompressDecompressOptions",
+                message: Some(format!(
+                    "Invalid options - expected number or table, got {}",
+                    value.type_name()
+                )),
+            }),
+        }
+    }
+}
+
+pub async fn compress(
+    format: CompressDecompressFormat,
+    source: impl AsRef<[u8]>,
+) -> LuaResult<Vec<u8>> {
+    compress_with_options(format, source, &CompressDecompressOptions::default()).await
+}
+
+pub async fn compress_with_options(
     format: CompressDecompressFormat,
     source: impl AsRef<[u8]>,
+    options: &CompressDecompressOptions,
 ) -> LuaResult<Vec<u8>> {
+    options.validate_for(format)?;
+
     if let CompressDecompressFormat::LZ4 = format {
         let source = source.as_ref().to_vec();
         return Ok(blocking::unblock(move || compress_prepend_size(&source)).await);
@@ -103,18 +203,34 @@ pub async fn compress<'lua>(
 
     let mut bytes = Vec::new();
     let reader = BufReader::new(source.as_ref());
+    let level = options.level();
 
     match format {
         CompressDecompressFormat::Brotli => {
-            let mut encoder = BrotliEncoder::with_quality(reader, CompressionQuality);
+            let mut encoder = BrotliEncoder::with_quality(reader, level);
             copy(&mut encoder, &mut bytes).await?;
         }
         CompressDecompressFormat::GZip => {
-            let mut encoder = GzipEncoder::with_quality(reader, CompressionQuality);
+            let mut encoder = GzipEncoder::with_quality(reader, level);
             copy(&mut encoder, &mut bytes).await?;
         }
         CompressDecompressFormat::ZLib => {
-            let mut encoder = ZlibEncoder::with_quality(reader, CompressionQuality);
+            let mut encoder = ZlibEncoder::with_quality(reader, level);
+            copy(&mut encoder, &mut bytes).await?;
+        }
+        CompressDecompressFormat::Zstd => {
+            let mut encoder = match &options.dictionary {
+                Some(dictionary) => ZstdEncoder::with_dict(reader, level, dictionary)?,
+                None => ZstdEncoder::with_quality(reader, level),
+            };
+            copy(&mut encoder, &mut bytes).await?;
+        }
+        CompressDecompressFormat::Xz => {
+            let mut encoder = XzEncoder::with_quality(reader, level);
+            copy(&mut encoder, &mut bytes).await?;
+        }
+        CompressDecompressFormat::Bzip2 => {
+            let mut encoder = BzEncoder::with_quality(reader, level);
             copy(&mut encoder, &mut bytes).await?;
         }
         CompressDecompressFormat::LZ4 => unreachable!(),
@@ -123,10 +239,20 @@ pub async fn compress<'lua>(
     Ok(bytes)
 }
 
-pub async fn decompress<'lua>(
+pub async fn decompress(
+    format: CompressDecompressFormat,
+    source: impl AsRef<[u8]>,
+) -> LuaResult<Vec<u8>> {
+    decompress_with_options(format, source, &CompressDecompressOptions::default()).await
+}
+
+pub async fn decompress_with_options(
     format: CompressDecompressFormat,
     source: impl AsRef<[u8]>,
+    options: &CompressDecompressOptions,
 ) -> LuaResult<Vec<u8>> {
+    options.validate_for(format)?;
+
     if let CompressDecompressFormat::LZ4 = format {
         let source = source.as_ref().to_vec();
         return blocking::unblock(move || decompress_size_prepended(&source))
@@ -150,6 +276,21 @@ pub async fn decompress<'lua>(
             let mut decoder = ZlibDecoder::new(reader);
             copy(&mut decoder, &mut bytes).await?;
         }
+        CompressDecompressFormat::Zstd => {
+            let mut decoder = match &options.dictionary {
+                Some(dictionary) => ZstdDecoder::with_dict(reader, dictionary)?,
+                None => ZstdDecoder::new(reader),
+            };
+            copy(&mut decoder, &mut bytes).await?;
+        }
+        CompressDecompressFormat::Xz => {
+            let mut decoder = XzDecoder::new(reader);
+            copy(&mut decoder, &mut bytes).await?;
+        }
+        CompressDecompressFormat::Bzip2 => {
+            let mut decoder = BzDecoder::new(reader);
+            copy(&mut decoder, &mut bytes).await?;
+        }
         CompressDecompressFormat::LZ4 => unreachable!(),
     }
 
@@ -0,0 +1,194 @@
+use std::{
+    pin::Pin,
+    sync::{Arc, Mutex},
+    task::{Context, Poll},
+};
+
+use bstr::BString;
+use mlua::prelude::*;
+use tokio::{
+    io::{self, AsyncWrite, AsyncWriteExt},
+    sync::Mutex as AsyncMutex,
+};
+
+use async_compression::tokio::write::{
+    BrotliDecoder, BrotliEncoder, BzDecoder, BzEncoder, GzipDecoder, GzipEncoder, XzDecoder,
+    XzEncoder, ZlibDecoder, ZlibEncoder, ZstdDecoder, ZstdEncoder,
+};
+
+use super::compress_decompress::{CompressDecompressFormat, CompressDecompressOptions};
+
+type BoxedWriter = Box<dyn AsyncWrite + Send + Unpin>;
+
+/**
+    An in-memory buffer that encoders and decoders write their output into.
+
+    Output is taken out of the buffer after each write to the stream,
+    so that it never holds more than what a single write produced.
+*/
+#[derive(Debug, Clone, Default)]
+struct SharedBuffer {
+    inner: Arc<Mutex<Vec<u8>>>,
+}
+
+impl SharedBuffer {
+    fn take(&self) -> Vec<u8> {
+        std::mem::take(&mut *self.inner.lock().expect("Buffer lock was poisoned"))
+    }
+}
+
+impl AsyncWrite for SharedBuffer {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        _: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        let mut inner = self.inner.lock().expect("Buffer lock was poisoned");
+        inner.extend_from_slice(buf);
+        Poll::Ready(Ok(buf.len()))
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, _: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, _: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Poll::Ready(Ok(()))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CompressStreamKind {
+    Compress,
+    Decompress,
+}
+
+/**
+    A streaming compressor or decompressor.
+
+    Chunks of input are written to the stream one at a time, and each
+    write returns whatever output the format has produced so far, which
+    makes it possible to process inputs that are too large to buffer.
+*/
+#[derive(Clone)]
+pub struct CompressStream {
+    kind: CompressStreamKind,
+    format: CompressDecompressFormat,
+    writer: Arc<AsyncMutex<Option<BoxedWriter>>>,
+    output: SharedBuffer,
+}
+
+impl CompressStream {
+    pub fn new(
+        kind: CompressStreamKind,
+        format: CompressDecompressFormat,
+        options: &CompressDecompressOptions,
+    ) -> LuaResult<Self> {
+        options.validate_for(format)?;
+
+        let output = SharedBuffer::default();
+        let inner = output.clone();
+        let level = options.level();
+
+        let writer: BoxedWriter = match (kind, format) {
+            (_, CompressDecompressFormat::LZ4) => {
+                return Err(LuaError::RuntimeError(
+                    "Streaming is not supported for the lz4 format".to_string(),
+                ))
+            }
+            (CompressStreamKind::Compress, CompressDecompressFormat::Brotli) => {
+                Box::new(BrotliEncoder::with_quality(inner, level))
+            }
+            (CompressStreamKind::Compress, CompressDecompressFormat::GZip) => {
+                Box::new(GzipEncoder::with_quality(inner, level))
+            }
+            (CompressStreamKind::Compress, CompressDecompressFormat::ZLib) => {
+                Box::new(ZlibEncoder::with_quality(inner, level))
+            }
+            (CompressStreamKind::Compress, CompressDecompressFormat::Zstd) => {
+                match &options.dictionary {
+                    Some(dictionary) => Box::new(ZstdEncoder::with_dict(inner, level, dictionary)?),
+                    None => Box::new(ZstdEncoder::with_quality(inner, level)),
+                }
+            }
+            (CompressStreamKind::Compress, CompressDecompressFormat::Xz) => {
+                Box::new(XzEncoder::with_quality(inner, level))
+            }
+            (CompressStreamKind::Compress, CompressDecompressFormat::Bzip2) => {
+                Box::new(BzEncoder::with_quality(inner, level))
+            }
+            (CompressStreamKind::Decompress, CompressDecompressFormat::Brotli) => {
+                Box::new(BrotliDecoder::new(inner))
+            }
+            (CompressStreamKind::Decompress, CompressDecompressFormat::GZip) => {
+                Box::new(GzipDecoder::new(inner))
+            }
+            (CompressStreamKind::Decompress, CompressDecompressFormat::ZLib) => {
+                Box::new(ZlibDecoder::new(inner))
+            }
+            (CompressStreamKind::Decompress, CompressDecompressFormat::Zstd) => {
+                match &options.dictionary {
+                    Some(dictionary) => Box::new(ZstdDecoder::with_dict(inner, dictionary)?),
+                    None => Box::new(ZstdDecoder::new(inner)),
+                }
+            }
+            (CompressStreamKind::Decompress, CompressDecompressFormat::Xz) => {
+                Box::new(XzDecoder::new(inner))
+            }
+            (CompressStreamKind::Decompress, CompressDecompressFormat::Bzip2) => {
+                Box::new(BzDecoder::new(inner))
+            }
+        };
+
+        Ok(Self {
+            kind,
+            format,
+            writer: Arc::new(AsyncMutex::new(Some(writer))),
+            output,
+        })
+    }
+
+    pub async fn write(&self, chunk: impl AsRef<[u8]>) -> LuaResult<Vec<u8>> {
+        let mut writer = self.writer.lock().await;
+        match writer.as_mut() {
+            Some(writer) => {
+                writer.write_all(chunk.as_ref()).await?;
+                Ok(self.output.take())
+            }
+            None => Err(LuaError::runtime("Stream has already been finished")),
+        }
+    }
+
+    pub async fn finish(&self) -> LuaResult<Vec<u8>> {
+        let mut writer = self.writer.lock().await;
+        match writer.take() {
+            Some(mut writer) => {
+                writer.shutdown().await?;
+                Ok(self.output.take())
+            }
+            None => Err(LuaError::runtime("Stream has already been finished")),
+        }
+    }
+}
+
+impl LuaUserData for CompressStream {
+    fn add_fields<'lua, F: LuaUserDataFields<'lua, Self>>(fields: &mut F) {
+        fields.add_meta_field(LuaMetaMethod::Type, "CompressStream");
+        fields.add_field_method_get("format", |_, this| Ok(this.format.name()));
+        fields.add_field_method_get("kind", |_, this| {
+            Ok(match this.kind {
+                CompressStreamKind::Compress => "compress",
+                CompressStreamKind::Decompress => "decompress",
+            })
+        });
+    }
+
+    fn add_methods<'lua, M: LuaUserDataMethods<'lua, Self>>(methods: &mut M) {
+        methods.add_async_method("write", |lua, this, chunk: BString| async move {
+            lua.create_string(this.write(chunk).await?)
+        });
+        methods.add_async_method("finish", |lua, this, ()| async move {
+            lua.create_string(this.finish().await?)
+        });
+    }
+}
@@ -0,0 +1,267 @@
+use std::collections::BTreeSet;
+
+use csv::{QuoteStyle, ReaderBuilder, WriterBuilder};
+use mlua::prelude::*;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum CsvQuoteStyle {
+    #[default]
+    Necessary,
+    Always,
+    NonNumeric,
+    Never,
+}
+
+impl CsvQuoteStyle {
+    const NAMED_STYLES: &'static [(&'static str, Self)] = &[
+        ("necessary", Self::Necessary),
+        ("always", Self::Always),
+        ("nonnumeric", Self::NonNumeric),
+        ("never", Self::Never),
+    ];
+}
+
+impl From<CsvQuoteStyle> for QuoteStyle {
+    fn from(style: CsvQuoteStyle) -> Self {
+        match style {
+            CsvQuoteStyle::Necessary => QuoteStyle::Necessary,
+            CsvQuoteStyle::Always => QuoteStyle::Always,
+            CsvQuoteStyle::NonNumeric => QuoteStyle::NonNumeric,
+            CsvQuoteStyle::Never => QuoteStyle::Never,
+        }
+    }
+}
+
+impl<'lua> FromLua<'lua> for CsvQuoteStyle {
+    fn from_lua(value: LuaValue<'lua>, _: &'lua Lua) -> LuaResult<Self> {
+        if let LuaValue::String(s) = &value {
+            let name = s.to_string_lossy().to_ascii_lowercase();
+            Self::NAMED_STYLES
+                .iter()
+                .find(|(style_name, _)| *style_name == name.trim())
+                .map(|(_, style)| *style)
+                .ok_or_else(|| LuaError::FromLuaConversionError {
+                    from: value.type_name(),
+                    to: "CsvQuoteStyle",
+                    message: Some(format!(
+                        "Invalid quote style '{name}', valid styles are:  {}",
+                        Self::NAMED_STYLES
+                            .iter()
+                            .map(|(style_name, _)| *style_name)
+                            .collect::<Vec<_>>()
+                            .join(", ")
+                    )),
+                })
+        } else {
+            Err(LuaError::FromLuaConversionError {
+                from: value.type_name(),
+                to: "CsvQuoteStyle",
+                message: None,
+            })
+        }
+    }
+}
+
+/**
+    Options for reading and writing CSV.
+
+    When `header` is enabled, the first row is treated as column names and
+    each remaining row maps to a dictionary keyed by those names.
+*/
+#[derive(Debug, Clone)]
+pub struct CsvOptions {
+    pub header: bool,
+    pub delimiter: u8,
+    pub quote: u8,
+    pub quote_style: CsvQuoteStyle,
+    pub columns: Option<Vec<String>>,
+}
+
+impl Default for CsvOptions {
+    fn default() -> Self {
+        Self {
+            header: true,
+            delimiter: b',',
+            quote: b'"',
+            quote_style: CsvQuoteStyle::default(),
+            columns: None,
+        }
+    }
+}
+
+fn parse_single_byte(value: Option<LuaString>, name: &str, default: u8) -> LuaResult<u8> {
+    match value {
+        None => Ok(default),
+        Some(s) => match s.as_bytes() {
+            [byte] => Ok(*byte),
+            bytes => Err(LuaError::RuntimeError(format!(
+                "Invalid option '{name}' - expected a single character, got '{}'",
+                String::from_utf8_lossy(bytes)
+            ))),
+        },
+    }
+}
+
+impl CsvOptions {
+    pub fn from_table(lua: &Lua, table: &LuaTable) -> LuaResult<Self> {
+        let defaults = Self::default();
+        let header = table.get::<_, Option<bool>>("header")?;
+        let delimiter = table.get::<_, Option<LuaString>>("delimiter")?;
+        let quote = table.get::<_, Option<LuaString>>("quote")?;
+        let quote_style = match table.get::<_, LuaValue>("quoteStyle")? {
+            LuaValue::Nil => defaults.quote_style,
+            value => CsvQuoteStyle::from_lua(value, lua)?,
+        };
+        let columns = table.get::<_, Option<Vec<String>>>("columns")?;
+        Ok(Self {
+            header: header.unwrap_or(defaults.header),
+            delimiter: parse_single_byte(delimiter, "delimiter", defaults.delimiter)?,
+            quote: parse_single_byte(quote, "quote", defaults.quote)?,
+            quote_style,
+            columns,
+        })
+    }
+}
+
+fn field_to_bytes(value: LuaValue) -> LuaResult<Vec<u8>> {
+    Ok(match value {
+        LuaValue::Nil => Vec::new(),
+        LuaValue::Boolean(b) => b.to_string().into_bytes(),
+        LuaValue::Integer(i) => i.to_string().into_bytes(),
+        LuaValue::Number(n) if n.is_finite() && n.fract() == 0.0 && n.abs() < 2f64.powi(53) => {
+            (n as i64).to_string().into_bytes()
+        }
+        LuaValue::Number(n) => n.to_string().into_bytes(),
+        LuaValue::String(s) => s.as_bytes().to_vec(),
+        value => {
+            return Err(LuaError::RuntimeError(format!(
+                "Invalid CSV field - expected string, number or boolean, got {}",
+                value.type_name()
+            )))
+        }
+    })
+}
+
+pub fn encode_csv(value: LuaValue, options: &CsvOptions) -> LuaResult<Vec<u8>> {
+    let rows = match value {
+        LuaValue::Table(t) => t
+            .sequence_values::<LuaValue>()
+            .map(|row| match row? {
+                LuaValue::Table(row) => Ok(row),
+                row => Err(LuaError::RuntimeError(format!(
+                    "Invalid CSV row - expected table, got {}",
+                    row.type_name()
+                ))),
+            })
+            .collect::<LuaResult<Vec<_>>>()?,
+        value => {
+            return Err(LuaError::RuntimeError(format!(
+                "Invalid CSV - expected an array of rows, got {}",
+                value.type_name()
+            )))
+        }
+    };
+
+    // Dictionary rows are written using either the given columns or
+    // all of the keys found in them, sorted, so that output is stable
+    let columns = match &options.columns {
+        Some(columns) => columns.clone(),
+        None => {
+            let mut keys = BTreeSet::new();
+            for row in rows.iter().filter(|row| row.raw_len() == 0) {
+                for pair in row.clone().pairs::<LuaValue, LuaValue>() {
+                    let (key, _) = pair?;
+                    match key {
+                        LuaValue::String(s) => keys.insert(s.to_str()?.to_string()),
+                        key => {
+                            return Err(LuaError::RuntimeError(format!(
+                                "Invalid CSV column - expected string, got {}",
+                                key.type_name()
+                            )))
+                        }
+                    };
+                }
+            }
+            keys.into_iter().collect()
+        }
+    };
+
+    let mut writer = WriterBuilder::new()
+        .delimiter(options.delimiter)
+        .quote(options.quote)
+        .quote_style(options.quote_style.into())
+        .flexible(true)
+        .from_writer(Vec::new());
+
+    if options.header && !columns.is_empty() {
+        writer.write_record(&columns).into_lua_err()?;
+    }
+
+    for row in rows {
+        let fields = if row.raw_len() > 0 {
+            row.sequence_values::<LuaValue>()
+                .map(|field| field_to_bytes(field?))
+                .collect::<LuaResult<Vec<_>>>()?
+        } else {
+            columns
+                .iter()
+                .map(|column| field_to_bytes(row.raw_get(column.as_str())?))
+                .collect::<LuaResult<Vec<_>>>()?
+        };
+        writer.write_record(&fields).into_lua_err()?;
+    }
+
+    writer
+        .into_inner()
+        .map_err(|e| e.into_error())
+        .into_lua_err()
+}
+
+pub fn decode_csv<'lua>(
+    lua: &'lua Lua,
+    bytes: &[u8],
+    options: &CsvOptions,
+) -> LuaResult<LuaValue<'lua>> {
+    let mut reader = ReaderBuilder::new()
+        .has_headers(options.header)
+        .delimiter(options.delimiter)
+        .quote(options.quote)
+        .quoting(options.quote_style != CsvQuoteStyle::Never)
+        .flexible(true)
+        .from_reader(bytes);
+
+    let columns = if options.header {
+        let headers = reader.byte_headers().into_lua_err()?;
+        let columns = headers
+            .iter()
+            .map(|column| lua.create_string(column))
+            .collect::<LuaResult<Vec<_>>>()?;
+        Some(columns)
+    } else {
+        None
+    };
+
+    let rows = lua.create_table()?;
+    for record in reader.byte_records() {
+        let record = record.into_lua_err()?;
+        let row = match &columns {
+            Some(columns) => {
+                let row = lua.create_table_with_capacity(0, columns.len())?;
+                for (column, field) in columns.iter().zip(record.iter()) {
+                    row.raw_set(column.clone(), lua.create_string(field)?)?;
+                }
+                row
+            }
+            None => {
+                let row = lua.create_table_with_capacity(record.len(), 0)?;
+                for field in record.iter() {
+                    row.raw_push(lua.create_string(field)?)?;
+                }
+                row
+            }
+        };
+        rows.raw_push(row)?;
+    }
+
+    Ok(LuaValue::Table(rows))
+}
@@ -0,0 +1,206 @@
+use std::{fmt::Write as _, sync::Arc};
+
+use jsonschema::JSONSchema;
+use mlua::prelude::*;
+
+use ciborium::Value as CborValue;
+use serde_json::Value as JsonValue;
+use serde_yaml::Value as YamlValue;
+use toml::Value as TomlValue;
+
+// Integers with a larger magnitude than this can not be
+// represented exactly by a double, which Luau uses for numbers
+const MAX_SAFE_INTEGER: i128 = (1 << 53) - 1;
+
+fn is_safe_integer(value: impl Into<i128>) -> bool {
+    value.into().abs() <= MAX_SAFE_INTEGER
+}
+
+/**
+    Options that change how decoded values are represented in Luau.
+
+    All options are disabled by default, which keeps decoded
+    values as plain tables, numbers, strings and booleans.
+*/
+#[derive(Debug, Clone, Default)]
+pub struct DecodeOptions {
+    pub big_integers_as_strings: bool,
+    pub mark_arrays: bool,
+    pub preserve_null: bool,
+    pub schema: Option<Arc<JSONSchema>>,
+}
+
+impl DecodeOptions {
+    pub fn from_table<'lua>(
+        lua: &'lua Lua,
+        table: &LuaTable<'lua>,
+        deserialize_options: LuaDeserializeOptions,
+    ) -> LuaResult<Self> {
+        let schema = match table.get::<_, LuaValue>("schema")? {
+            LuaValue::Nil => None,
+            LuaValue::String(s) => {
+                let schema: JsonValue = serde_json::from_slice(s.as_bytes()).map_err(|e| {
+                    LuaError::RuntimeError(format!("Invalid option 'schema' - {e}"))
+                })?;
+                Some(compile_schema(&schema)?)
+            }
+            LuaValue::Table(t) => {
+                let schema: JsonValue =
+                    lua.from_value_with(LuaValue::Table(t), deserialize_options)?;
+                Some(compile_schema(&schema)?)
+            }
+            value => {
+                return Err(LuaError::RuntimeError(format!(
+                    "Invalid option 'schema' - expected table or string, got {}",
+                    value.type_name()
+                )))
+            }
+        };
+        Ok(Self {
+            big_integers_as_strings: table
+                .get::<_, Option<bool>>("bigIntegersAsStrings")?
+                .unwrap_or_default(),
+            mark_arrays: table
+                .get::<_, Option<bool>>("markArrays")?
+                .unwrap_or_default(),
+            preserve_null: table
+                .get::<_, Option<bool>>("preserveNull")?
+                .unwrap_or_default(),
+            schema,
+        })
+    }
+
+    pub fn lua_serialize_options(&self, base: LuaSerializeOptions) -> LuaSerializeOptions {
+        base.set_array_metatable(self.mark_arrays)
+            .serialize_none_to_null(self.preserve_null)
+            .serialize_unit_to_null(self.preserve_null)
+    }
+
+    /**
+        Validates the given value against the schema, if any.
+
+        Errors list every failed validation along with
+        the path to the value that failed it, if any.
+    */
+    pub fn validate(&self, value: &JsonValue) -> LuaResult<()> {
+        let Some(schema) = &self.schema else {
+            return Ok(());
+        };
+        if let Err(errors) = schema.validate(value) {
+            let mut message = String::from("Decoded value does not match schema");
+            for error in errors {
+                let path = error.instance_path.to_string();
+                let path = if path.is_empty() { "/" } else { path.as_str() };
+                write!(message, "\n    at '{path}': {error}").unwrap();
+            }
+            return Err(LuaError::RuntimeError(message));
+        }
+        Ok(())
+    }
+
+    pub fn validate_serializable(&self, value: &impl serde::Serialize) -> LuaResult<()> {
+        if self.schema.is_some() {
+            self.validate(&serde_json::to_value(value).into_lua_err()?)
+        } else {
+            Ok(())
+        }
+    }
+
+    pub fn check_schema_supported(&self, format_name: &str) -> LuaResult<()> {
+        if self.schema.is_some() {
+            Err(LuaError::RuntimeError(format!(
+                "Invalid option 'schema' - schemas are not supported for {format_name}"
+            )))
+        } else {
+            Ok(())
+        }
+    }
+
+    pub fn json(&self, value: JsonValue) -> JsonValue {
+        if !self.big_integers_as_strings {
+            return value;
+        }
+        match value {
+            JsonValue::Number(n) => match (n.as_i64(), n.as_u64()) {
+                (Some(i), _) if !is_safe_integer(i) => JsonValue::String(i.to_string()),
+                (_, Some(u)) if !is_safe_integer(u) => JsonValue::String(u.to_string()),
+                _ => JsonValue::Number(n),
+            },
+            JsonValue::Array(values) => {
+                JsonValue::Array(values.into_iter().map(|v| self.json(v)).collect())
+            }
+            JsonValue::Object(map) => {
+                JsonValue::Object(map.into_iter().map(|(k, v)| (k, self.json(v))).collect())
+            }
+            value => value,
+        }
+    }
+
+    pub fn yaml(&self, value: YamlValue) -> YamlValue {
+        if !self.big_integers_as_strings {
+            return value;
+        }
+        match value {
+            YamlValue::Number(n) => match (n.as_i64(), n.as_u64()) {
+                (Some(i), _) if !is_safe_integer(i) => YamlValue::String(i.to_string()),
+                (_, Some(u)) if !is_safe_integer(u) => YamlValue::String(u.to_string()),
+                _ => YamlValue::Number(n),
+            },
+            YamlValue::Sequence(values) => {
+                YamlValue::Sequence(values.into_iter().map(|v| self.yaml(v)).collect())
+            }
+            YamlValue::Mapping(map) => {
+                YamlValue::Mapping(map.into_iter().map(|(k, v)| (k, self.yaml(v))).collect())
+            }
+            YamlValue::Tagged(tagged) => {
+                let mut tagged = *tagged;
+                tagged.value = self.yaml(tagged.value);
+                YamlValue::Tagged(Box::new(tagged))
+            }
+            value => value,
+        }
+    }
+
+    pub fn toml(&self, value: TomlValue) -> TomlValue {
+        if !self.big_integers_as_strings {
+            return value;
+        }
+        match value {
+            TomlValue::Integer(i) if !is_safe_integer(i) => TomlValue::String(i.to_string()),
+            TomlValue::Array(values) => {
+                TomlValue::Array(values.into_iter().map(|v| self.toml(v)).collect())
+            }
+            TomlValue::Table(map) => {
+                TomlValue::Table(map.into_iter().map(|(k, v)| (k, self.toml(v))).collect())
+            }
+            value => value,
+        }
+    }
+
+    pub fn cbor(&self, value: CborValue) -> CborValue {
+        if !self.big_integers_as_strings {
+            return value;
+        }
+        match value {
+            CborValue::Integer(i) if !is_safe_integer(i) => {
+                CborValue::Text(i128::from(i).to_string())
+            }
+            CborValue::Array(values) => {
+                CborValue::Array(values.into_iter().map(|v| self.cbor(v)).collect())
+            }
+            CborValue::Map(pairs) => CborValue::Map(
+                pairs
+                    .into_iter()
+                    .map(|(k, v)| (self.cbor(k), self.cbor(v)))
+                    .collect(),
+            ),
+            value => value,
+        }
+    }
+}
+
+fn compile_schema(schema: &JsonValue) -> LuaResult<Arc<JSONSchema>> {
+    JSONSchema::compile(schema)
+        .map(Arc::new)
+        .map_err(|e| LuaError::RuntimeError(format!("Invalid option 'schema' - {e}")))
+}
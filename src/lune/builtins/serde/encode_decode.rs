@@ -1,10 +1,17 @@
 use bstr::{BString, ByteSlice};
 use mlua::prelude::*;
 
+use ciborium::Value as CborValue;
 use serde_json::Value as JsonValue;
 use serde_yaml::Value as YamlValue;
 use toml::Value as TomlValue;
 
+use super::{
+    binary::{decode_base64, decode_hex, encode_base64, encode_hex, BinaryOptions},
+    csv::{decode_csv, encode_csv, CsvOptions},
+    decode_options::DecodeOptions,
+};
+
 const LUA_SERIALIZE_OPTIONS: LuaSerializeOptions = LuaSerializeOptions::new()
     .set_array_metatable(false)
     .serialize_none_to_null(false)
@@ -20,6 +27,12 @@ pub enum EncodeDecodeFormat {
     Json,
     Yaml,
     Toml,
+    MsgPack,
+    Cbor,
+    Csv,
+    Base64,
+    Base64Url,
+    Hex,
 }
 
 impl<'lua> FromLua<'lua> for EncodeDecodeFormat {
@@ -29,11 +42,17 @@ impl<'lua> FromLua<'lua> for EncodeDecodeFormat {
                 "json" => Ok(Self::Json),
                 "yaml" => Ok(Self::Yaml),
                 "toml" => Ok(Self::Toml),
+                "msgpack" | "messagepack" => Ok(Self::MsgPack),
+                "cbor" => Ok(Self::Cbor),
+                "csv" => Ok(Self::Csv),
+                "base64" => Ok(Self::Base64),
+                "base64url" => Ok(Self::Base64Url),
+                "hex" => Ok(Self::Hex),
                 kind => Err(LuaError::FromLuaConversionError {
                     from: value.type_name(),
                     to: "EncodeDecodeFormat",
                     message: Some(format!(
-                        "Invalid format '{kind}', valid formats are:  json, yaml, toml"
+                        "Invalid format '{kind}', valid formats are:  json, yaml, toml, msgpack, cbor, csv, base64, base64url, hex"
                     )),
                 }),
             }
@@ -47,10 +66,53 @@ impl<'lua> FromLua<'lua> for EncodeDecodeFormat {
     }
 }
 
-#[derive(Debug, Clone, Copy)]
+/**
+    Options given as the last argument to `serde.encode` and `serde.decode`.
+
+    This may be a boolean, for backwards compatibility with the
+    `pretty` argument, or a table with format-specific options.
+*/
+#[derive(Debug, Clone, Default)]
+pub struct EncodeDecodeOptions {
+    pub pretty: bool,
+    pub csv: CsvOptions,
+    pub binary: BinaryOptions,
+    pub decode: DecodeOptions,
+}
+
+impl<'lua> FromLua<'lua> for EncodeDecodeOptions {
+    fn from_lua(value: LuaValue<'lua>, lua: &'lua Lua) -> LuaResult<Self> {
+        match value {
+            LuaValue::Nil => Ok(Self::default()),
+            LuaValue::Boolean(pretty) => Ok(Self {
+                pretty,
+                ..Self::default()
+            }),
+            LuaValue::Table(t) => Ok(Self {
+                pretty: t.get::<_, Option<bool>>("pretty")?.unwrap_or_default(),
+                csv: CsvOptions::from_table(lua, &t)?,
+                binary: BinaryOptions::from_table(&t)?,
+                decode: DecodeOptions::from_table(lua, &t, LUA_DESERIALIZE_OPTIONS)?,
+            }),
+            value => Err(LuaError::FromLuaConversionError {
+                from: value.type_name(),
+                to: "EncodeDecodeOptions",
+                message: Some(format!(
+                    "Invalid options - expected boolean or table, got {}",
+                    value.type_name()
+                )),
+            }),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
 pub struct EncodeDecodeConfig {
     pub format: EncodeDecodeFormat,
     pub pretty: bool,
+    pub csv: CsvOptions,
+    pub binary: BinaryOptions,
+    pub decode: DecodeOptions,
 }
 
 impl EncodeDecodeConfig {
@@ -83,32 +145,128 @@ impl EncodeDecodeConfig {
                 };
                 s.as_bytes().to_vec()
             }
+            EncodeDecodeFormat::MsgPack => {
+                let serialized: CborValue = lua.from_value_with(value, LUA_DESERIALIZE_OPTIONS)?;
+                rmp_serde::to_vec(&integers_from_floats(serialized)).into_lua_err()?
+            }
+            EncodeDecodeFormat::Cbor => {
+                let serialized: CborValue = lua.from_value_with(value, LUA_DESERIALIZE_OPTIONS)?;
+                let mut writer = Vec::with_capacity(128);
+                ciborium::into_writer(&integers_from_floats(serialized), &mut writer)
+                    .into_lua_err()?;
+                writer
+            }
+            EncodeDecodeFormat::Csv => encode_csv(value, &self.csv)?,
+            EncodeDecodeFormat::Base64 => {
+                encode_base64(BString::from_lua(value, lua)?, false, self.binary)
+            }
+            EncodeDecodeFormat::Base64Url => {
+                encode_base64(BString::from_lua(value, lua)?, true, self.binary)
+            }
+            EncodeDecodeFormat::Hex => encode_hex(BString::from_lua(value, lua)?),
         };
         lua.create_string(bytes)
     }
 
     pub fn deserialize_from_string(self, lua: &Lua, string: BString) -> LuaResult<LuaValue> {
         let bytes = string.as_bytes();
+        let decode = &self.decode;
+        let options = decode.lua_serialize_options(LUA_SERIALIZE_OPTIONS);
         match self.format {
             EncodeDecodeFormat::Json => {
                 let value: JsonValue = serde_json::from_slice(bytes).into_lua_err()?;
-                lua.to_value_with(&value, LUA_SERIALIZE_OPTIONS)
+                decode.validate(&value)?;
+                lua.to_value_with(&decode.json(value), options)
             }
             EncodeDecodeFormat::Yaml => {
                 let value: YamlValue = serde_yaml::from_slice(bytes).into_lua_err()?;
-                lua.to_value_with(&value, LUA_SERIALIZE_OPTIONS)
+                decode.validate_serializable(&value)?;
+                lua.to_value_with(&decode.yaml(value), options)
             }
             EncodeDecodeFormat::Toml => {
                 if let Ok(s) = string.to_str() {
                     let value: TomlValue = toml::from_str(s).into_lua_err()?;
-                    lua.to_value_with(&value, LUA_SERIALIZE_OPTIONS)
+                    decode.validate_serializable(&value)?;
+                    lua.to_value_with(&decode.toml(value), options)
                 } else {
                     Err(LuaError::RuntimeError(
                         "TOML must be valid utf-8".to_string(),
                     ))
                 }
             }
+            EncodeDecodeFormat::MsgPack => {
+                let value: CborValue = untagged(rmp_serde::from_slice(bytes).into_lua_err()?);
+                decode.validate_serializable(&value)?;
+                lua.to_value_with(&decode.cbor(value), options)
+            }
+            EncodeDecodeFormat::Cbor => {
+                let value: CborValue = untagged(ciborium::from_reader(bytes).into_lua_err()?);
+                decode.validate_serializable(&value)?;
+                lua.to_value_with(&decode.cbor(value), options)
+            }
+            EncodeDecodeFormat::Csv => {
+                decode.check_schema_supported("csv")?;
+                decode_csv(lua, bytes, &self.csv)
+            }
+            EncodeDecodeFormat::Base64 => {
+                decode.check_schema_supported("base64")?;
+                let bytes = decode_base64(bytes, false, self.binary)?;
+                lua.create_string(bytes).map(LuaValue::String)
+            }
+            EncodeDecodeFormat::Base64Url => {
+                decode.check_schema_supported("base64url")?;
+                let bytes = decode_base64(bytes, true, self.binary)?;
+                lua.create_string(bytes).map(LuaValue::String)
+            }
+            EncodeDecodeFormat::Hex => {
+                decode.check_schema_supported("hex")?;
+                let bytes = decode_hex(bytes, self.binary)?;
+                lua.create_string(bytes).map(LuaValue::String)
+            }
+        }
+    }
+}
+
+/**
+    Converts whole floats into integers, recursively.
+
+    Luau only has a single number type, and encoding every
+    number as a float would be wasteful for binary formats.
+*/
+fn integers_from_floats(value: CborValue) -> CborValue {
+    match value {
+        CborValue::Float(f) if f.is_finite() && f.fract() == 0.0 && f.abs() < 2f64.powi(53) => {
+            CborValue::Integer((f as i64).into())
         }
+        CborValue::Array(values) => {
+            CborValue::Array(values.into_iter().map(integers_from_floats).collect())
+        }
+        CborValue::Map(pairs) => CborValue::Map(
+            pairs
+                .into_iter()
+                .map(|(k, v)| (integers_from_floats(k), integers_from_floats(v)))
+                .collect(),
+        ),
+        value => value,
+    }
+}
+
+/**
+    Strips semantic tags from CBOR values, recursively.
+
+    Tags have no equivalent in Luau, so only the tagged values are kept.
+*/
+fn untagged(value: CborValue) -> CborValue {
+    match value {
+        CborValue::Tag(_, inner) => untagged(*inner),
+        CborValue::Array(values) => CborValue::Array(values.into_iter().map(untagged).collect()),
+        CborValue::Map(pairs) => CborValue::Map(
+            pairs
+                .into_iter()
+                .map(|(k, v)| (untagged(k), untagged(v)))
+                .collect(),
+        ),
+        value => value,
     }
 }
 
@@ -117,6 +275,9 @@ impl From<EncodeDecodeFormat> for EncodeDecodeConfig {
         Self {
             format,
             pretty: false,
+            csv: CsvOptions::default(),
+            binary: BinaryOptions::default(),
+            decode: DecodeOptions::default(),
         }
     }
 }
@@ -126,6 +287,21 @@ impl From<(EncodeDecodeFormat, bool)> for EncodeDecodeConfig {
         Self {
             format: value.0,
             pretty: value.1,
+            csv: CsvOptions::default(),
+            binary: BinaryOptions::default(),
+            decode: DecodeOptions::default(),
+        }
+    }
+}
+
+impl From<(EncodeDecodeFormat, EncodeDecodeOptions)> for EncodeDecodeConfig {
+    fn from(value: (EncodeDecodeFormat, EncodeDecodeOptions)) -> Self {
+        Self {
+            format: value.0,
+            pretty: value.1.pretty,
+            csv: value.1.csv,
+            binary: value.1.binary,
+            decode: value.1.decode,
         }
     }
 }
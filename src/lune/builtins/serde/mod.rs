@@ -1,48 +1,99 @@
 use bstr::BString;
 use mlua::prelude::*;
 
+pub(super) mod binary;
 pub(super) mod compress_decompress;
+pub(super) mod compress_stream;
+pub(super) mod csv;
+pub(super) mod decode_options;
 pub(super) mod encode_decode;
 
-use compress_decompress::{compress, decompress, CompressDecompressFormat};
-use encode_decode::{EncodeDecodeConfig, EncodeDecodeFormat};
+use compress_decompress::{
+    compress_with_options, decompress_with_options, CompressDecompressFormat,
+    CompressDecompressOptions,
+};
+use compress_stream::{CompressStream, CompressStreamKind};
+use encode_decode::{EncodeDecodeConfig, EncodeDecodeFormat, EncodeDecodeOptions};
 
 use crate::lune::util::TableBuilder;
 
 pub fn create(lua: &Lua) -> LuaResult<LuaTable> {
     TableBuilder::new(lua)?
+        .with_value("null", lua.null())?
+        .with_function("array", serde_array)?
+        .with_function("isArray", serde_is_array)?
         .with_function("encode", serde_encode)?
         .with_function("decode", serde_decode)?
         .with_async_function("compress", serde_compress)?
         .with_async_function("decompress", serde_decompress)?
+        .with_function("createCompressStream", serde_create_compress_stream)?
+        .with_function("createDecompressStream", serde_create_decompress_stream)?
         .build_readonly()
 }
 
+fn serde_array<'lua>(lua: &'lua Lua, table: Option<LuaTable<'lua>>) -> LuaResult<LuaTable<'lua>> {
+    let array_metatable = lua.array_metatable();
+    let table = match table {
+        Some(table) => table,
+        None => lua.create_table()?,
+    };
+    match table.get_metatable() {
+        Some(metatable) if metatable != array_metatable => Err(LuaError::RuntimeError(
+            "Invalid table - tables with a metatable can not be marked as arrays".to_string(),
+        )),
+        _ => {
+            table.set_metatable(Some(array_metatable));
+            Ok(table)
+        }
+    }
+}
+
+fn serde_is_array(lua: &Lua, table: LuaTable) -> LuaResult<bool> {
+    Ok(table.raw_len() > 0 || table.get_metatable() == Some(lua.array_metatable()))
+}
+
 fn serde_encode<'lua>(
     lua: &'lua Lua,
-    (format, val, pretty): (EncodeDecodeFormat, LuaValue<'lua>, Option<bool>),
+    (format, val, options): (EncodeDecodeFormat, LuaValue<'lua>, EncodeDecodeOptions),
 ) -> LuaResult<LuaString<'lua>> {
-    let config = EncodeDecodeConfig::from((format, pretty.unwrap_or_default()));
+    let config = EncodeDecodeConfig::from((format, options));
     config.serialize_to_string(lua, val)
 }
 
-fn serde_decode(lua: &Lua, (format, str): (EncodeDecodeFormat, BString)) -> LuaResult<LuaValue> {
-    let config = EncodeDecodeConfig::from(format);
+fn serde_decode(
+    lua: &Lua,
+    (format, str, options): (EncodeDecodeFormat, BString, EncodeDecodeOptions),
+) -> LuaResult<LuaValue> {
+    let config = EncodeDecodeConfig::from((format, options));
     config.deserialize_from_string(lua, str)
 }
 
 async fn serde_compress(
     lua: &Lua,
-    (format, str): (CompressDecompressFormat, BString),
+    (format, str, options): (CompressDecompressFormat, BString, CompressDecompressOptions),
 ) -> LuaResult<LuaString> {
-    let bytes = compress(format, str).await?;
+    let bytes = compress_with_options(format, str, &options).await?;
     lua.create_string(bytes)
 }
 
 async fn serde_decompress(
     lua: &Lua,
-    (format, str): (CompressDecompressFormat, BString),
+    (format, str, options): (CompressDecompressFormat, BString, CompressDecompressOptions),
 ) -> LuaResult<LuaString> {
-    let bytes = decompress(format, str).await?;
+    let bytes = decompress_with_options(format, str, &options).await?;
     lua.create_string(bytes)
 }
+
+fn serde_create_compress_stream(
+    _: &Lua,
+    (format, options): (CompressDecompressFormat, CompressDecompressOptions),
+) -> LuaResult<CompressStream> {
+    CompressStream::new(CompressStreamKind::Compress, format, &options)
+}
+
+fn serde_create_decompress_stream(
+    _: &Lua,
+    (format, options): (CompressDecompressFormat, CompressDecompressOptions),
+) -> LuaResult<CompressStream> {
+    CompressStream::new(CompressStreamKind::Decompress, format, &options)
+}
@@ -0,0 +1,141 @@
+use std::{cell::RefCell, rc::Rc};
+
+use mlua::prelude::*;
+use tokio::sync::{
+    broadcast::{
+        self,
+        error::{RecvError, TryRecvError},
+    },
+    Mutex as AsyncMutex,
+};
+
+use super::store_value;
+
+type BroadcastValue = Rc<LuaRegistryKey>;
+
+/**
+    A channel that sends every value to all of its subscribers.
+
+    Each subscriber holds at most `capacity` values that it has not yet
+    received - once a subscriber falls further behind than that, the
+    oldest values are skipped for that subscriber.
+*/
+#[derive(Debug, Clone)]
+pub struct LuaBroadcast {
+    capacity: usize,
+    sender: Rc<RefCell<Option<broadcast::Sender<BroadcastValue>>>>,
+}
+
+impl LuaBroadcast {
+    pub fn new(capacity: usize) -> Self {
+        let (tx, _) = broadcast::channel(capacity);
+        Self {
+            capacity,
+            sender: Rc::new(RefCell::new(Some(tx))),
+        }
+    }
+
+    pub fn is_closed(&self) -> bool {
+        self.sender.borrow().is_none()
+    }
+
+    /**
+        Sends a value to all current subscribers, returning the number of subscribers.
+    */
+    pub fn send(&self, key: LuaRegistryKey) -> LuaResult<usize> {
+        match self.sender.borrow().as_ref() {
+            // NOTE: Sending only fails when there are no subscribers,
+            // which is not an error, the value is simply not received
+            Some(tx) => Ok(tx.send(Rc::new(key)).unwrap_or_default()),
+            None => Err(LuaError::runtime("Broadcast channel is closed")),
+        }
+    }
+
+    pub fn subscribe(&self) -> LuaResult<LuaBroadcastReceiver> {
+        match self.sender.borrow().as_ref() {
+            Some(tx) => Ok(LuaBroadcastReceiver {
+                receiver: Rc::new(AsyncMutex::new(tx.subscribe())),
+            }),
+            None => Err(LuaError::runtime("Broadcast channel is closed")),
+        }
+    }
+
+    /**
+        Closes the channel, preventing any more values from being sent.
+
+        Values that were sent before closing can still be received by subscribers.
+    */
+    pub fn close(&self) {
+        self.sender.borrow_mut().take();
+    }
+}
+
+impl LuaUserData for LuaBroadcast {
+    fn add_fields<'lua, F: LuaUserDataFields<'lua, Self>>(fields: &mut F) {
+        fields.add_meta_field(LuaMetaMethod::Type, "Broadcast");
+        fields.add_field_method_get("capacity", |_, this| Ok(this.capacity));
+        fields.add_field_method_get("closed", |_, this| Ok(this.is_closed()));
+    }
+
+    fn add_methods<'lua, M: LuaUserDataMethods<'lua, Self>>(methods: &mut M) {
+        methods.add_method("send", |lua, this, value: LuaValue| {
+            this.send(store_value(lua, value)?)
+        });
+        methods.add_method("subscribe", |_, this, ()| this.subscribe());
+        methods.add_method("close", |_, this, ()| {
+            this.close();
+            Ok(())
+        });
+    }
+}
+
+/**
+    A subscriber to a [`LuaBroadcast`], receiving all values sent after subscribing.
+*/
+#[derive(Debug, Clone)]
+pub struct LuaBroadcastReceiver {
+    receiver: Rc<AsyncMutex<broadcast::Receiver<BroadcastValue>>>,
+}
+
+impl LuaBroadcastReceiver {
+    pub async fn recv(&self) -> Option<BroadcastValue> {
+        let mut rx = self.receiver.lock().await;
+        loop {
+            match rx.recv().await {
+                Ok(value) => return Some(value),
+                Err(RecvError::Lagged(_)) => continue,
+                Err(RecvError::Closed) => return None,
+            }
+        }
+    }
+
+    pub fn try_recv(&self) -> Option<BroadcastValue> {
+        let mut rx = self.receiver.try_lock().ok()?;
+        loop {
+            match rx.try_recv() {
+                Ok(value) => return Some(value),
+                Err(TryRecvError::Lagged(_)) => continue,
+                Err(TryRecvError::Empty | TryRecvError::Closed) => return None,
+            }
+        }
+    }
+}
+
+impl LuaUserData for LuaBroadcastReceiver {
+    fn add_fields<'lua, F: LuaUserDataFields<'lua, Self>>(fields: &mut F) {
+        fields.add_meta_field(LuaMetaMethod::Type, "BroadcastReceiver");
+    }
+
+    fn add_methods<'lua, M: LuaUserDataMethods<'lua, Self>>(methods: &mut M) {
+        methods.add_async_method("recv", |lua, this, ()| async move {
+            match this.recv().await {
+                Some(key) => lua.registry_value::<LuaValue>(&key),
+                None => Ok(LuaValue::Nil),
+            }
+        });
+        methods.add_method("tryRecv", |lua, this, ()| match this.try_recv() {
+            Some(key) => lua.registry_value::<LuaValue>(&key),
+            None => Ok(LuaValue::Nil),
+        });
+    }
+}
@@ -0,0 +1,160 @@
+use std::{cell::RefCell, rc::Rc};
+
+use mlua::prelude::*;
+use tokio::sync::{
+    mpsc::{self, error::TrySendError},
+    Mutex as AsyncMutex,
+};
+
+use super::store_value;
+
+const ERR_CLOSED: &str = "Channel is closed";
+
+#[derive(Debug, Clone)]
+enum ChannelSender {
+    Bounded(mpsc::Sender<LuaRegistryKey>),
+    Unbounded(mpsc::UnboundedSender<LuaRegistryKey>),
+}
+
+#[derive(Debug)]
+enum ChannelReceiver {
+    Bounded(mpsc::Receiver<LuaRegistryKey>),
+    Unbounded(mpsc::UnboundedReceiver<LuaRegistryKey>),
+}
+
+impl ChannelReceiver {
+    async fn recv(&mut self) -> Option<LuaRegistryKey> {
+        match self {
+            Self::Bounded(rx) => rx.recv().await,
+            Self::Unbounded(rx) => rx.recv().await,
+        }
+    }
+
+    fn try_recv(&mut self) -> Option<LuaRegistryKey> {
+        match self {
+            Self::Bounded(rx) => rx.try_recv().ok(),
+            Self::Unbounded(rx) => rx.try_recv().ok(),
+        }
+    }
+}
+
+/**
+    A channel for sending values from one or more threads to one or more other threads.
+
+    Bounded channels hold at most `capacity` values, and sending to a full
+    channel waits until there is room. Unbounded channels never wait to send.
+
+    Receiving from a channel waits until a value is available, or until the
+    channel has been closed and all values sent before closing were received.
+*/
+#[derive(Debug, Clone)]
+pub struct LuaChannel {
+    capacity: Option<usize>,
+    sender: Rc<RefCell<Option<ChannelSender>>>,
+    receiver: Rc<AsyncMutex<ChannelReceiver>>,
+}
+
+impl LuaChannel {
+    pub fn bounded(capacity: usize) -> Self {
+        let (tx, rx) = mpsc::channel(capacity);
+        Self {
+            capacity: Some(capacity),
+            sender: Rc::new(RefCell::new(Some(ChannelSender::Bounded(tx)))),
+            receiver: Rc::new(AsyncMutex::new(ChannelReceiver::Bounded(rx))),
+        }
+    }
+
+    pub fn unbounded() -> Self {
+        let (tx, rx) = mpsc::unbounded_channel();
+        Self {
+            capacity: None,
+            sender: Rc::new(RefCell::new(Some(ChannelSender::Unbounded(tx)))),
+            receiver: Rc::new(AsyncMutex::new(ChannelReceiver::Unbounded(rx))),
+        }
+    }
+
+    fn sender(&self) -> LuaResult<ChannelSender> {
+        self.sender
+            .borrow()
+            .clone()
+            .ok_or_else(|| LuaError::runtime(ERR_CLOSED))
+    }
+
+    pub fn is_closed(&self) -> bool {
+        self.sender.borrow().is_none()
+    }
+
+    pub async fn send(&self, key: LuaRegistryKey) -> LuaResult<()> {
+        let result = match self.sender()? {
+            ChannelSender::Bounded(tx) => tx.send(key).await.is_ok(),
+            ChannelSender::Unbounded(tx) => tx.send(key).is_ok(),
+        };
+        if result {
+            Ok(())
+        } else {
+            Err(LuaError::runtime(ERR_CLOSED))
+        }
+    }
+
+    pub fn try_send(&self, key: LuaRegistryKey) -> LuaResult<bool> {
+        match self.sender()? {
+            ChannelSender::Bounded(tx) => match tx.try_send(key) {
+                Ok(()) => Ok(true),
+                Err(TrySendError::Full(_)) => Ok(false),
+                Err(TrySendError::Closed(_)) => Err(LuaError::runtime(ERR_CLOSED)),
+            },
+            ChannelSender::Unbounded(tx) => match tx.send(key) {
+                Ok(()) => Ok(true),
+                Err(_) => Err(LuaError::runtime(ERR_CLOSED)),
+            },
+        }
+    }
+
+    pub async fn recv(&self) -> Option<LuaRegistryKey> {
+        self.receiver.lock().await.recv().await
+    }
+
+    pub fn try_recv(&self) -> Option<LuaRegistryKey> {
+        self.receiver.try_lock().ok()?.try_recv()
+    }
+
+    /**
+        Closes the channel, preventing any more values from being sent.
+
+        Values that were sent before closing can still be received.
+    */
+    pub fn close(&self) {
+        self.sender.borrow_mut().take();
+    }
+}
+
+impl LuaUserData for LuaChannel {
+    fn add_fields<'lua, F: LuaUserDataFields<'lua, Self>>(fields: &mut F) {
+        fields.add_meta_field(LuaMetaMethod::Type, "Channel");
+        fields.add_field_method_get("capacity", |_, this| Ok(this.capacity));
+        fields.add_field_method_get("closed", |_, this| Ok(this.is_closed()));
+    }
+
+    fn add_methods<'lua, M: LuaUserDataMethods<'lua, Self>>(methods: &mut M) {
+        methods.add_async_method("send", |lua, this, value: LuaValue| async move {
+            this.send(store_value(lua, value)?).await
+        });
+        methods.add_method("trySend", |lua, this, value: LuaValue| {
+            this.try_send(store_value(lua, value)?)
+        });
+        methods.add_async_method("recv", |lua, this, ()| async move {
+            match this.recv().await {
+                Some(key) => lua.registry_value::<LuaValue>(&key),
+                None => Ok(LuaValue::Nil),
+            }
+        });
+        methods.add_method("tryRecv", |lua, this, ()| match this.try_recv() {
+            Some(key) => lua.registry_value::<LuaValue>(&key),
+            None => Ok(LuaValue::Nil),
+        });
+        methods.add_method("close", |_, this, ()| {
+            this.close();
+            Ok(())
+        });
+    }
+}
@@ -0,0 +1,64 @@
+use std::{cell::RefCell, rc::Rc};
+
+use mlua::prelude::*;
+use tokio::sync::watch;
+
+/**
+    A one-shot event that threads can wait for.
+
+    Once the event has been set, all waiting threads resume with the
+    value it was set with, and any later waits return that value instantly.
+*/
+#[derive(Debug, Clone)]
+pub struct LuaEvent {
+    value: Rc<RefCell<Option<LuaRegistryKey>>>,
+    set_tx: Rc<watch::Sender<bool>>,
+}
+
+impl LuaEvent {
+    pub fn new() -> Self {
+        Self {
+            value: Rc::default(),
+            set_tx: Rc::new(watch::Sender::new(false)),
+        }
+    }
+
+    pub fn is_set(&self) -> bool {
+        *self.set_tx.borrow()
+    }
+
+    pub fn set(&self, key: LuaRegistryKey) -> LuaResult<()> {
+        if self.is_set() {
+            return Err(LuaError::runtime("Event has already been set"));
+        }
+        self.value.borrow_mut().replace(key);
+        self.set_tx.send_replace(true);
+        Ok(())
+    }
+
+    pub async fn wait<'lua>(&self, lua: &'lua Lua) -> LuaResult<LuaValue<'lua>> {
+        let mut set_rx = self.set_tx.subscribe();
+        set_rx
+            .wait_for(|set| *set)
+            .await
+            .expect("Event sender was dropped");
+        match self.value.borrow().as_ref() {
+            Some(key) => lua.registry_value(key),
+            None => Ok(LuaValue::Nil),
+        }
+    }
+}
+
+impl LuaUserData for LuaEvent {
+    fn add_fields<'lua, F: LuaUserDataFields<'lua, Self>>(fields: &mut F) {
+        fields.add_meta_field(LuaMetaMethod::Type, "Event");
+        fields.add_field_method_get("isSet", |_, this| Ok(this.is_set()));
+    }
+
+    fn add_methods<'lua, M: LuaUserDataMethods<'lua, Self>>(methods: &mut M) {
+        methods.add_method("set", |lua, this, value: LuaValue| {
+            this.set(lua.create_registry_value(value)?)
+        });
+        methods.add_async_method("wait", |lua, this, ()| async move { this.wait(lua).await });
+    }
+}
@@ -0,0 +1,68 @@
+use mlua::prelude::*;
+
+use crate::lune::util::TableBuilder;
+
+mod broadcast;
+mod channel;
+mod event;
+mod mutex;
+mod semaphore;
+
+use self::{
+    broadcast::LuaBroadcast, channel::LuaChannel, event::LuaEvent, mutex::LuaMutex,
+    semaphore::LuaSemaphore,
+};
+
+const DEFAULT_BROADCAST_CAPACITY: usize = 64;
+
+pub fn create(lua: &Lua) -> LuaResult<LuaTable> {
+    TableBuilder::new(lua)?
+        .with_function("broadcast", sync_broadcast)?
+        .with_function("channel", sync_channel)?
+        .with_function("event", |_, ()| Ok(LuaEvent::new()))?
+        .with_function("mutex", |_, ()| Ok(LuaMutex::new()))?
+        .with_function("semaphore", sync_semaphore)?
+        .build_readonly()
+}
+
+fn validate_capacity(capacity: usize) -> LuaResult<usize> {
+    if capacity == 0 {
+        Err(LuaError::RuntimeError(
+            "Invalid capacity - expected a positive integer, got 0".to_string(),
+        ))
+    } else {
+        Ok(capacity)
+    }
+}
+
+fn sync_broadcast(_: &Lua, capacity: Option<usize>) -> LuaResult<LuaBroadcast> {
+    let capacity = validate_capacity(capacity.unwrap_or(DEFAULT_BROADCAST_CAPACITY))?;
+    Ok(LuaBroadcast::new(capacity))
+}
+
+fn sync_channel(_: &Lua, capacity: Option<usize>) -> LuaResult<LuaChannel> {
+    match capacity {
+        Some(capacity) => Ok(LuaChannel::bounded(validate_capacity(capacity)?)),
+        None => Ok(LuaChannel::unbounded()),
+    }
+}
+
+fn sync_semaphore(_: &Lua, permits: u32) -> LuaResult<LuaSemaphore> {
+    Ok(LuaSemaphore::new(permits))
+}
+
+/**
+    Stores a value sent between threads in the registry.
+
+    Sending `nil` is not allowed, since receiving `nil`
+    is used to signal that there will be no more values.
+*/
+fn store_value(lua: &Lua, value: LuaValue) -> LuaResult<LuaRegistryKey> {
+    if value.is_nil() {
+        Err(LuaError::RuntimeError(
+            "Invalid value - expected a value other than nil".to_string(),
+        ))
+    } else {
+        lua.create_registry_value(value)
+    }
+}
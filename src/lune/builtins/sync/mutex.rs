@@ -0,0 +1,83 @@
+use std::{cell::RefCell, sync::Arc};
+
+use mlua::prelude::*;
+use tokio::sync::{Mutex as AsyncMutex, OwnedMutexGuard};
+
+/**
+    A mutex that only lets a single thread hold its lock at any given time.
+
+    Locking yields the current thread until the lock is available.
+*/
+#[derive(Debug, Clone, Default)]
+pub struct LuaMutex {
+    inner: Arc<AsyncMutex<()>>,
+}
+
+impl LuaMutex {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn is_locked(&self) -> bool {
+        self.inner.try_lock().is_err()
+    }
+
+    pub async fn lock(&self) -> LuaMutexGuard {
+        LuaMutexGuard::new(Arc::clone(&self.inner).lock_owned().await)
+    }
+
+    pub fn try_lock(&self) -> Option<LuaMutexGuard> {
+        Arc::clone(&self.inner)
+            .try_lock_owned()
+            .ok()
+            .map(LuaMutexGuard::new)
+    }
+}
+
+impl LuaUserData for LuaMutex {
+    fn add_fields<'lua, F: LuaUserDataFields<'lua, Self>>(fields: &mut F) {
+        fields.add_meta_field(LuaMetaMethod::Type, "Mutex");
+        fields.add_field_method_get("locked", |_, this| Ok(this.is_locked()));
+    }
+
+    fn add_methods<'lua, M: LuaUserDataMethods<'lua, Self>>(methods: &mut M) {
+        methods.add_async_method("lock", |_, this, ()| async move { Ok(this.lock().await) });
+        methods.add_method("tryLock", |_, this, ()| Ok(this.try_lock()));
+    }
+}
+
+/**
+    A held lock for a [`LuaMutex`].
+
+    The lock is released when `unlock` is called, or when
+    the guard is garbage collected, whichever happens first.
+*/
+#[derive(Debug)]
+pub struct LuaMutexGuard {
+    guard: RefCell<Option<OwnedMutexGuard<()>>>,
+}
+
+impl LuaMutexGuard {
+    fn new(guard: OwnedMutexGuard<()>) -> Self {
+        Self {
+            guard: RefCell::new(Some(guard)),
+        }
+    }
+
+    pub fn unlock(&self) -> LuaResult<()> {
+        match self.guard.borrow_mut().take() {
+            Some(_) => Ok(()),
+            None => Err(LuaError::runtime("Mutex has already been unlocked")),
+        }
+    }
+}
+
+impl LuaUserData for LuaMutexGuard {
+    fn add_fields<'lua, F: LuaUserDataFields<'lua, Self>>(fields: &mut F) {
+        fields.add_meta_field(LuaMetaMethod::Type, "MutexGuard");
+    }
+
+    fn add_methods<'lua, M: LuaUserDataMethods<'lua, Self>>(methods: &mut M) {
+        methods.add_method("unlock", |_, this, ()| this.unlock());
+    }
+}
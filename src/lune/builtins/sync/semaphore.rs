@@ -0,0 +1,114 @@
+use std::{cell::RefCell, sync::Arc};
+
+use mlua::prelude::*;
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+
+/**
+    A semaphore that lets at most a fixed number of threads hold its permits at once.
+
+    Acquiring permits yields the current thread until enough permits are available.
+*/
+#[derive(Debug, Clone)]
+pub struct LuaSemaphore {
+    permits: u32,
+    inner: Arc<Semaphore>,
+}
+
+impl LuaSemaphore {
+    pub fn new(permits: u32) -> Self {
+        Self {
+            permits,
+            inner: Arc::new(Semaphore::new(permits as usize)),
+        }
+    }
+
+    pub fn available(&self) -> usize {
+        self.inner.available_permits()
+    }
+
+    fn validate_count(&self, count: Option<u32>) -> LuaResult<u32> {
+        match count.unwrap_or(1) {
+            0 => Err(LuaError::RuntimeError(
+                "Invalid permit count - expected a positive integer, got 0".to_string(),
+            )),
+            count if count > self.permits => Err(LuaError::RuntimeError(format!(
+                "Invalid permit count - expected at most {}, got {count}",
+                self.permits
+            ))),
+            count => Ok(count),
+        }
+    }
+
+    pub async fn acquire(&self, count: Option<u32>) -> LuaResult<LuaSemaphorePermit> {
+        let count = self.validate_count(count)?;
+        let permit = Arc::clone(&self.inner)
+            .acquire_many_owned(count)
+            .await
+            .expect("Semaphore is never closed");
+        Ok(LuaSemaphorePermit::new(count, permit))
+    }
+
+    pub fn try_acquire(&self, count: Option<u32>) -> LuaResult<Option<LuaSemaphorePermit>> {
+        let count = self.validate_count(count)?;
+        Ok(Arc::clone(&self.inner)
+            .try_acquire_many_owned(count)
+            .ok()
+            .map(|permit| LuaSemaphorePermit::new(count, permit)))
+    }
+}
+
+impl LuaUserData for LuaSemaphore {
+    fn add_fields<'lua, F: LuaUserDataFields<'lua, Self>>(fields: &mut F) {
+        fields.add_meta_field(LuaMetaMethod::Type, "Semaphore");
+        fields.add_field_method_get("available", |_, this| Ok(this.available()));
+        fields.add_field_method_get("permits", |_, this| Ok(this.permits));
+    }
+
+    fn add_methods<'lua, M: LuaUserDataMethods<'lua, Self>>(methods: &mut M) {
+        methods.add_async_method("acquire", |_, this, count: Option<u32>| async move {
+            this.acquire(count).await
+        });
+        methods.add_method("tryAcquire", |_, this, count: Option<u32>| {
+            this.try_acquire(count)
+        });
+    }
+}
+
+/**
+    Permits acquired from a [`LuaSemaphore`].
+
+    The permits are released when `release` is called, or when
+    the permit is garbage collected, whichever happens first.
+*/
+#[derive(Debug)]
+pub struct LuaSemaphorePermit {
+    count: u32,
+    permit: RefCell<Option<OwnedSemaphorePermit>>,
+}
+
+impl LuaSemaphorePermit {
+    fn new(count: u32, permit: OwnedSemaphorePermit) -> Self {
+        Self {
+            count,
+            permit: RefCell::new(Some(permit)),
+        }
+    }
+
+    pub fn release(&self) -> LuaResult<()> {
+        match self.permit.borrow_mut().take() {
+            Some(_) => Ok(()),
+            None => Err(LuaError::runtime("Permit has already been released")),
+        }
+    }
+}
+
+impl LuaUserData for LuaSemaphorePermit {
+    fn add_fields<'lua, F: LuaUserDataFields<'lua, Self>>(fields: &mut F) {
+        fields.add_meta_field(LuaMetaMethod::Type, "SemaphorePermit");
+        fields.add_field_method_get("count", |_, this| Ok(this.count));
+    }
+
+    fn add_methods<'lua, M: LuaUserDataMethods<'lua, Self>>(methods: &mut M) {
+        methods.add_method("release", |_, this, ()| this.release());
+    }
+}
@@ -0,0 +1,168 @@
+use std::{
+    cell::RefCell,
+    collections::HashMap,
+    rc::{Rc, Weak},
+};
+
+use mlua::prelude::*;
+use mlua_luau_scheduler::{LuaSchedulerExt, LuaSpawnExt, ThreadId};
+use tokio::sync::watch;
+
+use super::tracked::{cancel_thread, TrackedThread};
+
+#[derive(Debug, Default)]
+struct TaskGroupState {
+    children: HashMap<ThreadId, LuaRegistryKey>,
+    error: Option<LuaError>,
+}
+
+/**
+    A group of tasks that are waited for, and cancelled, together.
+
+    Once any task in the group errors, all of the other tasks that are
+    still running get cancelled, and the error is raised from `wait`.
+*/
+#[derive(Debug, Clone)]
+pub struct TaskGroup {
+    state: Rc<RefCell<TaskGroupState>>,
+    running_tx: Rc<watch::Sender<usize>>,
+    // NOTE: Bumped on every cancellation, so that the futures
+    // listening for results of cancelled children stop waiting
+    generation_tx: Rc<watch::Sender<u64>>,
+}
+
+impl TaskGroup {
+    pub fn new() -> Self {
+        Self {
+            state: Rc::default(),
+            running_tx: Rc::new(watch::Sender::new(0)),
+            generation_tx: Rc::new(watch::Sender::new(0)),
+        }
+    }
+
+    pub fn running(&self) -> usize {
+        *self.running_tx.borrow()
+    }
+
+    pub fn spawn<'lua>(
+        &self,
+        lua: &'lua Lua,
+        function: LuaFunction<'lua>,
+        args: LuaMultiValue<'lua>,
+    ) -> LuaResult<LuaThread<'lua>> {
+        let tracked = TrackedThread::spawn(lua, function, args)?;
+        let id = tracked.id();
+
+        {
+            let mut state = self.state.borrow_mut();
+            let key = lua.create_registry_value(tracked.thread().clone())?;
+            state.children.insert(id, key);
+            self.running_tx.send_replace(state.children.len());
+        }
+
+        let inner_lua = lua
+            .app_data_ref::<Weak<Lua>>()
+            .expect("Missing weak lua ref")
+            .upgrade()
+            .expect("Lua was dropped unexpectedly");
+        let mut generation_rx = self.generation_tx.subscribe();
+        let generation = *generation_rx.borrow_and_update();
+        let group = self.clone();
+
+        lua.spawn_local(async move {
+            let finished = tokio::select! {
+                () = inner_lua.wait_for_thread(id) => true,
+                _ = generation_rx.wait_for(|g| *g != generation) => false,
+            };
+            if finished {
+                let result = TrackedThread::result(&inner_lua, id).map(|_| ());
+                // NOTE: Cancelling can only fail if the thread
+                // is currently running, which it never is here
+                group.finish(&inner_lua, id, result).ok();
+            }
+        });
+
+        Ok(tracked.thread().clone())
+    }
+
+    fn finish(&self, lua: &Lua, id: ThreadId, result: LuaResult<()>) -> LuaResult<()> {
+        let first_error = {
+            let mut state = self.state.borrow_mut();
+            let Some(key) = state.children.remove(&id) else {
+                return Ok(());
+            };
+            lua.remove_registry_value(key)?;
+            self.running_tx.send_replace(state.children.len());
+            match result {
+                Err(e) if state.error.is_none() => {
+                    state.error.replace(e);
+                    true
+                }
+                _ => false,
+            }
+        };
+        if first_error {
+            self.cancel(lua)?;
+        }
+        Ok(())
+    }
+
+    /**
+        Cancels all of the tasks in the group that are still running.
+    */
+    pub fn cancel(&self, lua: &Lua) -> LuaResult<()> {
+        let keys = {
+            let mut state = self.state.borrow_mut();
+            state
+                .children
+                .drain()
+                .map(|(_, key)| key)
+                .collect::<Vec<_>>()
+        };
+        self.running_tx.send_replace(0);
+        self.generation_tx.send_modify(|g| *g += 1);
+        for key in keys {
+            let thread = lua.registry_value::<LuaThread>(&key)?;
+            lua.remove_registry_value(key)?;
+            cancel_thread(lua, thread)?;
+        }
+        Ok(())
+    }
+
+    /**
+        Waits for all of the tasks in the group to finish.
+
+        Errors with the first error that any of the tasks in the group
+        errored with, if any, which also resets the group so that it
+        may be used again.
+    */
+    pub async fn wait(&self) -> LuaResult<()> {
+        let mut running_rx = self.running_tx.subscribe();
+        running_rx
+            .wait_for(|running| *running == 0)
+            .await
+            .expect("Task group sender was dropped");
+        match self.state.borrow_mut().error.take() {
+            Some(e) => Err(e),
+            None => Ok(()),
+        }
+    }
+}
+
+impl LuaUserData for TaskGroup {
+    fn add_fields<'lua, F: LuaUserDataFields<'lua, Self>>(fields: &mut F) {
+        fields.add_meta_field(LuaMetaMethod::Type, "TaskGroup");
+        fields.add_field_method_get("running", |_, this| Ok(this.running()));
+    }
+
+    fn add_methods<'lua, M: LuaUserDataMethods<'lua, Self>>(methods: &mut M) {
+        methods.add_method(
+            "spawn",
+            |lua, this, (function, args): (LuaFunction, LuaMultiValue)| {
+                this.spawn(lua, function, args)
+            },
+        );
+        methods.add_method("cancel", |lua, this, ()| this.cancel(lua));
+        methods.add_async_method("wait", |_, this, ()| async move { this.wait().await });
+    }
+}
@@ -1,12 +1,21 @@
 use std::time::Duration;
 
+use futures_util::{stream::FuturesUnordered, StreamExt};
 use mlua::prelude::*;
 
-use mlua_luau_scheduler::Functions;
+use mlua_luau_scheduler::{Functions, LuaSchedulerExt};
 use tokio::time::{self, Instant};
 
 use crate::lune::util::TableBuilder;
 
+mod group;
+mod timer;
+mod tracked;
+
+use group::TaskGroup;
+use timer::{TaskTimer, TaskTimerKind};
+use tracked::TrackedThread;
+
 const DELAY_IMPL_LUA: &str = r#"
 return defer(function(...)
     wait(select(1, ...))
@@ -37,11 +46,21 @@ pub fn create(lua: &Lua) -> LuaResult<LuaTable<'_>> {
     co.set("resume", fns.resume.clone())?;
     co.set("wrap", fns.wrap.clone())?;
 
+    // Store functions needed for spawning and cancelling
+    // tracked threads, used by join, race, timeout & groups
+    tracked::store_functions(lua, fns.cancel.clone())?;
+
     TableBuilder::new(lua)?
         .with_value("cancel", fns.cancel)?
         .with_value("defer", fns.defer)?
         .with_value("delay", task_delay)?
+        .with_function("group", |_, ()| Ok(TaskGroup::new()))?
+        .with_function("interval", interval)?
+        .with_async_function("join", join)?
+        .with_async_function("race", race)?
+        .with_function("schedule", schedule)?
         .with_value("spawn", fns.spawn)?
+        .with_async_function("timeout", timeout)?
         .with_value("wait", task_wait)?
         .build_readonly()
 }
@@ -55,3 +74,140 @@ async fn wait(_: &Lua, secs: Option<f64>) -> LuaResult<f64> {
 
     Ok((after - before).as_secs_f64())
 }
+
+fn interval<'lua>(
+    lua: &'lua Lua,
+    (secs, function, args): (f64, LuaFunction<'lua>, LuaMultiValue<'lua>),
+) -> LuaResult<TaskTimer> {
+    TaskTimer::start(lua, TaskTimerKind::interval(secs)?, function, args)
+}
+
+fn schedule<'lua>(
+    lua: &'lua Lua,
+    (expression, function, args): (String, LuaFunction<'lua>, LuaMultiValue<'lua>),
+) -> LuaResult<TaskTimer> {
+    TaskTimer::start(lua, TaskTimerKind::schedule(&expression)?, function, args)
+}
+
+/**
+    A function to spawn, or a thread that was already spawned, given to join or race.
+*/
+enum TaskTarget<'lua> {
+    Function(LuaFunction<'lua>),
+    Thread(LuaThread<'lua>),
+}
+
+impl<'lua> FromLua<'lua> for TaskTarget<'lua> {
+    fn from_lua(value: LuaValue<'lua>, _: &'lua Lua) -> LuaResult<Self> {
+        match value {
+            LuaValue::Function(function) => Ok(Self::Function(function)),
+            LuaValue::Thread(thread) => Ok(Self::Thread(thread)),
+            value => Err(LuaError::RuntimeError(format!(
+                "Invalid task - expected function or thread, got {}",
+                value.type_name()
+            ))),
+        }
+    }
+}
+
+fn spawn_all<'lua>(
+    lua: &'lua Lua,
+    targets: Vec<TaskTarget<'lua>>,
+) -> LuaResult<Vec<TrackedThread<'lua>>> {
+    targets
+        .into_iter()
+        .map(|target| match target {
+            TaskTarget::Function(function) => {
+                TrackedThread::spawn(lua, function, LuaMultiValue::new())
+            }
+            TaskTarget::Thread(thread) => Ok(TrackedThread::track(lua, thread)),
+        })
+        .collect()
+}
+
+fn cancel_all<'lua>(lua: &'lua Lua, threads: &[TrackedThread<'lua>]) -> LuaResult<()> {
+    for thread in threads {
+        thread.cancel(lua)?;
+        // NOTE: Threads that finished but were never waited for
+        // still have their results stored, so we clear them here
+        lua.get_thread_result(thread.id());
+    }
+    Ok(())
+}
+
+async fn join<'lua>(lua: &'lua Lua, targets: Vec<TaskTarget<'lua>>) -> LuaResult<LuaTable<'lua>> {
+    let threads = spawn_all(lua, targets)?;
+
+    let mut results = vec![None; threads.len()];
+    let mut pending = threads
+        .iter()
+        .enumerate()
+        .map(|(index, thread)| async move { (index, thread.wait(lua).await) })
+        .collect::<FuturesUnordered<_>>();
+    while let Some((index, result)) = pending.next().await {
+        match result {
+            Ok(values) => results[index] = Some(values),
+            Err(e) => {
+                drop(pending);
+                cancel_all(lua, &threads)?;
+                return Err(e);
+            }
+        }
+    }
+
+    let table = lua.create_table_with_capacity(results.len(), 0)?;
+    for values in results {
+        let values = values.expect("Missing joined thread result");
+        table.raw_push(lua.create_sequence_from(values)?)?;
+    }
+    Ok(table)
+}
+
+async fn race<'lua>(
+    lua: &'lua Lua,
+    targets: Vec<TaskTarget<'lua>>,
+) -> LuaResult<LuaMultiValue<'lua>> {
+    if targets.is_empty() {
+        return Err(LuaError::RuntimeError(
+            "Invalid tasks - expected at least one function or thread to race".to_string(),
+        ));
+    }
+
+    let threads = spawn_all(lua, targets)?;
+
+    let mut pending = threads
+        .iter()
+        .enumerate()
+        .map(|(index, thread)| async move { (index, thread.wait(lua).await) })
+        .collect::<FuturesUnordered<_>>();
+    let (index, result) = pending.next().await.expect("Missing raced thread result");
+    drop(pending);
+
+    cancel_all(lua, &threads)?;
+
+    let mut values = result?.into_vec();
+    values.insert(0, LuaValue::Integer(index as i32 + 1));
+    Ok(LuaMultiValue::from_vec(values))
+}
+
+async fn timeout<'lua>(
+    lua: &'lua Lua,
+    (secs, function, args): (f64, LuaFunction<'lua>, LuaMultiValue<'lua>),
+) -> LuaResult<LuaMultiValue<'lua>> {
+    let duration = Duration::try_from_secs_f64(secs).map_err(|_| {
+        LuaError::RuntimeError(format!(
+            "Invalid timeout - expected a non-negative number, got {secs}"
+        ))
+    })?;
+
+    let thread = TrackedThread::spawn(lua, function, args)?;
+    match time::timeout(duration, thread.wait(lua)).await {
+        Ok(result) => result,
+        Err(_) => {
+            cancel_all(lua, &[thread])?;
+            Err(LuaError::RuntimeError(format!(
+                "Task timed out after {secs} seconds"
+            )))
+        }
+    }
+}
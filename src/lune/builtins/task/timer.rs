@@ -0,0 +1,163 @@
+use std::{
+    rc::{Rc, Weak},
+    str::FromStr,
+    time::Duration,
+};
+
+use chrono::Local;
+use cron::Schedule;
+use mlua::prelude::*;
+use mlua_luau_scheduler::{LuaSchedulerExt, LuaSpawnExt};
+use tokio::{
+    sync::watch,
+    time::{self, Instant, MissedTickBehavior},
+};
+
+/**
+    How often a [`TaskTimer`] should run its function.
+*/
+#[derive(Debug, Clone)]
+pub enum TaskTimerKind {
+    Interval(Duration),
+    Schedule(Box<Schedule>),
+}
+
+impl TaskTimerKind {
+    pub fn interval(secs: f64) -> LuaResult<Self> {
+        match Duration::try_from_secs_f64(secs) {
+            Ok(duration) if !duration.is_zero() => Ok(Self::Interval(duration)),
+            _ => Err(LuaError::RuntimeError(format!(
+                "Invalid interval - expected a positive number, got {secs}"
+            ))),
+        }
+    }
+
+    /**
+        Parses a cron expression.
+
+        Accepts both the standard five fields, starting with minutes, as well
+        as six or seven fields that start with seconds and end with years.
+    */
+    pub fn schedule(expression: &str) -> LuaResult<Self> {
+        let expression = expression.trim();
+        let full_expression = if expression.split_whitespace().count() == 5 {
+            format!("0 {expression}")
+        } else {
+            expression.to_string()
+        };
+        Schedule::from_str(&full_expression)
+            .map(|schedule| Self::Schedule(Box::new(schedule)))
+            .map_err(|e| {
+                LuaError::RuntimeError(format!("Invalid cron expression '{expression}' - {e}"))
+            })
+    }
+}
+
+/**
+    A timer that repeatedly runs a function on the scheduler, until stopped.
+
+    Each run of the function is spawned as a new thread, meaning that runs
+    that yield do not delay the next run, and errors do not stop the timer.
+*/
+#[derive(Debug, Clone)]
+pub struct TaskTimer {
+    stopped_tx: Rc<watch::Sender<bool>>,
+}
+
+impl TaskTimer {
+    pub fn start<'lua>(
+        lua: &'lua Lua,
+        kind: TaskTimerKind,
+        function: LuaFunction<'lua>,
+        args: LuaMultiValue<'lua>,
+    ) -> LuaResult<Self> {
+        let function_key = lua.create_registry_value(function)?;
+        let args_key = lua.create_registry_value(args.into_vec())?;
+
+        let inner_lua = lua
+            .app_data_ref::<Weak<Lua>>()
+            .expect("Missing weak lua ref")
+            .upgrade()
+            .expect("Lua was dropped unexpectedly");
+
+        let stopped_tx = Rc::new(watch::Sender::new(false));
+        let stopped_tx_inner = Rc::clone(&stopped_tx);
+        let mut stopped_rx = stopped_tx.subscribe();
+
+        lua.spawn_local(async move {
+            let run = || {
+                let function = inner_lua.registry_value::<LuaFunction>(&function_key)?;
+                let args = inner_lua.registry_value::<Vec<LuaValue>>(&args_key)?;
+                inner_lua.push_thread_front(function, LuaMultiValue::from_vec(args))
+            };
+            tokio::select! {
+                () = run_timer(kind, run) => {},
+                _ = stopped_rx.wait_for(|stopped| *stopped) => {},
+            }
+            // NOTE: Schedules may run out of upcoming times, in
+            // which case the timer stops without being told to
+            stopped_tx_inner.send_replace(true);
+        });
+
+        Ok(Self { stopped_tx })
+    }
+
+    pub fn is_stopped(&self) -> bool {
+        *self.stopped_tx.borrow()
+    }
+
+    pub fn stop(&self) {
+        self.stopped_tx.send_replace(true);
+    }
+}
+
+async fn run_timer<F, R>(kind: TaskTimerKind, run: F)
+where
+    F: Fn() -> LuaResult<R>,
+{
+    match kind {
+        TaskTimerKind::Interval(period) => {
+            // NOTE: Ticks are scheduled relative to when the timer started instead
+            // of when the last tick happened, so that runs do not drift over time
+            let mut interval = time::interval_at(Instant::now() + period, period);
+            interval.set_missed_tick_behavior(MissedTickBehavior::Skip);
+            loop {
+                interval.tick().await;
+                // NOTE: Pushing threads can only fail when
+                // the scheduler is no longer running
+                if run().is_err() {
+                    break;
+                }
+            }
+        }
+        TaskTimerKind::Schedule(schedule) => {
+            let mut last = Local::now();
+            loop {
+                let now = Local::now();
+                let Some(next) = schedule.after(&last.max(now)).next() else {
+                    break;
+                };
+                let wait = (next - now).to_std().unwrap_or_default();
+                time::sleep(wait).await;
+                if run().is_err() {
+                    break;
+                }
+                last = next;
+            }
+        }
+    }
+}
+
+impl LuaUserData for TaskTimer {
+    fn add_fields<'lua, F: LuaUserDataFields<'lua, Self>>(fields: &mut F) {
+        fields.add_meta_field(LuaMetaMethod::Type, "TaskTimer");
+        fields.add_field_method_get("stopped", |_, this| Ok(this.is_stopped()));
+    }
+
+    fn add_methods<'lua, M: LuaUserDataMethods<'lua, Self>>(methods: &mut M) {
+        methods.add_method("stop", |_, this, ()| {
+            this.stop();
+            Ok(())
+        });
+    }
+}
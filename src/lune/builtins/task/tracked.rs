@@ -0,0 +1,190 @@
+use mlua::prelude::*;
+
+use mlua_luau_scheduler::{LuaSchedulerExt, ThreadId};
+
+const REGISTRY_KEY_CANCEL: &str = "TaskTrackedCancel";
+const REGISTRY_KEY_PROTECTED: &str = "TaskTrackedProtected";
+
+const PROTECTED_IMPL_LUA: &str = r"
+return pcall(...)
+";
+
+/**
+    Stores the functions that tracked threads need in the registry.
+
+    Must be called once, before any tracked threads are spawned.
+*/
+pub fn store_functions<'lua>(lua: &'lua Lua, cancel: LuaFunction<'lua>) -> LuaResult<()> {
+    let protected_env = lua.create_table_from(vec![(
+        "pcall",
+        lua.globals().get::<_, LuaFunction>("pcall")?,
+    )])?;
+    let protected = lua
+        .load(PROTECTED_IMPL_LUA)
+        .set_name("=__task_protected")
+        .set_environment(protected_env)
+        .into_function()?;
+    lua.set_named_registry_value(REGISTRY_KEY_CANCEL, cancel)?;
+    lua.set_named_registry_value(REGISTRY_KEY_PROTECTED, protected)?;
+    Ok(())
+}
+
+/**
+    Converts an error value caught by `pcall` back into a [`LuaError`].
+*/
+fn value_into_error(value: LuaValue) -> LuaError {
+    match value {
+        LuaValue::Error(e) => e,
+        LuaValue::Nil => LuaError::RuntimeError("nil".to_string()),
+        value => match value.to_string() {
+            Ok(s) => LuaError::RuntimeError(s),
+            Err(e) => e,
+        },
+    }
+}
+
+/**
+    How the result of a tracked thread is retrieved once it finishes.
+*/
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum TrackedKind {
+    /// Spawned by us, running its function in protected mode.
+    Protected,
+    /// Spawned elsewhere, such as using `task.spawn` or `task.defer`.
+    Unprotected,
+    /// Spawned elsewhere, but already finished before it could be tracked.
+    Finished,
+    /// Spawned elsewhere, but already errored before it could be tracked.
+    Errored,
+}
+
+/**
+    A Lua thread spawned on the scheduler, with its result being tracked.
+
+    The function given to the thread runs in protected mode, so that
+    an error in it is given back to whoever waits for the thread,
+    instead of being reported as an unhandled error by the scheduler.
+*/
+#[derive(Debug, Clone)]
+pub struct TrackedThread<'lua> {
+    thread: LuaThread<'lua>,
+    id: ThreadId,
+    kind: TrackedKind,
+}
+
+impl<'lua> TrackedThread<'lua> {
+    /**
+        Spawns the given function with the given arguments on the scheduler.
+
+        The function will start running once the current thread yields.
+    */
+    pub fn spawn(
+        lua: &'lua Lua,
+        function: LuaFunction<'lua>,
+        args: LuaMultiValue<'lua>,
+    ) -> LuaResult<Self> {
+        let protected = lua.named_registry_value::<LuaFunction>(REGISTRY_KEY_PROTECTED)?;
+        let thread = lua.create_thread(protected)?;
+        let id = ThreadId::from(&thread);
+
+        let mut args = args.into_vec();
+        args.insert(0, LuaValue::Function(function));
+
+        lua.track_thread(id);
+        lua.push_thread_front(thread.clone(), LuaMultiValue::from_vec(args))?;
+
+        Ok(Self {
+            thread,
+            id,
+            kind: TrackedKind::Protected,
+        })
+    }
+
+    /**
+        Tracks a thread that was already spawned on the scheduler,
+        such as one returned from `task.spawn` or `task.defer`.
+
+        Unlike threads spawned using [`TrackedThread::spawn`], errors in the given thread
+        are also reported by the scheduler. Threads that already finished can not give back
+        their values, and threads that already errored can not give back their error, so
+        waiting for either of those raises an error instead.
+    */
+    pub fn track(lua: &'lua Lua, thread: LuaThread<'lua>) -> Self {
+        let id = ThreadId::from(&thread);
+        let kind = match thread.status() {
+            LuaThreadStatus::Resumable => {
+                lua.track_thread(id);
+                TrackedKind::Unprotected
+            }
+            LuaThreadStatus::Unresumable => TrackedKind::Finished,
+            LuaThreadStatus::Error => TrackedKind::Errored,
+        };
+        Self { thread, id, kind }
+    }
+
+    pub fn id(&self) -> ThreadId {
+        self.id
+    }
+
+    pub fn thread(&self) -> &LuaThread<'lua> {
+        &self.thread
+    }
+
+    /**
+        Waits for the thread to finish, returning its values or error.
+
+        Note that this will never resolve if the thread gets cancelled.
+    */
+    pub async fn wait(&self, lua: &'lua Lua) -> LuaResult<LuaMultiValue<'lua>> {
+        match self.kind {
+            TrackedKind::Protected => {
+                lua.wait_for_thread(self.id).await;
+                Self::result(lua, self.id)
+            }
+            TrackedKind::Unprotected => {
+                lua.wait_for_thread(self.id).await;
+                lua.get_thread_result(self.id)
+                    .expect("Missing tracked thread result")
+            }
+            // NOTE: The scheduler only keeps the values of threads that were tracked before
+            // they finished, returning no values here would look like the thread returned none
+            TrackedKind::Finished => Err(LuaError::RuntimeError(
+                "Thread finished before it could be waited for, so its values can not be retrieved"
+                    .to_string(),
+            )),
+            TrackedKind::Errored => Err(LuaError::RuntimeError(
+                "Thread errored before it could be waited for".to_string(),
+            )),
+        }
+    }
+
+    /**
+        Gets the result of the protected thread with the given id, which must have finished.
+    */
+    pub fn result(lua: &'lua Lua, id: ThreadId) -> LuaResult<LuaMultiValue<'lua>> {
+        let mut values = lua
+            .get_thread_result(id)
+            .expect("Missing tracked thread result")?
+            .into_vec()
+            .into_iter();
+        match values.next() {
+            Some(LuaValue::Boolean(true)) => Ok(LuaMultiValue::from_vec(values.collect())),
+            _ => Err(value_into_error(values.next().unwrap_or(LuaValue::Nil))),
+        }
+    }
+
+    /**
+        Cancels the thread, if it has not already finished.
+    */
+    pub fn cancel(&self, lua: &'lua Lua) -> LuaResult<()> {
+        cancel_thread(lua, self.thread.clone())
+    }
+}
+
+/**
+    Cancels the given thread, the same way that `task.cancel` does.
+*/
+pub fn cancel_thread<'lua>(lua: &'lua Lua, thread: LuaThread<'lua>) -> LuaResult<()> {
+    let cancel = lua.named_registry_value::<LuaFunction>(REGISTRY_KEY_CANCEL)?;
+    cancel.call(thread)
+}
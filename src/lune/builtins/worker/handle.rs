@@ -0,0 +1,196 @@
+use std::{
+    cell::RefCell,
+    rc::{Rc, Weak},
+    sync::Arc,
+    time::Duration,
+};
+
+use mlua::prelude::*;
+use mlua_luau_scheduler::{LuaSchedulerExt, LuaSpawnExt};
+use tokio::{
+    sync::{mpsc, watch, Mutex as AsyncMutex},
+    time,
+};
+
+use crate::lune::builtins::serde::encode_decode::EncodeDecodeFormat;
+
+use super::{
+    messages::{decode_message, encode_message, WorkerEvent, WorkerTermination},
+    thread::WorkerChannels,
+};
+
+const COLLECT_GARBAGE_INTERVAL: Duration = Duration::from_millis(500);
+
+/**
+    A handle to a worker, running a script on a separate OS thread.
+
+    Events sent by the worker are processed in the background for as long
+    as the worker is running, which also keeps the current script running.
+    Dropping the handle lets the worker know that no more messages will be
+    sent to it, which is how workers waiting for messages get to finish.
+*/
+#[derive(Debug, Clone)]
+pub struct LuaWorker {
+    format: EncodeDecodeFormat,
+    messages_tx: mpsc::UnboundedSender<Vec<u8>>,
+    received_rx: Rc<AsyncMutex<mpsc::UnboundedReceiver<Vec<u8>>>>,
+    error_handlers: Rc<RefCell<Vec<LuaRegistryKey>>>,
+    exit_tx: Rc<watch::Sender<Option<bool>>>,
+    termination: Arc<WorkerTermination>,
+}
+
+impl LuaWorker {
+    pub fn new(lua: &Lua, format: EncodeDecodeFormat, channels: WorkerChannels) -> Self {
+        let WorkerChannels {
+            messages_tx,
+            mut events_rx,
+            termination,
+        } = channels;
+
+        let (received_tx, received_rx) = mpsc::unbounded_channel();
+        let worker = Self {
+            format,
+            messages_tx,
+            received_rx: Rc::new(AsyncMutex::new(received_rx)),
+            error_handlers: Rc::default(),
+            exit_tx: Rc::new(watch::Sender::new(None)),
+            termination,
+        };
+
+        let inner_lua = lua
+            .app_data_ref::<Weak<Lua>>()
+            .expect("Missing weak lua ref")
+            .upgrade()
+            .expect("Lua was dropped unexpectedly");
+        // NOTE: This must not hold onto the sender for messages, since
+        // dropping the handle is what lets the worker know that no more
+        // messages will be sent, and the only thing that the handle holds
+        // onto by itself is the receiver, which tells us if it was dropped
+        let handle_rx = Rc::downgrade(&worker.received_rx);
+        let error_handlers = Rc::clone(&worker.error_handlers);
+        let exit_tx = Rc::clone(&worker.exit_tx);
+
+        lua.spawn_local(async move {
+            loop {
+                let event = tokio::select! {
+                    event = events_rx.recv() => event,
+                    // NOTE: Handles are only dropped once they get garbage collected, which
+                    // may never happen by itself when the script has nothing left to run,
+                    // so we collect garbage whenever the worker has been quiet for a while
+                    () = time::sleep(COLLECT_GARBAGE_INTERVAL), if handle_rx.strong_count() > 0 => {
+                        inner_lua.gc_collect().ok();
+                        continue;
+                    }
+                };
+                match event {
+                    Some(WorkerEvent::Message(message)) => {
+                        received_tx.send(message).ok();
+                    }
+                    Some(WorkerEvent::Error(message)) => {
+                        dispatch_error(&inner_lua, &error_handlers, message);
+                    }
+                    Some(WorkerEvent::Exit(success)) => {
+                        exit_tx.send_replace(Some(success));
+                        break;
+                    }
+                    None => break,
+                }
+            }
+            // NOTE: The worker thread may have panicked and
+            // never sent its exit event, so we also exit here
+            exit_tx.send_if_modified(|exit| {
+                if exit.is_none() {
+                    exit.replace(false);
+                    true
+                } else {
+                    false
+                }
+            });
+        });
+
+        worker
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.exit_tx.borrow().is_none()
+    }
+
+    pub fn send(&self, lua: &Lua, value: LuaValue) -> LuaResult<()> {
+        let message = encode_message(lua, self.format, value)?;
+        if self.is_running() && self.messages_tx.send(message).is_ok() {
+            Ok(())
+        } else {
+            Err(LuaError::runtime("Worker is no longer running"))
+        }
+    }
+
+    pub async fn recv<'lua>(&self, lua: &'lua Lua) -> LuaResult<LuaValue<'lua>> {
+        match self.received_rx.lock().await.recv().await {
+            Some(message) => decode_message(lua, self.format, message),
+            None => Ok(LuaValue::Nil),
+        }
+    }
+
+    pub fn on_error(&self, lua: &Lua, handler: LuaFunction) -> LuaResult<()> {
+        let key = lua.create_registry_value(handler)?;
+        self.error_handlers.borrow_mut().push(key);
+        Ok(())
+    }
+
+    pub fn terminate(&self) {
+        self.termination.terminate();
+    }
+
+    /**
+        Waits for the worker to exit, returning `true` if it exited successfully.
+    */
+    pub async fn wait(&self) -> bool {
+        let mut exit_rx = self.exit_tx.subscribe();
+        let exit = exit_rx
+            .wait_for(Option::is_some)
+            .await
+            .expect("Worker exit sender was dropped");
+        exit.unwrap_or_default()
+    }
+}
+
+/**
+    Calls all of the given error handlers with an error message from a worker.
+
+    Errors that no handlers have been added for are printed instead, so that they are never silently lost.
+*/
+fn dispatch_error(lua: &Lua, error_handlers: &RefCell<Vec<LuaRegistryKey>>, message: String) {
+    let handlers = error_handlers
+        .borrow()
+        .iter()
+        .filter_map(|key| lua.registry_value::<LuaFunction>(key).ok())
+        .collect::<Vec<_>>();
+    if handlers.is_empty() {
+        eprintln!("{message}");
+    }
+    for handler in handlers {
+        // NOTE: Errors in handlers are reported by the scheduler, and pushing
+        // threads can only fail when the scheduler is no longer running
+        lua.push_thread_front(handler, message.clone()).ok();
+    }
+}
+
+impl LuaUserData for LuaWorker {
+    fn add_fields<'lua, F: LuaUserDataFields<'lua, Self>>(fields: &mut F) {
+        fields.add_meta_field(LuaMetaMethod::Type, "Worker");
+        fields.add_field_method_get("running", |_, this| Ok(this.is_running()));
+    }
+
+    fn add_methods<'lua, M: LuaUserDataMethods<'lua, Self>>(methods: &mut M) {
+        methods.add_method("send", |lua, this, value: LuaValue| this.send(lua, value));
+        methods.add_async_method("recv", |lua, this, ()| async move { this.recv(lua).await });
+        methods.add_method("onError", |lua, this, handler: LuaFunction| {
+            this.on_error(lua, handler)
+        });
+        methods.add_method("terminate", |_, this, ()| {
+            this.terminate();
+            Ok(())
+        });
+        methods.add_async_method("wait", |_, this, ()| async move { Ok(this.wait().await) });
+    }
+}
@@ -0,0 +1,69 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use bstr::BString;
+use mlua::prelude::*;
+use tokio::sync::Notify;
+
+use crate::lune::builtins::serde::encode_decode::{EncodeDecodeConfig, EncodeDecodeFormat};
+
+/**
+    An event sent from a worker thread to the thread that spawned it.
+*/
+#[derive(Debug, Clone)]
+pub enum WorkerEvent {
+    Message(Vec<u8>),
+    Error(String),
+    Exit(bool),
+}
+
+/**
+    A signal for terminating a worker, shared between the worker and the thread that spawned it.
+*/
+#[derive(Debug, Default)]
+pub struct WorkerTermination {
+    terminated: AtomicBool,
+    notify: Notify,
+}
+
+impl WorkerTermination {
+    pub fn is_terminated(&self) -> bool {
+        self.terminated.load(Ordering::Relaxed)
+    }
+
+    pub fn terminate(&self) {
+        self.terminated.store(true, Ordering::Relaxed);
+        self.notify.notify_one();
+    }
+
+    pub async fn wait(&self) {
+        self.notify.notified().await;
+    }
+}
+
+/**
+    Encodes a value to send between threads, using the given format.
+
+    Sending `nil` is not allowed, since receiving `nil` is used
+    to signal that there will be no more messages to receive.
+*/
+pub fn encode_message(
+    lua: &Lua,
+    format: EncodeDecodeFormat,
+    value: LuaValue,
+) -> LuaResult<Vec<u8>> {
+    if value.is_nil() {
+        return Err(LuaError::RuntimeError(
+            "Invalid message - expected a value other than nil".to_string(),
+        ));
+    }
+    let encoded = EncodeDecodeConfig::from(format).serialize_to_string(lua, value)?;
+    Ok(encoded.as_bytes().to_vec())
+}
+
+pub fn decode_message(
+    lua: &Lua,
+    format: EncodeDecodeFormat,
+    bytes: Vec<u8>,
+) -> LuaResult<LuaValue> {
+    EncodeDecodeConfig::from(format).deserialize_from_string(lua, BString::from(bytes))
+}
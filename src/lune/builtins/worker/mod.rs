@@ -0,0 +1,77 @@
+use std::path::PathBuf;
+
+use mlua::prelude::*;
+
+use crate::lune::util::TableBuilder;
+
+mod handle;
+mod messages;
+mod options;
+mod thread;
+
+use self::{
+    handle::LuaWorker,
+    options::WorkerOptions,
+    thread::{spawn_worker_thread, WorkerContext, WorkerScript},
+};
+
+const ERR_NOT_WORKER: &str = "This function may only be called from inside of a worker";
+
+pub fn create(lua: &Lua) -> LuaResult<LuaTable> {
+    TableBuilder::new(lua)?
+        .with_value("isWorker", WorkerContext::get(lua).is_some())?
+        .with_async_function("spawn", worker_spawn)?
+        .with_function("spawnSource", worker_spawn_source)?
+        .with_function("send", worker_send)?
+        .with_async_function("recv", worker_recv)?
+        .build_readonly()
+}
+
+async fn worker_spawn(lua: &Lua, (path, options): (String, WorkerOptions)) -> LuaResult<LuaWorker> {
+    let path = PathBuf::from(path);
+    let contents = tokio::fs::read(&path).await.map_err(|e| {
+        LuaError::RuntimeError(format!(
+            "Failed to read worker script at '{}' - {e}",
+            path.display()
+        ))
+    })?;
+    // NOTE: We skip the extension here to remove it from stack traces,
+    // the same way that the cli does it for the script being ran
+    let script = WorkerScript {
+        name: options
+            .name
+            .clone()
+            .unwrap_or_else(|| path.with_extension("").display().to_string()),
+        contents,
+    };
+    let format = options.format;
+    let channels = spawn_worker_thread(script, options)?;
+    Ok(LuaWorker::new(lua, format, channels))
+}
+
+fn worker_spawn_source(
+    lua: &Lua,
+    (source, options): (LuaString, WorkerOptions),
+) -> LuaResult<LuaWorker> {
+    let script = WorkerScript {
+        name: options.name.clone().unwrap_or_else(|| "worker".to_string()),
+        contents: source.as_bytes().to_vec(),
+    };
+    let format = options.format;
+    let channels = spawn_worker_thread(script, options)?;
+    Ok(LuaWorker::new(lua, format, channels))
+}
+
+fn worker_send(lua: &Lua, value: LuaValue) -> LuaResult<()> {
+    match WorkerContext::get(lua) {
+        Some(context) => context.send(lua, value),
+        None => Err(LuaError::runtime(ERR_NOT_WORKER)),
+    }
+}
+
+async fn worker_recv(lua: &Lua, (): ()) -> LuaResult<LuaValue> {
+    match WorkerContext::get(lua) {
+        Some(context) => context.recv(lua).await,
+        None => Err(LuaError::runtime(ERR_NOT_WORKER)),
+    }
+}
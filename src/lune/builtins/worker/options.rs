@@ -0,0 +1,61 @@
+use mlua::prelude::*;
+
+use crate::lune::builtins::serde::encode_decode::EncodeDecodeFormat;
+
+/**
+    Options for spawning a worker, given as the last argument to `worker.spawn`.
+*/
+#[derive(Debug, Clone)]
+pub struct WorkerOptions {
+    pub format: EncodeDecodeFormat,
+    pub args: Vec<String>,
+    pub name: Option<String>,
+}
+
+impl Default for WorkerOptions {
+    fn default() -> Self {
+        Self {
+            format: EncodeDecodeFormat::MsgPack,
+            args: Vec::new(),
+            name: None,
+        }
+    }
+}
+
+impl<'lua> FromLua<'lua> for WorkerOptions {
+    fn from_lua(value: LuaValue<'lua>, lua: &'lua Lua) -> LuaResult<Self> {
+        let defaults = Self::default();
+        match value {
+            LuaValue::Nil => Ok(defaults),
+            LuaValue::Table(t) => {
+                let format = match t.get::<_, LuaValue>("format")? {
+                    LuaValue::Nil => defaults.format,
+                    value => match EncodeDecodeFormat::from_lua(value, lua)? {
+                        format @ (EncodeDecodeFormat::Json
+                        | EncodeDecodeFormat::MsgPack
+                        | EncodeDecodeFormat::Cbor) => format,
+                        _ => {
+                            return Err(LuaError::RuntimeError(
+                                "Invalid option 'format' - expected one of json, msgpack, cbor"
+                                    .to_string(),
+                            ))
+                        }
+                    },
+                };
+                Ok(Self {
+                    format,
+                    args: t.get::<_, Option<Vec<String>>>("args")?.unwrap_or_default(),
+                    name: t.get("name")?,
+                })
+            }
+            value => Err(LuaError::FromLuaConversionError {
+                from: value.type_name(),
+                to: "WorkerOptions",
+                message: Some(format!(
+                    "Invalid worker options - expected table or nil, got {}",
+                    value.type_name()
+                )),
+            }),
+        }
+    }
+}
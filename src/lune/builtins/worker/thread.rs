@@ -0,0 +1,168 @@
+use std::{process::ExitCode, rc::Rc, sync::Arc, thread};
+
+use mlua::prelude::*;
+use tokio::{
+    runtime::Builder as RuntimeBuilder,
+    sync::{mpsc, Mutex as AsyncMutex},
+};
+
+use crate::lune::{
+    builtins::serde::encode_decode::EncodeDecodeFormat, util::interrupts::Interrupts, Runtime,
+    RuntimeError,
+};
+
+use super::{
+    messages::{decode_message, encode_message, WorkerEvent, WorkerTermination},
+    options::WorkerOptions,
+};
+
+/**
+    A script to run in a worker, read from a file or given as source code.
+*/
+#[derive(Debug, Clone)]
+pub struct WorkerScript {
+    pub name: String,
+    pub contents: Vec<u8>,
+}
+
+/**
+    State available from inside of a worker thread, used
+    to communicate with the thread that spawned the worker.
+*/
+#[derive(Debug, Clone)]
+pub struct WorkerContext {
+    format: EncodeDecodeFormat,
+    events_tx: mpsc::UnboundedSender<WorkerEvent>,
+    messages_rx: Rc<AsyncMutex<mpsc::UnboundedReceiver<Vec<u8>>>>,
+}
+
+impl WorkerContext {
+    /**
+        Gets the worker context for the current Luau VM, if it is running inside of a worker.
+    */
+    pub fn get(lua: &Lua) -> Option<Self> {
+        lua.app_data_ref::<Self>().map(|context| context.clone())
+    }
+
+    pub fn send(&self, lua: &Lua, value: LuaValue) -> LuaResult<()> {
+        let message = encode_message(lua, self.format, value)?;
+        // NOTE: The receiving end lives as long as the worker
+        // handle in the parent, and the worker thread itself
+        self.events_tx.send(WorkerEvent::Message(message)).ok();
+        Ok(())
+    }
+
+    pub async fn recv<'lua>(&self, lua: &'lua Lua) -> LuaResult<LuaValue<'lua>> {
+        match self.messages_rx.lock().await.recv().await {
+            Some(message) => decode_message(lua, self.format, message),
+            None => Ok(LuaValue::Nil),
+        }
+    }
+}
+
+/**
+    The ends of the channels used by the thread that spawned a worker.
+*/
+#[derive(Debug)]
+pub struct WorkerChannels {
+    pub messages_tx: mpsc::UnboundedSender<Vec<u8>>,
+    pub events_rx: mpsc::UnboundedReceiver<WorkerEvent>,
+    pub termination: Arc<WorkerTermination>,
+}
+
+/**
+    Spawns a new OS thread, running the given script in a new, isolated Lune runtime.
+*/
+pub fn spawn_worker_thread(
+    script: WorkerScript,
+    options: WorkerOptions,
+) -> LuaResult<WorkerChannels> {
+    let (messages_tx, messages_rx) = mpsc::unbounded_channel();
+    let (events_tx, events_rx) = mpsc::unbounded_channel();
+    let termination = Arc::new(WorkerTermination::default());
+
+    let termination_inner = Arc::clone(&termination);
+    thread::Builder::new()
+        .name(format!("lune-worker ({})", script.name))
+        .spawn(move || {
+            let success = run_worker(
+                script,
+                options,
+                messages_rx,
+                events_tx.clone(),
+                termination_inner,
+            );
+            events_tx.send(WorkerEvent::Exit(success)).ok();
+        })?;
+
+    Ok(WorkerChannels {
+        messages_tx,
+        events_rx,
+        termination,
+    })
+}
+
+fn run_worker(
+    script: WorkerScript,
+    options: WorkerOptions,
+    messages_rx: mpsc::UnboundedReceiver<Vec<u8>>,
+    events_tx: mpsc::UnboundedSender<WorkerEvent>,
+    termination: Arc<WorkerTermination>,
+) -> bool {
+    let send_error = {
+        let events_tx = events_tx.clone();
+        move |error: &RuntimeError| {
+            let message = error.clone().disable_colors().to_string();
+            events_tx.send(WorkerEvent::Error(message)).ok();
+        }
+    };
+
+    let rt = match RuntimeBuilder::new_current_thread().enable_all().build() {
+        Ok(rt) => rt,
+        Err(e) => {
+            send_error(&LuaError::external(e).into());
+            return false;
+        }
+    };
+
+    // NOTE: Errors are printed by the thread that spawned the
+    // worker, and only if that thread has no handlers for them
+    let mut runtime = Runtime::new()
+        .with_args(options.args)
+        .with_error_printing(false)
+        .with_error_callback(send_error.clone());
+
+    runtime.lua().set_app_data(WorkerContext {
+        format: options.format,
+        events_tx,
+        messages_rx: Rc::new(AsyncMutex::new(messages_rx)),
+    });
+
+    // NOTE: Yielding from the interrupt stops any running Luau code, even
+    // if it never yields by itself, which gives the scheduler a chance to
+    // notice that the worker was terminated and stop running altogether
+    let termination_interrupt = Arc::clone(&termination);
+    Interrupts::add(runtime.lua(), move |_| {
+        if termination_interrupt.is_terminated() {
+            Ok(LuaVmState::Yield)
+        } else {
+            Ok(LuaVmState::Continue)
+        }
+    });
+
+    // NOTE: Terminated workers may also finish running normally, since
+    // yielding from the interrupt may leave no more threads to resume
+    let success = rt.block_on(async {
+        tokio::select! {
+            result = runtime.run(&script.name, script.contents) => match result {
+                Ok(code) => code == ExitCode::SUCCESS,
+                Err(e) => {
+                    send_error(&e);
+                    false
+                }
+            },
+            () = termination.wait() => false,
+        }
+    });
+    success && !termination.is_terminated()
+}
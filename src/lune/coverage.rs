@@ -0,0 +1,288 @@
+use std::{
+    cell::{Cell, RefCell},
+    collections::BTreeMap,
+    fmt::Write as _,
+    rc::Rc,
+    str::FromStr,
+};
+
+use mlua::prelude::*;
+use serde::Serialize;
+
+use crate::lune::{update_compiler, util::paths::chunk_source_path};
+
+/**
+    A format that a [`CoverageReport`] can be written in.
+*/
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum CoverageFormat {
+    #[default]
+    Lcov,
+    Json,
+}
+
+impl CoverageFormat {
+    /**
+        Gets the file extension typically used for this format.
+    */
+    pub fn extension(self) -> &'static str {
+        match self {
+            Self::Lcov => "lcov",
+            Self::Json => "json",
+        }
+    }
+}
+
+impl FromStr for CoverageFormat {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "lcov" => Ok(Self::Lcov),
+            "json" => Ok(Self::Json),
+            _ => Err(format!(
+                "Invalid coverage format '{s}' - expected one of lcov, json"
+            )),
+        }
+    }
+}
+
+impl<'lua> FromLua<'lua> for CoverageFormat {
+    fn from_lua(value: LuaValue<'lua>, _: &'lua Lua) -> LuaResult<Self> {
+        match value {
+            LuaValue::Nil => Ok(Self::default()),
+            LuaValue::String(s) => s.to_str()?.parse().map_err(LuaError::RuntimeError),
+            value => Err(LuaError::FromLuaConversionError {
+                from: value.type_name(),
+                to: "CoverageFormat",
+                message: Some(format!(
+                    "Invalid coverage format - expected string, got {}",
+                    value.type_name()
+                )),
+            }),
+        }
+    }
+}
+
+/**
+    Coverage for a single function in a source file.
+*/
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct FunctionCoverage {
+    pub name: String,
+    pub line: usize,
+    pub hits: u64,
+}
+
+/**
+    Coverage for a single source file, with hit counts for each function and line.
+*/
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct FileCoverage {
+    pub functions: Vec<FunctionCoverage>,
+    pub lines: BTreeMap<usize, u64>,
+}
+
+impl FileCoverage {
+    fn merge(&mut self, other: FileCoverage) {
+        for function in other.functions {
+            let existing = self
+                .functions
+                .iter_mut()
+                .find(|f| f.name == function.name && f.line == function.line);
+            match existing {
+                Some(existing) => existing.hits += function.hits,
+                None => self.functions.push(function),
+            }
+        }
+        for (line, hits) in other.lines {
+            *self.lines.entry(line).or_default() += hits;
+        }
+    }
+}
+
+/**
+    Code coverage collected for all chunks loaded while coverage was enabled.
+
+    Chunks are keyed by the path of their source file, or by
+    their chunk name if no such file exists, such as for code
+    loaded from strings using `luau.load`.
+*/
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+#[serde(transparent)]
+pub struct CoverageReport {
+    files: BTreeMap<String, FileCoverage>,
+}
+
+impl CoverageReport {
+    /**
+        Gets the coverage for each source file in this report.
+    */
+    pub fn files(&self) -> &BTreeMap<String, FileCoverage> {
+        &self.files
+    }
+
+    /**
+        Writes this report in the given format.
+    */
+    pub fn to_format(&self, format: CoverageFormat) -> String {
+        match format {
+            CoverageFormat::Lcov => self.to_lcov(),
+            CoverageFormat::Json => self.to_json(),
+        }
+    }
+
+    /**
+        Writes this report in the lcov tracefile format.
+    */
+    pub fn to_lcov(&self) -> String {
+        let mut lcov = String::new();
+        for (path, file) in &self.files {
+            // NOTE: Writing to a string can not fail, so we ignore results here
+            writeln!(lcov, "TN:").ok();
+            writeln!(lcov, "SF:{path}").ok();
+            for function in &file.functions {
+                writeln!(lcov, "FN:{},{}", function.line, function.name).ok();
+            }
+            for function in &file.functions {
+                writeln!(lcov, "FNDA:{},{}", function.hits, function.name).ok();
+            }
+            let functions_hit = file.functions.iter().filter(|f| f.hits > 0).count();
+            writeln!(lcov, "FNF:{}", file.functions.len()).ok();
+            writeln!(lcov, "FNH:{functions_hit}").ok();
+            for (line, hits) in &file.lines {
+                writeln!(lcov, "DA:{line},{hits}").ok();
+            }
+            let lines_hit = file.lines.values().filter(|hits| **hits > 0).count();
+            writeln!(lcov, "LF:{}", file.lines.len()).ok();
+            writeln!(lcov, "LH:{lines_hit}").ok();
+            writeln!(lcov, "end_of_record").ok();
+        }
+        lcov
+    }
+
+    /**
+        Writes this report as pretty-printed JSON.
+    */
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).expect("Coverage report should always serialize")
+    }
+}
+
+/**
+    Code coverage collection for a Luau VM.
+
+    While enabled, all chunks loaded into the VM are compiled with coverage
+    enabled, and are kept around so that their coverage can be reported.
+*/
+#[derive(Debug, Default)]
+pub struct Coverage {
+    level: Cell<u8>,
+    chunks: RefCell<Vec<(String, LuaRegistryKey)>>,
+}
+
+impl Coverage {
+    /**
+        Gets the coverage state for the given Lua state, if coverage has ever been enabled.
+    */
+    pub fn get(lua: &Lua) -> Option<Rc<Self>> {
+        lua.app_data_ref::<Rc<Self>>().map(|this| Rc::clone(&this))
+    }
+
+    /**
+        Enables coverage for any chunks loaded after this call.
+
+        The level is the same as the Luau compiler option `coverageLevel`,
+        and should be either `1` for statement coverage, or `2` to also
+        include coverage for expressions.
+    */
+    pub fn enable(lua: &Lua, level: u8) {
+        let this = Self::get(lua).unwrap_or_else(|| {
+            let this = Rc::new(Self::default());
+            lua.set_app_data(Rc::clone(&this));
+            this
+        });
+        this.level.set(level);
+        update_compiler(lua);
+    }
+
+    /**
+        Disables coverage for any chunks loaded after this call.
+
+        Coverage for chunks that have already been loaded is kept, and will still be reported.
+    */
+    pub fn disable(lua: &Lua) {
+        if let Some(this) = Self::get(lua) {
+            this.level.set(0);
+            update_compiler(lua);
+        }
+    }
+
+    /**
+        Gets the current coverage level, which is `0` while coverage is disabled.
+    */
+    pub fn level(&self) -> u8 {
+        self.level.get()
+    }
+
+    /**
+        Tracks coverage for a newly loaded chunk, if coverage is currently enabled.
+    */
+    pub fn track(lua: &Lua, name: impl Into<String>, function: &LuaFunction) -> LuaResult<()> {
+        if let Some(this) = Self::get(lua) {
+            if this.level() > 0 {
+                let key = lua.create_registry_value(function.clone())?;
+                this.chunks.borrow_mut().push((name.into(), key));
+            }
+        }
+        Ok(())
+    }
+
+    /**
+        Creates a report containing the current coverage for all tracked chunks.
+    */
+    pub fn report(&self, lua: &Lua) -> LuaResult<CoverageReport> {
+        let mut report = CoverageReport::default();
+        for (name, key) in self.chunks.borrow().iter() {
+            let function = lua.registry_value::<LuaFunction>(key)?;
+            report
+                .files
+                .entry(chunk_source_path(name))
+                .or_default()
+                .merge(chunk_coverage(&function));
+        }
+        Ok(report)
+    }
+}
+
+fn chunk_coverage(function: &LuaFunction) -> FileCoverage {
+    let mut coverage = FileCoverage::default();
+    function.coverage(|info| {
+        let line = usize::try_from(info.line_defined).unwrap_or_default();
+        let name = match info.function {
+            Some(name) => name,
+            None if info.depth == 0 => "<main>".to_string(),
+            None => format!("<anonymous:{line}>"),
+        };
+
+        // NOTE: Hits are given for each line in the source file, where lines
+        // without any code have negative hit counts and should be skipped
+        let mut function_hits = None;
+        for (line, hits) in info.hits.iter().enumerate() {
+            let Ok(hits) = u64::try_from(*hits) else {
+                continue;
+            };
+            function_hits.get_or_insert(hits);
+            // NOTE: Lines may contain code for multiple functions, such as
+            // one-line functions, and the most hit one is the most accurate
+            let line_hits = coverage.lines.entry(line).or_default();
+            *line_hits = (*line_hits).max(hits);
+        }
+
+        coverage.functions.push(FunctionCoverage {
+            name,
+            line,
+            hits: function_hits.unwrap_or_default(),
+        });
+    });
+    coverage
+}
@@ -0,0 +1,165 @@
+use std::ffi::{c_int, CStr};
+
+use mlua::{ffi, prelude::*};
+
+/**
+    The name of the registry value containing the function that is called for breakpoints.
+*/
+pub const BREAK_HANDLER: &str = "__luneweb_debugger_break";
+const BREAK_HANDLER_C: &CStr = c"__luneweb_debugger_break";
+
+/**
+    Functions implemented using the Luau debug API, which `mlua` does not provide.
+
+    Stack frames are given to these functions as positions from the bottom of
+    the stack, since their levels change depending on where they are called from.
+*/
+#[derive(Debug)]
+pub struct DebugFunctions {
+    set_breakpoint: LuaRegistryKey,
+    frame_function: LuaRegistryKey,
+    frame_locals: LuaRegistryKey,
+}
+
+impl DebugFunctions {
+    /**
+        Creates debug functions for the given Lua state, and makes the Luau VM call the
+        function stored in the registry under [`BREAK_HANDLER`] for any breakpoints hit.
+    */
+    pub fn install(lua: &Lua) -> LuaResult<Self> {
+        // SAFETY: All of these functions only use the arguments given to them,
+        // and keep the stack balanced, returning exactly what they push
+        let (install, set_breakpoint, frame_function, frame_locals) = unsafe {
+            (
+                lua.create_c_function(install_callbacks)?,
+                lua.create_c_function(set_breakpoint)?,
+                lua.create_c_function(frame_function)?,
+                lua.create_c_function(frame_locals)?,
+            )
+        };
+        install.call::<_, ()>(())?;
+        Ok(Self {
+            set_breakpoint: lua.create_registry_value(set_breakpoint)?,
+            frame_function: lua.create_registry_value(frame_function)?,
+            frame_locals: lua.create_registry_value(frame_locals)?,
+        })
+    }
+
+    /**
+        Enables or disables a breakpoint in the given function, or any function
+        defined inside of it, at the first line with code at or after the given line.
+
+        Returns the line that the breakpoint was set at, if any.
+    */
+    pub fn set_breakpoint(
+        &self,
+        lua: &Lua,
+        function: &LuaFunction,
+        line: usize,
+        enabled: bool,
+    ) -> LuaResult<Option<usize>> {
+        let set_breakpoint = lua.registry_value::<LuaFunction>(&self.set_breakpoint)?;
+        let line = set_breakpoint.call::<_, i64>((function.clone(), line, enabled))?;
+        Ok(usize::try_from(line).ok())
+    }
+
+    /**
+        Gets the function running at the given stack position of the current thread.
+    */
+    pub fn frame_function<'lua>(
+        &self,
+        lua: &'lua Lua,
+        position: usize,
+    ) -> LuaResult<Option<LuaFunction<'lua>>> {
+        let frame_function = lua.registry_value::<LuaFunction>(&self.frame_function)?;
+        frame_function.call(position)
+    }
+
+    /**
+        Gets the names and values of all local variables that are currently
+        in scope at the given stack position of the current thread.
+    */
+    pub fn frame_locals<'lua>(
+        &self,
+        lua: &'lua Lua,
+        position: usize,
+    ) -> LuaResult<Vec<(String, LuaValue<'lua>)>> {
+        let frame_locals = lua.registry_value::<LuaFunction>(&self.frame_locals)?;
+        let (names, values) = frame_locals.call::<_, (LuaTable, LuaTable)>(position)?;
+        let mut locals = Vec::new();
+        for (index, name) in names.sequence_values::<String>().enumerate() {
+            locals.push((name?, values.raw_get(index + 1)?));
+        }
+        Ok(locals)
+    }
+}
+
+/**
+    Gets the stack level for a position from the bottom of the stack.
+*/
+unsafe fn position_level(state: *mut ffi::lua_State, position: c_int) -> c_int {
+    ffi::lua_stackdepth(state) - position
+}
+
+unsafe extern "C-unwind" fn on_break(state: *mut ffi::lua_State, _: *mut ffi::lua_Debug) {
+    if ffi::lua_rawgetfield(state, ffi::LUA_REGISTRYINDEX, BREAK_HANDLER_C.as_ptr())
+        == ffi::LUA_TFUNCTION
+    {
+        ffi::lua_call(state, 0, 0);
+    } else {
+        ffi::lua_pop(state, 1);
+    }
+}
+
+unsafe extern "C-unwind" fn install_callbacks(state: *mut ffi::lua_State) -> c_int {
+    // NOTE: The userdata of callbacks is used by mlua, so we must only set the callbacks we need
+    (*ffi::lua_callbacks(state)).debugbreak = Some(on_break);
+    0
+}
+
+unsafe extern "C-unwind" fn set_breakpoint(state: *mut ffi::lua_State) -> c_int {
+    let line = c_int::try_from(ffi::lua_tointeger(state, 2)).unwrap_or(c_int::MAX);
+    let enabled = ffi::lua_toboolean(state, 3);
+    let target = ffi::lua_breakpoint(state, 1, line, enabled);
+    ffi::lua_pushinteger(state, ffi::lua_Integer::from(target));
+    1
+}
+
+unsafe extern "C-unwind" fn frame_function(state: *mut ffi::lua_State) -> c_int {
+    let position = c_int::try_from(ffi::lua_tointeger(state, 1)).unwrap_or(c_int::MAX);
+    let mut ar = std::mem::zeroed::<ffi::lua_Debug>();
+    if ffi::lua_getinfo(
+        state,
+        position_level(state, position),
+        c"f".as_ptr(),
+        &mut ar,
+    ) == 0
+    {
+        ffi::lua_pushnil(state);
+    }
+    1
+}
+
+unsafe extern "C-unwind" fn frame_locals(state: *mut ffi::lua_State) -> c_int {
+    let position = c_int::try_from(ffi::lua_tointeger(state, 1)).unwrap_or(c_int::MAX);
+    let level = position_level(state, position);
+
+    ffi::lua_createtable(state, 0, 0);
+    ffi::lua_createtable(state, 0, 0);
+    let names = ffi::lua_gettop(state) - 1;
+    let values = names + 1;
+
+    let mut n = 1;
+    loop {
+        let name = ffi::lua_getlocal(state, level, n);
+        if name.is_null() {
+            break;
+        }
+        ffi::lua_rawseti(state, values, n);
+        ffi::lua_pushstring(state, name);
+        ffi::lua_rawseti(state, names, n);
+        n += 1;
+    }
+
+    2
+}
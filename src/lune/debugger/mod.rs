@@ -0,0 +1,668 @@
+use std::{
+    cell::{Cell, RefCell},
+    path::PathBuf,
+    rc::Rc,
+    sync::{atomic::Ordering, mpsc::Receiver, Arc},
+};
+
+use mlua::prelude::*;
+use serde_json::{json, Value as JsonValue};
+
+use crate::lune::{
+    update_compiler,
+    util::{
+        interrupts::Interrupts,
+        paths::{chunk_source_path, make_absolute_and_clean},
+    },
+};
+
+mod ffi;
+mod protocol;
+mod server;
+
+pub use server::DebugServer;
+
+use ffi::{DebugFunctions, BREAK_HANDLER};
+use protocol::Request;
+use server::{Command, ResumeKind, Shared, SourceBreakpoint};
+
+/**
+    The id of the only thread reported to clients.
+
+    Luau runs all of its threads on a single OS thread, and pausing
+    at a breakpoint pauses all of them, so we report a single thread
+    whose stack is the stack of the Luau thread that was paused.
+*/
+pub(crate) const THREAD_ID: i64 = 1;
+
+/**
+    A chunk loaded into the VM, together with the lines that breakpoints are currently set at.
+*/
+#[derive(Debug)]
+struct Chunk {
+    path: PathBuf,
+    function: LuaRegistryKey,
+    lines: Vec<usize>,
+}
+
+/**
+    A location in running code, used to tell when stepping has reached a new line.
+
+    The position of a location is the position of its stack
+    frame, counted from the bottom of the stack of its thread.
+*/
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Location {
+    thread: usize,
+    position: usize,
+    line: Option<usize>,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Step {
+    kind: ResumeKind,
+    from: Location,
+}
+
+impl Step {
+    fn is_done(&self, location: Location) -> bool {
+        if location.thread != self.from.thread {
+            return false;
+        }
+        match self.kind {
+            ResumeKind::Continue => false,
+            ResumeKind::StepOver => {
+                location.position <= self.from.position && location != self.from
+            }
+            ResumeKind::StepIn => location != self.from,
+            ResumeKind::StepOut => location.position < self.from.position,
+        }
+    }
+}
+
+#[derive(Debug)]
+struct Frame {
+    position: usize,
+    name: String,
+    source: String,
+    path: Option<PathBuf>,
+    line: Option<usize>,
+    is_luau: bool,
+}
+
+#[derive(Debug)]
+enum Reference {
+    Locals(usize),
+    Globals,
+    Table(LuaRegistryKey),
+}
+
+/**
+    A debugger attached to a Luau VM, controlled by a client connected to a [`DebugServer`].
+
+    Breakpoints are set using the Luau debug API, which calls back into
+    the debugger when they are hit. Stepping is done by setting temporary
+    breakpoints on every line of the functions that may run next, and uses
+    interrupts to stop in any functions called when stepping into calls.
+*/
+pub struct Debugger {
+    shared: Arc<Shared>,
+    commands: Receiver<Command>,
+    functions: DebugFunctions,
+    chunks: RefCell<Vec<Chunk>>,
+    temporary: RefCell<Vec<(LuaRegistryKey, Vec<usize>)>>,
+    step: Cell<Option<Step>>,
+    paused: Cell<bool>,
+    frames: RefCell<Vec<Frame>>,
+    references: RefCell<Vec<Reference>>,
+}
+
+impl Debugger {
+    /**
+        Gets the debugger attached to the given Lua state, if any.
+    */
+    pub fn get(lua: &Lua) -> Option<Rc<Self>> {
+        lua.app_data_ref::<Rc<Self>>().map(|this| Rc::clone(&this))
+    }
+
+    /**
+        Attaches a debugger to the given Lua state, controlled by the given server.
+
+        Chunks loaded after this are compiled with full debug info, and without
+        native code generation, since variables in natively compiled
+        functions can not be inspected.
+    */
+    pub fn attach(lua: &Lua, server: &DebugServer) -> LuaResult<()> {
+        let commands = server
+            .commands
+            .lock()
+            .expect("Debugger commands lock was poisoned")
+            .take()
+            .ok_or_else(|| {
+                LuaError::RuntimeError("Debug server is already attached to a runtime".to_string())
+            })?;
+
+        let this = Rc::new(Self {
+            shared: Arc::clone(&server.shared),
+            commands,
+            functions: DebugFunctions::install(lua)?,
+            chunks: RefCell::new(Vec::new()),
+            temporary: RefCell::new(Vec::new()),
+            step: Cell::new(None),
+            paused: Cell::new(false),
+            frames: RefCell::new(Vec::new()),
+            references: RefCell::new(Vec::new()),
+        });
+        lua.set_app_data(Rc::clone(&this));
+
+        // NOTE: Breakpoints set before attaching are set for each chunk once it
+        // is loaded, so there is nothing to sync for any earlier changes
+        this.shared
+            .breakpoints_changed
+            .store(false, Ordering::SeqCst);
+
+        let on_break = lua.create_function(|lua, ()| match Self::get(lua) {
+            Some(this) => this.on_break(lua),
+            None => Ok(()),
+        })?;
+        lua.set_named_registry_value(BREAK_HANDLER, on_break)?;
+
+        Interrupts::add(lua, move |lua| this.on_interrupt(lua));
+        lua.enable_jit(false);
+        update_compiler(lua);
+
+        Ok(())
+    }
+
+    /**
+        Tracks a newly loaded chunk, setting any breakpoints for its source file.
+    */
+    pub fn track(lua: &Lua, name: &str, function: &LuaFunction) -> LuaResult<()> {
+        if let Some(this) = Self::get(lua) {
+            let chunk = Chunk {
+                path: make_absolute_and_clean(chunk_source_path(name)),
+                function: lua.create_registry_value(function.clone())?,
+                lines: Vec::new(),
+            };
+            let breakpoints = this.breakpoints_for(&chunk.path);
+            this.chunks.borrow_mut().push(chunk);
+            if !breakpoints.is_empty() {
+                let index = this.chunks.borrow().len() - 1;
+                this.set_chunk_breakpoints(lua, index, &breakpoints)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn breakpoints_for(&self, path: &PathBuf) -> Vec<SourceBreakpoint> {
+        self.shared
+            .breakpoints
+            .lock()
+            .expect("Debugger breakpoints lock was poisoned")
+            .get(path)
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    fn sync_breakpoints(&self, lua: &Lua) -> LuaResult<()> {
+        let paths = self
+            .chunks
+            .borrow()
+            .iter()
+            .map(|chunk| chunk.path.clone())
+            .collect::<Vec<_>>();
+        for (index, path) in paths.iter().enumerate() {
+            let breakpoints = self.breakpoints_for(path);
+            self.set_chunk_breakpoints(lua, index, &breakpoints)?;
+        }
+        Ok(())
+    }
+
+    fn set_chunk_breakpoints(
+        &self,
+        lua: &Lua,
+        index: usize,
+        breakpoints: &[SourceBreakpoint],
+    ) -> LuaResult<()> {
+        let mut chunks = self.chunks.borrow_mut();
+        let chunk = &mut chunks[index];
+        let function = lua.registry_value::<LuaFunction>(&chunk.function)?;
+
+        for line in chunk.lines.drain(..) {
+            self.functions.set_breakpoint(lua, &function, line, false)?;
+        }
+
+        for breakpoint in breakpoints {
+            let target = self
+                .functions
+                .set_breakpoint(lua, &function, breakpoint.line, true)?;
+            let body = match target {
+                Some(line) => {
+                    chunk.lines.push(line);
+                    json!({ "id": breakpoint.id, "verified": true, "line": line })
+                }
+                None => json!({
+                    "id": breakpoint.id,
+                    "verified": false,
+                    "line": breakpoint.line,
+                    "message": "No code found at or after this line",
+                }),
+            };
+            self.shared.connection.event(
+                "breakpoint",
+                json!({ "reason": "changed", "breakpoint": body }),
+            );
+        }
+
+        Ok(())
+    }
+
+    fn is_breakpoint(&self, lua: &Lua, level: usize) -> bool {
+        let Some(debug) = lua.inspect_stack(level) else {
+            return false;
+        };
+        let Ok(line) = usize::try_from(debug.curr_line()) else {
+            return false;
+        };
+        let Some(path) = source_name(&debug).map(|name| frame_path(&name)) else {
+            return false;
+        };
+        self.chunks
+            .borrow()
+            .iter()
+            .any(|chunk| chunk.path == path && chunk.lines.contains(&line))
+    }
+
+    fn on_break(&self, lua: &Lua) -> LuaResult<()> {
+        // NOTE: Breakpoints may be hit while inspecting variables, and
+        // the break handler itself is the frame at the top of the stack
+        if self.paused.get() {
+            return Ok(());
+        }
+
+        let location = location(lua, 1);
+        if self.step.get().is_some_and(|step| step.is_done(location)) {
+            self.pause(lua, 1, "step")
+        } else if self.is_breakpoint(lua, 1) {
+            self.pause(lua, 1, "breakpoint")
+        } else {
+            Ok(())
+        }
+    }
+
+    fn on_interrupt(&self, lua: &Lua) -> LuaResult<LuaVmState> {
+        if self.paused.get() {
+            return Ok(LuaVmState::Continue);
+        }
+
+        if self
+            .shared
+            .breakpoints_changed
+            .swap(false, Ordering::SeqCst)
+        {
+            self.sync_breakpoints(lua)?;
+        }
+
+        let location = location(lua, 0);
+        // NOTE: Functions that are called do not have any temporary breakpoints
+        // set, so stepping into them stops at the first interrupt inside of them
+        let stepped_in = self.step.get().is_some_and(|step| {
+            step.kind == ResumeKind::StepIn
+                && step.from.thread == location.thread
+                && location.position > step.from.position
+        });
+
+        if self.shared.pause_requested.swap(false, Ordering::SeqCst) {
+            self.pause(lua, 0, "pause")?;
+        } else if stepped_in {
+            self.pause(lua, 0, "step")?;
+        }
+
+        Ok(LuaVmState::Continue)
+    }
+
+    /**
+        Pauses the VM at the stack frame at the given level, handling
+        requests from the client until it tells the VM to resume.
+    */
+    fn pause(&self, lua: &Lua, level: usize, reason: &str) -> LuaResult<()> {
+        self.paused.set(true);
+        self.step.set(None);
+        self.clear_temporary_breakpoints(lua)?;
+
+        let location = location(lua, level);
+        *self.frames.borrow_mut() = capture_frames(lua, level);
+
+        self.shared.paused.store(true, Ordering::SeqCst);
+        self.shared.connection.event(
+            "stopped",
+            json!({
+                "reason": reason,
+                "threadId": THREAD_ID,
+                "allThreadsStopped": true,
+            }),
+        );
+
+        let mut resume = ResumeKind::Continue;
+        while let Ok(command) = self.commands.recv() {
+            match command {
+                Command::StackTrace(request) => self.respond(&request, self.stack_trace(&request)),
+                Command::Scopes(request) => self.respond(&request, self.scopes(&request)),
+                Command::Variables(request) => {
+                    self.respond(&request, self.variables(lua, &request));
+                }
+                Command::Resume(request, kind) => {
+                    self.shared
+                        .connection
+                        .respond(&request, json!({ "allThreadsContinued": true }));
+                    resume = kind;
+                    break;
+                }
+                Command::Disconnect => break,
+            }
+        }
+
+        self.shared.paused.store(false, Ordering::SeqCst);
+        self.references.borrow_mut().clear();
+        let frames = self.frames.take();
+
+        if self
+            .shared
+            .breakpoints_changed
+            .swap(false, Ordering::SeqCst)
+        {
+            self.sync_breakpoints(lua)?;
+        }
+        if resume != ResumeKind::Continue {
+            self.start_step(lua, &frames, location, resume)?;
+        }
+
+        self.paused.set(false);
+        Ok(())
+    }
+
+    fn respond(&self, request: &Request, body: LuaResult<JsonValue>) {
+        match body {
+            Ok(body) => self.shared.connection.respond(request, body),
+            Err(e) => self.shared.connection.respond_error(request, e.to_string()),
+        }
+    }
+
+    fn start_step(
+        &self,
+        lua: &Lua,
+        frames: &[Frame],
+        from: Location,
+        kind: ResumeKind,
+    ) -> LuaResult<()> {
+        // NOTE: Functions may return straight into another return, so we
+        // need breakpoints in all callers, and not just the closest one
+        let positions = frames
+            .iter()
+            .filter(|frame| frame.is_luau)
+            .map(|frame| frame.position)
+            .filter(|position| match kind {
+                ResumeKind::StepOut => *position < from.position,
+                _ => *position <= from.position,
+            })
+            .collect::<Vec<_>>();
+
+        for position in positions {
+            if let Some(function) = self.functions.frame_function(lua, position)? {
+                self.break_on_every_line(lua, function)?;
+            }
+        }
+
+        self.step.set(Some(Step { kind, from }));
+        Ok(())
+    }
+
+    fn break_on_every_line(&self, lua: &Lua, function: LuaFunction) -> LuaResult<()> {
+        let mut lines = Vec::new();
+        let mut line = 0;
+        while let Some(target) = self.functions.set_breakpoint(lua, &function, line, true)? {
+            lines.push(target);
+            line = target + 1;
+        }
+        let key = lua.create_registry_value(function)?;
+        self.temporary.borrow_mut().push((key, lines));
+        Ok(())
+    }
+
+    fn clear_temporary_breakpoints(&self, lua: &Lua) -> LuaResult<()> {
+        let temporary = self.temporary.take();
+        if temporary.is_empty() {
+            return Ok(());
+        }
+
+        for (key, lines) in temporary {
+            let function = lua.registry_value::<LuaFunction>(&key)?;
+            for line in lines {
+                self.functions.set_breakpoint(lua, &function, line, false)?;
+            }
+        }
+
+        // NOTE: Disabling a breakpoint also disables any breakpoint
+        // set by the client on the same line, so we set those again
+        for chunk in self.chunks.borrow().iter() {
+            let function = lua.registry_value::<LuaFunction>(&chunk.function)?;
+            for line in &chunk.lines {
+                self.functions.set_breakpoint(lua, &function, *line, true)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn stack_trace(&self, request: &Request) -> LuaResult<JsonValue> {
+        let frames = self.frames.borrow();
+        let start = json_usize(&request.arguments["startFrame"]).unwrap_or(0);
+        let count = json_usize(&request.arguments["levels"])
+            .filter(|levels| *levels > 0)
+            .unwrap_or(frames.len());
+
+        let stack_frames = frames
+            .iter()
+            .enumerate()
+            .skip(start)
+            .take(count)
+            .map(|(id, frame)| {
+                let source = match &frame.path {
+                    Some(path) => json!({
+                        "name": path.file_name().map(|name| name.to_string_lossy()),
+                        "path": path.to_string_lossy(),
+                    }),
+                    None => json!({ "name": frame.source }),
+                };
+                let mut stack_frame = json!({
+                    "id": id,
+                    "name": frame.name,
+                    "line": frame.line.unwrap_or_default(),
+                    "column": 1,
+                });
+                if frame.is_luau {
+                    stack_frame["source"] = source;
+                } else {
+                    stack_frame["presentationHint"] = json!("subtle");
+                }
+                stack_frame
+            })
+            .collect::<Vec<_>>();
+
+        Ok(json!({
+            "stackFrames": stack_frames,
+            "totalFrames": frames.len(),
+        }))
+    }
+
+    fn scopes(&self, request: &Request) -> LuaResult<JsonValue> {
+        let frames = self.frames.borrow();
+        let frame = json_usize(&request.arguments["frameId"])
+            .and_then(|id| frames.get(id))
+            .ok_or_else(|| LuaError::RuntimeError("Invalid frame id".to_string()))?;
+
+        let mut scopes = Vec::new();
+        if frame.is_luau {
+            scopes.push(json!({
+                "name": "Locals",
+                "presentationHint": "locals",
+                "variablesReference": self.add_reference(Reference::Locals(frame.position)),
+                "expensive": false,
+            }));
+        }
+        scopes.push(json!({
+            "name": "Globals",
+            "variablesReference": self.add_reference(Reference::Globals),
+            "expensive": true,
+        }));
+
+        Ok(json!({ "scopes": scopes }))
+    }
+
+    fn variables(&self, lua: &Lua, request: &Request) -> LuaResult<JsonValue> {
+        let id = json_usize(&request.arguments["variablesReference"]).unwrap_or_default();
+
+        let mut variables = Vec::new();
+        {
+            let references = self.references.borrow();
+            let reference = id
+                .checked_sub(1)
+                .and_then(|index| references.get(index))
+                .ok_or_else(|| LuaError::RuntimeError("Invalid variables reference".to_string()))?;
+            match reference {
+                Reference::Locals(position) => {
+                    for (name, value) in self.functions.frame_locals(lua, *position)? {
+                        variables.push((name, value));
+                    }
+                }
+                Reference::Globals => {
+                    for pair in lua.globals().pairs::<LuaValue, LuaValue>() {
+                        let (key, value) = pair?;
+                        variables.push((describe_key(&key), value));
+                    }
+                    variables.sort_by(|a, b| a.0.cmp(&b.0));
+                }
+                Reference::Table(key) => {
+                    let table = lua.registry_value::<LuaTable>(key)?;
+                    for pair in table.pairs::<LuaValue, LuaValue>() {
+                        let (key, value) = pair?;
+                        variables.push((describe_key(&key), value));
+                    }
+                }
+            }
+        }
+
+        let variables = variables
+            .into_iter()
+            .map(|(name, value)| {
+                let reference = match &value {
+                    LuaValue::Table(table) => {
+                        let key = lua.create_registry_value(table.clone())?;
+                        self.add_reference(Reference::Table(key))
+                    }
+                    _ => 0,
+                };
+                Ok(json!({
+                    "name": name,
+                    "value": describe_value(&value),
+                    "type": value.type_name(),
+                    "variablesReference": reference,
+                }))
+            })
+            .collect::<LuaResult<Vec<_>>>()?;
+
+        Ok(json!({ "variables": variables }))
+    }
+
+    fn add_reference(&self, reference: Reference) -> usize {
+        let mut references = self.references.borrow_mut();
+        references.push(reference);
+        references.len()
+    }
+}
+
+fn stack_depth(lua: &Lua) -> usize {
+    let mut depth = 0;
+    while lua.inspect_stack(depth).is_some() {
+        depth += 1;
+    }
+    depth
+}
+
+fn location(lua: &Lua, level: usize) -> Location {
+    Location {
+        thread: lua.current_thread().to_pointer() as usize,
+        position: stack_depth(lua).saturating_sub(level),
+        line: lua
+            .inspect_stack(level)
+            .and_then(|debug| usize::try_from(debug.curr_line()).ok()),
+    }
+}
+
+fn source_name(debug: &mlua::Debug) -> Option<String> {
+    // NOTE: Chunk names may be prefixed to tell Luau how to display
+    // them, but we want the plain names to find their source files
+    debug
+        .source()
+        .source
+        .map(|source| source.trim_start_matches(['=', '@']).to_string())
+}
+
+fn frame_path(source: &str) -> PathBuf {
+    make_absolute_and_clean(chunk_source_path(source))
+}
+
+fn capture_frames(lua: &Lua, level: usize) -> Vec<Frame> {
+    let depth = stack_depth(lua);
+    let mut frames = Vec::new();
+    let mut current = level;
+    while let Some(debug) = lua.inspect_stack(current) {
+        let is_luau = debug.source().what != "C";
+        let source = source_name(&debug).unwrap_or_default();
+        let path = Some(frame_path(&source)).filter(|path| is_luau && path.is_file());
+        let name = match debug.names().name {
+            Some(name) => name.to_string(),
+            None if is_luau => "<anonymous>".to_string(),
+            None => "[C]".to_string(),
+        };
+        frames.push(Frame {
+            position: depth - current,
+            name,
+            source,
+            path,
+            line: usize::try_from(debug.curr_line()).ok(),
+            is_luau,
+        });
+        current += 1;
+    }
+    frames
+}
+
+fn json_usize(value: &JsonValue) -> Option<usize> {
+    value.as_u64().and_then(|value| usize::try_from(value).ok())
+}
+
+fn describe_key(key: &LuaValue) -> String {
+    match key {
+        LuaValue::String(s) => s.to_string_lossy().to_string(),
+        key => format!("[{}]", describe_value(key)),
+    }
+}
+
+/**
+    Describes a value without calling any metamethods, since those could run
+    Luau code and hit breakpoints while the debugger is already paused.
+*/
+fn describe_value(value: &LuaValue) -> String {
+    match value {
+        LuaValue::Nil => "nil".to_string(),
+        LuaValue::Boolean(b) => b.to_string(),
+        LuaValue::Integer(i) => i.to_string(),
+        LuaValue::Number(n) => n.to_string(),
+        LuaValue::String(s) => format!("{:?}", s.to_string_lossy()),
+        value => format!("{}: {:p}", value.type_name(), value.to_pointer()),
+    }
+}
+
+#[cfg(test)]
+mod tests;
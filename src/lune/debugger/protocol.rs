@@ -0,0 +1,137 @@
+use std::{
+    io::{BufRead, BufReader, Error as IoError, ErrorKind, Read, Result as IoResult, Write},
+    net::{Shutdown, TcpStream},
+    sync::{
+        atomic::{AtomicI64, Ordering},
+        Mutex,
+    },
+};
+
+use serde::Deserialize;
+use serde_json::{json, Value as JsonValue};
+
+/**
+    A request sent by a debug adapter client.
+*/
+#[derive(Debug, Clone, Deserialize)]
+pub struct Request {
+    pub seq: i64,
+    pub command: String,
+    #[serde(default)]
+    pub arguments: JsonValue,
+}
+
+/**
+    Reads the next message sent over the given stream, returning
+    `None` if the stream was closed before a message was sent.
+
+    Messages use the base protocol of the Debug Adapter Protocol, which is a
+    set of headers followed by a JSON body with the length given in headers.
+*/
+pub fn read_message(reader: &mut BufReader<TcpStream>) -> IoResult<Option<JsonValue>> {
+    let mut content_length = None;
+    loop {
+        let mut header = String::new();
+        if reader.read_line(&mut header)? == 0 {
+            return Ok(None);
+        }
+        let header = header.trim_end();
+        if header.is_empty() {
+            break;
+        }
+        if let Some((name, value)) = header.split_once(':') {
+            if name.trim().eq_ignore_ascii_case("Content-Length") {
+                content_length = value.trim().parse::<usize>().ok();
+            }
+        }
+    }
+
+    let content_length = content_length
+        .ok_or_else(|| IoError::new(ErrorKind::InvalidData, "Missing Content-Length header"))?;
+
+    let mut body = vec![0; content_length];
+    reader.read_exact(&mut body)?;
+    Ok(Some(serde_json::from_slice(&body)?))
+}
+
+/**
+    The sending half of a connection to a debug adapter client.
+
+    Both the server thread and the Luau VM send messages, so each
+    message is written in full while holding a lock on the stream.
+*/
+#[derive(Debug)]
+pub struct Connection {
+    stream: Mutex<TcpStream>,
+    seq: AtomicI64,
+}
+
+impl Connection {
+    pub fn new(stream: TcpStream) -> Self {
+        Self {
+            stream: Mutex::new(stream),
+            seq: AtomicI64::new(1),
+        }
+    }
+
+    fn send(&self, mut message: JsonValue) {
+        message["seq"] = json!(self.seq.fetch_add(1, Ordering::SeqCst));
+        let body = message.to_string();
+        let mut stream = self
+            .stream
+            .lock()
+            .expect("Debugger stream lock was poisoned");
+        // NOTE: A client may disconnect at any time, and there is nobody
+        // left to tell about it when that happens, so errors are ignored
+        let _ = write!(stream, "Content-Length: {}\r\n\r\n{body}", body.len());
+        let _ = stream.flush();
+    }
+
+    /**
+        Sends a successful response to the given request.
+    */
+    pub fn respond(&self, request: &Request, body: JsonValue) {
+        self.send(json!({
+            "type": "response",
+            "request_seq": request.seq,
+            "command": request.command,
+            "success": true,
+            "body": body,
+        }));
+    }
+
+    /**
+        Sends a failed response to the given request.
+    */
+    pub fn respond_error(&self, request: &Request, message: impl Into<String>) {
+        self.send(json!({
+            "type": "response",
+            "request_seq": request.seq,
+            "command": request.command,
+            "success": false,
+            "message": message.into(),
+        }));
+    }
+
+    /**
+        Sends an event that was not requested by the client.
+    */
+    pub fn event(&self, event: &str, body: JsonValue) {
+        self.send(json!({
+            "type": "event",
+            "event": event,
+            "body": body,
+        }));
+    }
+
+    /**
+        Closes the connection, which also stops the server thread reading from it.
+    */
+    pub fn close(&self) {
+        let stream = self
+            .stream
+            .lock()
+            .expect("Debugger stream lock was poisoned");
+        let _ = stream.shutdown(Shutdown::Both);
+    }
+}
@@ -0,0 +1,276 @@
+use std::{
+    collections::HashMap,
+    io::{BufReader, Error as IoError, ErrorKind, Result as IoResult},
+    net::{SocketAddr, TcpListener},
+    path::PathBuf,
+    sync::{
+        atomic::{AtomicBool, AtomicI64, Ordering},
+        mpsc::{channel, Receiver, Sender},
+        Arc, Mutex,
+    },
+    thread,
+};
+
+use serde_json::{json, Value as JsonValue};
+
+use crate::lune::util::paths::make_absolute_and_clean;
+
+use super::protocol::{read_message, Connection, Request};
+
+/**
+    A way to resume a paused Luau VM.
+*/
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ResumeKind {
+    Continue,
+    StepOver,
+    StepIn,
+    StepOut,
+}
+
+/**
+    A request that can only be handled by the Luau VM while it is paused.
+*/
+#[derive(Debug)]
+pub enum Command {
+    StackTrace(Request),
+    Scopes(Request),
+    Variables(Request),
+    Resume(Request, ResumeKind),
+    Disconnect,
+}
+
+/**
+    A breakpoint set by the client, at the line it was requested at.
+*/
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SourceBreakpoint {
+    pub id: i64,
+    pub line: usize,
+}
+
+/**
+    State shared between the server thread and the Luau VM being debugged.
+*/
+#[derive(Debug)]
+pub struct Shared {
+    pub connection: Connection,
+    pub breakpoints: Mutex<HashMap<PathBuf, Vec<SourceBreakpoint>>>,
+    pub breakpoints_changed: AtomicBool,
+    pub pause_requested: AtomicBool,
+    pub paused: AtomicBool,
+    next_breakpoint_id: AtomicI64,
+}
+
+/**
+    A debug adapter server, connected to a single client, that a Lune runtime can be debugged through.
+
+    Clients communicate with the server using the Debug Adapter Protocol,
+    and may set breakpoints, step through code and inspect variables.
+*/
+#[derive(Debug, Clone)]
+pub struct DebugServer {
+    pub(super) shared: Arc<Shared>,
+    pub(super) commands: Arc<Mutex<Option<Receiver<Command>>>>,
+}
+
+impl DebugServer {
+    /**
+        Waits for a debug adapter client to connect to the given listener.
+
+        This blocks until the client has finished configuring the session, meaning
+        that any breakpoints sent by the client during configuration are set before
+        the server is returned, and may be hit right away by scripts that are ran.
+    */
+    pub fn accept(listener: &TcpListener) -> IoResult<Self> {
+        let (stream, _) = listener.accept()?;
+        let reader = BufReader::new(stream.try_clone()?);
+
+        let shared = Arc::new(Shared {
+            connection: Connection::new(stream),
+            breakpoints: Mutex::new(HashMap::new()),
+            breakpoints_changed: AtomicBool::new(false),
+            pause_requested: AtomicBool::new(false),
+            paused: AtomicBool::new(false),
+            next_breakpoint_id: AtomicI64::new(1),
+        });
+
+        let (command_tx, command_rx) = channel();
+        let (configured_tx, configured_rx) = channel();
+
+        let shared_inner = Arc::clone(&shared);
+        thread::Builder::new()
+            .name("luneweb-debugger".to_string())
+            .spawn(move || serve(&shared_inner, reader, &command_tx, &configured_tx))?;
+
+        if configured_rx.recv().is_err() {
+            return Err(IoError::new(
+                ErrorKind::ConnectionAborted,
+                "Debugger client disconnected before finishing configuration",
+            ));
+        }
+
+        Ok(Self {
+            shared,
+            commands: Arc::new(Mutex::new(Some(command_rx))),
+        })
+    }
+
+    /**
+        Binds to the given address and waits for a debug adapter client to connect.
+
+        See [`DebugServer::accept`] for more details.
+    */
+    pub fn listen(addr: SocketAddr) -> IoResult<Self> {
+        Self::accept(&TcpListener::bind(addr)?)
+    }
+
+    /**
+        Tells the client that the debugged program exited with the
+        given exit code, and closes the connection to the client.
+    */
+    pub fn finish(&self, exit_code: u8) {
+        let connection = &self.shared.connection;
+        connection.event("exited", json!({ "exitCode": exit_code }));
+        connection.event("terminated", json!({}));
+        connection.close();
+    }
+}
+
+fn serve(
+    shared: &Shared,
+    mut reader: BufReader<std::net::TcpStream>,
+    commands: &Sender<Command>,
+    configured: &Sender<()>,
+) {
+    let connection = &shared.connection;
+    while let Ok(Some(message)) = read_message(&mut reader) {
+        let Ok(request) = serde_json::from_value::<Request>(message) else {
+            continue;
+        };
+
+        // NOTE: Requests for the state of the VM are only sent by clients
+        // while paused, and the VM is the only one that can respond to them
+        let forward = |command: Command| {
+            if shared.paused.load(Ordering::SeqCst) {
+                let _ = commands.send(command);
+            } else if let Command::StackTrace(request)
+            | Command::Scopes(request)
+            | Command::Variables(request) = command
+            {
+                connection.respond_error(&request, "Not paused");
+            } else if let Command::Resume(request, _) = command {
+                connection.respond(&request, json!({ "allThreadsContinued": true }));
+            }
+        };
+
+        match request.command.as_str() {
+            "initialize" => {
+                connection.respond(
+                    &request,
+                    json!({
+                        "supportsConfigurationDoneRequest": true,
+                        "supportsSingleThreadExecutionRequests": false,
+                    }),
+                );
+                connection.event("initialized", json!({}));
+            }
+            "launch" | "attach" => {
+                if request.arguments["stopOnEntry"].as_bool() == Some(true) {
+                    shared.pause_requested.store(true, Ordering::SeqCst);
+                }
+                connection.respond(&request, JsonValue::Null);
+            }
+            "configurationDone" => {
+                connection.respond(&request, JsonValue::Null);
+                let _ = configured.send(());
+            }
+            "setBreakpoints" => set_breakpoints(shared, &request),
+            "setExceptionBreakpoints" => {
+                connection.respond(&request, json!({ "breakpoints": [] }));
+            }
+            "threads" => {
+                connection.respond(
+                    &request,
+                    json!({ "threads": [{ "id": super::THREAD_ID, "name": "main" }] }),
+                );
+            }
+            "pause" => {
+                shared.pause_requested.store(true, Ordering::SeqCst);
+                connection.respond(&request, JsonValue::Null);
+            }
+            "stackTrace" => forward(Command::StackTrace(request)),
+            "scopes" => forward(Command::Scopes(request)),
+            "variables" => forward(Command::Variables(request)),
+            "continue" => forward(Command::Resume(request, ResumeKind::Continue)),
+            "next" => forward(Command::Resume(request, ResumeKind::StepOver)),
+            "stepIn" => forward(Command::Resume(request, ResumeKind::StepIn)),
+            "stepOut" => forward(Command::Resume(request, ResumeKind::StepOut)),
+            "disconnect" => {
+                connection.respond(&request, JsonValue::Null);
+                break;
+            }
+            command => {
+                connection.respond_error(&request, format!("Unsupported request '{command}'"));
+            }
+        }
+    }
+
+    // NOTE: Once the client is gone, scripts should keep running as
+    // if they were never debugged, so we remove all breakpoints
+    shared
+        .breakpoints
+        .lock()
+        .expect("Debugger breakpoints lock was poisoned")
+        .clear();
+    shared.breakpoints_changed.store(true, Ordering::SeqCst);
+    shared.pause_requested.store(false, Ordering::SeqCst);
+    let _ = commands.send(Command::Disconnect);
+}
+
+fn set_breakpoints(shared: &Shared, request: &Request) {
+    let Some(path) = request.arguments["source"]["path"].as_str() else {
+        shared.connection.respond_error(
+            request,
+            "Breakpoints may only be set in source files with paths",
+        );
+        return;
+    };
+
+    let breakpoints = request.arguments["breakpoints"]
+        .as_array()
+        .map(Vec::as_slice)
+        .unwrap_or_default()
+        .iter()
+        .filter_map(|breakpoint| breakpoint["line"].as_u64())
+        .map(|line| SourceBreakpoint {
+            id: shared.next_breakpoint_id.fetch_add(1, Ordering::SeqCst),
+            line: usize::try_from(line).unwrap_or(usize::MAX),
+        })
+        .collect::<Vec<_>>();
+
+    // NOTE: Breakpoints are verified by the VM once it has loaded the source
+    // file, which may not happen until the file is required by some script
+    let body = breakpoints
+        .iter()
+        .map(|breakpoint| {
+            json!({
+                "id": breakpoint.id,
+                "verified": false,
+                "line": breakpoint.line,
+                "message": "Source has not been loaded yet",
+            })
+        })
+        .collect::<Vec<_>>();
+
+    shared
+        .breakpoints
+        .lock()
+        .expect("Debugger breakpoints lock was poisoned")
+        .insert(make_absolute_and_clean(path), breakpoints);
+    shared.breakpoints_changed.store(true, Ordering::SeqCst);
+
+    shared
+        .connection
+        .respond(request, json!({ "breakpoints": body }));
+}
@@ -0,0 +1,189 @@
+// NOTE: The debugger is controlled by a client connected to it over TCP, so these
+// tests act as that client from Rust, which scripts in src/tests.rs can not do
+
+use std::{
+    io::{BufReader, Write},
+    net::{SocketAddr, TcpListener, TcpStream},
+    process::ExitCode,
+    thread,
+    time::Duration,
+};
+
+use serde_json::{json, Value as JsonValue};
+
+use crate::{lune::util::paths::make_absolute_and_clean, tests::repo_path, Runtime};
+
+use super::{protocol::read_message, DebugServer};
+
+const SCRIPT_NAME: &str = "tests/debugger/breakpoints";
+const SCRIPT_PATH: &str = "tests/debugger/breakpoints.luau";
+
+/**
+    A minimal debug adapter client, which sends requests one at a
+    time and skips over any messages it is not currently waiting for.
+*/
+struct Client {
+    stream: TcpStream,
+    reader: BufReader<TcpStream>,
+    seq: i64,
+}
+
+impl Client {
+    fn connect(addr: SocketAddr) -> Self {
+        let stream = TcpStream::connect(addr).unwrap();
+        stream
+            .set_read_timeout(Some(Duration::from_secs(30)))
+            .unwrap();
+        let reader = BufReader::new(stream.try_clone().unwrap());
+        Self {
+            stream,
+            reader,
+            seq: 0,
+        }
+    }
+
+    fn wait_for(&mut self, matches: impl Fn(&JsonValue) -> bool) -> JsonValue {
+        loop {
+            let message = read_message(&mut self.reader)
+                .unwrap()
+                .expect("Debugger closed the connection");
+            if matches(&message) {
+                return message;
+            }
+        }
+    }
+
+    fn request(&mut self, command: &str, arguments: JsonValue) -> JsonValue {
+        self.seq += 1;
+        let body = json!({
+            "seq": self.seq,
+            "type": "request",
+            "command": command,
+            "arguments": arguments,
+        })
+        .to_string();
+        write!(self.stream, "Content-Length: {}\r\n\r\n{body}", body.len()).unwrap();
+
+        let seq = self.seq;
+        let response = self.wait_for(|message| message["request_seq"] == seq);
+        assert_eq!(response["success"], true, "Request failed: {response}");
+        response["body"].clone()
+    }
+
+    fn event(&mut self, event: &str) -> JsonValue {
+        let message = self.wait_for(|message| message["event"] == event);
+        message["body"].clone()
+    }
+
+    fn top_frame(&mut self) -> JsonValue {
+        let trace = self.request("stackTrace", json!({ "threadId": 1 }));
+        trace["stackFrames"][0].clone()
+    }
+
+    fn locals(&mut self, frame_id: &JsonValue) -> Vec<(String, String)> {
+        let scopes = self.request("scopes", json!({ "frameId": frame_id }));
+        let locals = scopes["scopes"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .find(|scope| scope["name"] == "Locals")
+            .expect("Missing locals scope")["variablesReference"]
+            .clone();
+        let variables = self.request("variables", json!({ "variablesReference": locals }));
+        variables["variables"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|variable| {
+                (
+                    variable["name"].as_str().unwrap().to_string(),
+                    variable["value"].as_str().unwrap().to_string(),
+                )
+            })
+            .collect()
+    }
+}
+
+fn has_local(locals: &[(String, String)], name: &str, value: &str) -> bool {
+    locals.iter().any(|(n, v)| n == name && v == value)
+}
+
+fn run_client(addr: SocketAddr) {
+    let path = make_absolute_and_clean(repo_path(SCRIPT_PATH));
+    let source = json!({ "path": path.to_string_lossy() });
+
+    let mut client = Client::connect(addr);
+    client.request("initialize", json!({ "adapterID": "luneweb" }));
+    client.event("initialized");
+    client.request("launch", json!({}));
+    let breakpoints = client.request(
+        "setBreakpoints",
+        json!({ "source": source, "breakpoints": [{ "line": 2 }] }),
+    );
+    assert_eq!(breakpoints["breakpoints"].as_array().unwrap().len(), 1);
+    client.request("configurationDone", json!({}));
+
+    // Breakpoints should be hit, with the stack and variables of the function
+    let stopped = client.event("stopped");
+    assert_eq!(stopped["reason"], "breakpoint");
+    let frame = client.top_frame();
+    assert_eq!(frame["name"], "add");
+    assert_eq!(frame["line"], 2);
+    assert_eq!(frame["source"]["path"], path.to_string_lossy().as_ref());
+    let locals = client.locals(&frame["id"]);
+    assert!(has_local(&locals, "a", "1"), "Unexpected locals {locals:?}");
+    assert!(has_local(&locals, "b", "2"), "Unexpected locals {locals:?}");
+
+    // Stepping over should stop at the next line in the same function
+    client.request("next", json!({ "threadId": 1 }));
+    assert_eq!(client.event("stopped")["reason"], "step");
+    let frame = client.top_frame();
+    assert_eq!(frame["line"], 3);
+    let locals = client.locals(&frame["id"]);
+    assert!(
+        has_local(&locals, "sum", "3"),
+        "Unexpected locals {locals:?}"
+    );
+
+    // Stepping out should stop in the caller, at the next line that it runs
+    client.request(
+        "setBreakpoints",
+        json!({ "source": source, "breakpoints": [] }),
+    );
+    client.request("stepOut", json!({ "threadId": 1 }));
+    assert_eq!(client.event("stopped")["reason"], "step");
+    let frame = client.top_frame();
+    assert_eq!(frame["line"], 7);
+    let locals = client.locals(&frame["id"]);
+    assert!(
+        has_local(&locals, "first", "3"),
+        "Unexpected locals {locals:?}"
+    );
+
+    // With no breakpoints left, the script should run until it exits
+    client.request("continue", json!({ "threadId": 1 }));
+    assert_eq!(client.event("exited")["exitCode"], 0);
+    client.event("terminated");
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn debugger_breakpoints_and_stepping() {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap();
+    let client = thread::spawn(move || run_client(addr));
+
+    let server = DebugServer::accept(&listener).unwrap();
+    let script = tokio::fs::read_to_string(repo_path(SCRIPT_PATH))
+        .await
+        .unwrap();
+    let script_name = repo_path(SCRIPT_NAME);
+    let mut runtime = Runtime::new().with_debugger(&server).unwrap();
+    let exit_code = runtime
+        .run(script_name.to_string_lossy(), script)
+        .await
+        .unwrap();
+    server.finish(u8::from(exit_code != ExitCode::SUCCESS));
+
+    client.join().unwrap();
+    assert_eq!(exit_code, ExitCode::SUCCESS);
+}
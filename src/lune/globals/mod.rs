@@ -8,6 +8,8 @@ mod require;
 mod version;
 mod warn;
 
+pub use require::require_builtin;
+
 pub fn inject_all(lua: &Lua) -> LuaResult<()> {
     let all = TableBuilder::new(lua)?
         .with_value("_G", g_table::create(lua)?)?
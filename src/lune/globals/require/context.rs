@@ -14,7 +14,9 @@ use tokio::{
     },
 };
 
-use crate::lune::{builtins::LuneBuiltin, util::paths::CWD};
+use crate::lune::{
+    builtins::LuneBuiltin, coverage::Coverage, debugger::Debugger, util::paths::CWD,
+};
 
 /**
     Context containing cached results for all `require` operations.
@@ -164,9 +166,13 @@ impl RequireContext {
         // Read the file at the given path, try to parse and
         // load it into a new lua thread that we can schedule
         let file_contents = fs::read(&abs_path).await?;
+        let file_name = rel_path.to_string_lossy().to_string();
         let file_thread = lua
             .load(file_contents)
-            .set_name(rel_path.to_string_lossy().to_string());
+            .set_name(&file_name)
+            .into_function()?;
+        Coverage::track(lua, &file_name, &file_thread)?;
+        Debugger::track(lua, &file_name, &file_thread)?;
 
         // Schedule the thread to run, wait for it to finish running
         let thread_id = lua.push_thread_back(file_thread, ())?;
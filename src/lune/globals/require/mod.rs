@@ -59,6 +59,17 @@ pub fn create(lua: &Lua) -> LuaResult<impl IntoLua<'_>> {
         .into_function()
 }
 
+/**
+    Loads the builtin library with the given name, the same
+    way that requiring `@luneweb/name` from a script would.
+*/
+pub fn require_builtin<'lua>(lua: &'lua Lua, name: &str) -> LuaResult<LuaMultiValue<'lua>> {
+    let context = lua
+        .app_data_ref::<RequireContext>()
+        .expect("Failed to get RequireContext from app data");
+    context.load_builtin(lua, name)
+}
+
 async fn require<'lua>(
     lua: &'lua Lua,
     (source, path): (LuaString<'lua>, LuaString<'lua>),
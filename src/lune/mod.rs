@@ -1,4 +1,5 @@
 use std::{
+    fmt::{Debug, Formatter, Result as FmtResult},
     process::ExitCode,
     rc::Rc,
     sync::{
@@ -7,21 +8,50 @@ use std::{
     },
 };
 
-use mlua::Lua;
+use mlua::{Compiler as LuaCompiler, Function as LuaFunction, Lua};
 use mlua_luau_scheduler::Scheduler;
+use once_cell::sync::Lazy;
 
 mod builtins;
+mod coverage;
+mod debugger;
 mod error;
 mod globals;
+mod profiler;
+mod testing;
+
+use builtins::{ProcessLifecycle, PROCESS_START_TIME};
 
 pub(crate) mod util;
 
+use util::paths::CWD;
+
+pub use coverage::{CoverageFormat, CoverageReport, FileCoverage, FunctionCoverage};
+pub use debugger::DebugServer;
 pub use error::RuntimeError;
+pub use profiler::{ProfileFormat, ProfileFrame, ProfileReport};
+pub use testing::{TestFileReport, TestFormat, TestReport, TestResult, TestRunner, TestStatus};
+
+use coverage::Coverage;
+use debugger::Debugger;
+use profiler::Profiler;
+
+type ErrorCallback = Arc<dyn Fn(&RuntimeError) + Send + Sync + 'static>;
 
-#[derive(Debug)]
 pub struct Runtime {
     lua: Rc<Lua>,
     args: Vec<String>,
+    error_callback: Option<ErrorCallback>,
+    print_errors: bool,
+}
+
+impl Debug for Runtime {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        f.debug_struct("Runtime")
+            .field("lua", &self.lua)
+            .field("args", &self.args)
+            .finish_non_exhaustive()
+    }
 }
 
 impl Runtime {
@@ -30,6 +60,11 @@ impl Runtime {
     */
     #[allow(clippy::new_without_default)]
     pub fn new() -> Self {
+        Lazy::force(&PROCESS_START_TIME);
+        // NOTE: Required modules and scripts shown in errors use paths relative to the
+        // directory that scripts started in, which process.chdir must not change later
+        Lazy::force(&CWD);
+
         let lua = Rc::new(Lua::new());
 
         lua.set_app_data(Rc::downgrade(&lua));
@@ -40,6 +75,8 @@ impl Runtime {
         Self {
             lua,
             args: Vec::new(),
+            error_callback: None,
+            print_errors: true,
         }
     }
 
@@ -55,6 +92,94 @@ impl Runtime {
         self
     }
 
+    /**
+        Enables or disables code coverage for scripts and modules loaded into this runtime.
+
+        Coverage can be retrieved after running scripts using [`Runtime::coverage_report`].
+    */
+    pub fn with_coverage(self, enabled: bool) -> Self {
+        if enabled {
+            Coverage::enable(&self.lua, 1);
+        } else {
+            Coverage::disable(&self.lua);
+        }
+        self
+    }
+
+    /**
+        Gets the code coverage for all scripts and modules that have been loaded
+        into this runtime while coverage was enabled, including any coverage
+        enabled from inside of scripts using `luau.coverage`.
+    */
+    pub fn coverage_report(&self) -> Result<CoverageReport, RuntimeError> {
+        match Coverage::get(&self.lua) {
+            Some(coverage) => Ok(coverage.report(&self.lua)?),
+            None => Ok(CoverageReport::default()),
+        }
+    }
+
+    /**
+        Enables sampling profiling of scripts ran in this runtime, taking the given
+        number of samples per second, or disables profiling if given `None`.
+
+        Samples can be retrieved after running scripts using [`Runtime::profile_report`].
+    */
+    pub fn with_profiling(self, sample_rate: Option<u32>) -> Self {
+        match sample_rate {
+            Some(rate) => Profiler::start(&self.lua, rate),
+            None => Profiler::stop(&self.lua),
+        }
+        self
+    }
+
+    /**
+        Gets all call stacks sampled while profiling this runtime, including
+        any profiling started from inside of scripts using `luau.profiler`.
+    */
+    pub fn profile_report(&self) -> ProfileReport {
+        Profiler::get(&self.lua)
+            .map(|profiler| profiler.report())
+            .unwrap_or_default()
+    }
+
+    /**
+        Attaches a debugger to this runtime, controlled by a client connected to the given server.
+
+        Breakpoints set by the client apply to any scripts and modules loaded after
+        this call, and native code generation is disabled for all of them.
+    */
+    pub fn with_debugger(self, server: &DebugServer) -> Result<Self, RuntimeError> {
+        Debugger::attach(&self.lua, server)?;
+        Ok(self)
+    }
+
+    /**
+        Sets a callback to call with any errors that happen while running
+        scripts, in addition to the errors being printed to stderr.
+    */
+    pub(crate) fn with_error_callback<F>(mut self, callback: F) -> Self
+    where
+        F: Fn(&RuntimeError) + Send + Sync + 'static,
+    {
+        self.error_callback = Some(Arc::new(callback));
+        self
+    }
+
+    /**
+        Enables or disables printing errors that happen while running scripts to stderr.
+    */
+    pub(crate) fn with_error_printing(mut self, enabled: bool) -> Self {
+        self.print_errors = enabled;
+        self
+    }
+
+    /**
+        Gets the Luau VM that this runtime runs scripts in.
+    */
+    pub(crate) fn lua(&self) -> &Lua {
+        &self.lua
+    }
+
     /**
         Runs a Lune script inside of the current runtime.
 
@@ -64,30 +189,85 @@ impl Runtime {
         &mut self,
         script_name: impl AsRef<str>,
         script_contents: impl AsRef<[u8]>,
+    ) -> Result<ExitCode, RuntimeError> {
+        let main = self.load(script_name, script_contents)?;
+        self.run_function(main).await
+    }
+
+    /**
+        Loads a Lune script into the current runtime, without running it.
+    */
+    pub(crate) fn load(
+        &self,
+        script_name: impl AsRef<str>,
+        script_contents: impl AsRef<[u8]>,
+    ) -> Result<LuaFunction<'_>, RuntimeError> {
+        let main = self
+            .lua
+            .load(script_contents.as_ref())
+            .set_name(script_name.as_ref())
+            .into_function()?;
+        Coverage::track(&self.lua, script_name.as_ref(), &main)?;
+        Debugger::track(&self.lua, script_name.as_ref(), &main)?;
+        Ok(main)
+    }
+
+    /**
+        Runs a function as the main thread of the current runtime,
+        the same way that the main chunk of a script would be ran.
+    */
+    pub(crate) async fn run_function(
+        &self,
+        main: LuaFunction<'_>,
     ) -> Result<ExitCode, RuntimeError> {
         // Create a new scheduler for this run
         let sched = Scheduler::new(&self.lua);
 
         // Add error callback to format errors nicely + store status
         let got_any_error = Arc::new(AtomicBool::new(false));
-        let got_any_inner = Arc::clone(&got_any_error);
-        sched.set_error_callback(move |e| {
-            got_any_inner.store(true, Ordering::SeqCst);
-            eprintln!("{}", RuntimeError::from(e));
-        });
-
-        // Load our "main" thread
-        let main = self
-            .lua
-            .load(script_contents.as_ref())
-            .set_name(script_name.as_ref());
+        set_error_callback(
+            &sched,
+            &got_any_error,
+            self.error_callback.clone(),
+            self.print_errors,
+        );
 
         // Run it on our scheduler until it and any other spawned threads complete
         sched.push_thread_back(main, ())?;
         sched.run().await;
 
+        let mut exit_code = sched.get_exit_code();
+        drop(sched);
+
+        // Run any exit hooks in a scheduler of their own, since the main
+        // scheduler may have been stopped early by a call to process.exit,
+        // and then clean up anything the stopped threads are still holding on to
+        if let Some(lifecycle) = ProcessLifecycle::get(&self.lua) {
+            let exit_code_set = lifecycle.take_exit_code();
+            let code = exit_code_set.unwrap_or(u8::from(got_any_error.load(Ordering::SeqCst)));
+            let hooks = lifecycle.take_exit_hooks(&self.lua)?;
+            if !hooks.is_empty() {
+                let sched = Scheduler::new(&self.lua);
+                set_error_callback(
+                    &sched,
+                    &got_any_error,
+                    self.error_callback.clone(),
+                    self.print_errors,
+                );
+                for hook in hooks {
+                    sched.push_thread_back(hook, code)?;
+                }
+                sched.run().await;
+                // NOTE: Exit hooks may also call process.exit to change the exit code
+                exit_code = sched.get_exit_code().or(exit_code);
+            }
+            if exit_code_set.is_some() || lifecycle.take_exit_code().is_some() {
+                lifecycle.run_exit_cleanups();
+            }
+        }
+
         // Return the exit code - default to FAILURE if we got any errors
-        Ok(sched.get_exit_code().unwrap_or({
+        Ok(exit_code.unwrap_or({
             if got_any_error.load(Ordering::SeqCst) {
                 ExitCode::FAILURE
             } else {
@@ -96,3 +276,37 @@ impl Runtime {
         }))
     }
 }
+
+/**
+    Sets the compiler used for any chunks loaded after this call, with
+    the options needed for coverage and debugging, if those are enabled.
+*/
+pub(crate) fn update_compiler(lua: &Lua) {
+    let coverage_level = Coverage::get(lua).map_or(0, |coverage| coverage.level());
+    // NOTE: Local variable names are only included at the highest debug level
+    let debug_level = if Debugger::get(lua).is_some() { 2 } else { 1 };
+    lua.set_compiler(
+        LuaCompiler::default()
+            .set_coverage_level(coverage_level)
+            .set_debug_level(debug_level),
+    );
+}
+
+fn set_error_callback(
+    sched: &Scheduler,
+    got_any_error: &Arc<AtomicBool>,
+    error_callback: Option<ErrorCallback>,
+    print_errors: bool,
+) {
+    let got_any_inner = Arc::clone(got_any_error);
+    sched.set_error_callback(move |e| {
+        got_any_inner.store(true, Ordering::SeqCst);
+        let error = RuntimeError::from(e);
+        if print_errors {
+            eprintln!("{error}");
+        }
+        if let Some(callback) = &error_callback {
+            callback(&error);
+        }
+    });
+}
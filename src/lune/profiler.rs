@@ -0,0 +1,310 @@
+use std::{
+    cell::{Cell, RefCell},
+    collections::HashMap,
+    fmt::Write as _,
+    rc::Rc,
+    str::FromStr,
+    time::{Duration, Instant},
+};
+
+use mlua::prelude::*;
+use serde_json::json;
+
+use crate::lune::util::interrupts::Interrupts;
+
+/**
+    The number of samples to take per second, unless another rate is given.
+*/
+pub(crate) const DEFAULT_SAMPLE_RATE: u32 = 1000;
+
+/**
+    A format that a [`ProfileReport`] can be written in.
+*/
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ProfileFormat {
+    #[default]
+    Folded,
+    Speedscope,
+}
+
+impl ProfileFormat {
+    /**
+        Gets the file extension typically used for this format.
+    */
+    pub fn extension(self) -> &'static str {
+        match self {
+            Self::Folded => "folded",
+            Self::Speedscope => "speedscope.json",
+        }
+    }
+}
+
+impl FromStr for ProfileFormat {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "folded" => Ok(Self::Folded),
+            "speedscope" => Ok(Self::Speedscope),
+            _ => Err(format!(
+                "Invalid profile format '{s}' - expected one of folded, speedscope"
+            )),
+        }
+    }
+}
+
+impl<'lua> FromLua<'lua> for ProfileFormat {
+    fn from_lua(value: LuaValue<'lua>, _: &'lua Lua) -> LuaResult<Self> {
+        match value {
+            LuaValue::Nil => Ok(Self::default()),
+            LuaValue::String(s) => s.to_str()?.parse().map_err(LuaError::RuntimeError),
+            value => Err(LuaError::FromLuaConversionError {
+                from: value.type_name(),
+                to: "ProfileFormat",
+                message: Some(format!(
+                    "Invalid profile format - expected string, got {}",
+                    value.type_name()
+                )),
+            }),
+        }
+    }
+}
+
+/**
+    A single function in a sampled call stack.
+*/
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct ProfileFrame {
+    pub name: String,
+    pub file: String,
+    pub line: usize,
+}
+
+impl ProfileFrame {
+    fn from_debug(debug: &mlua::Debug) -> Self {
+        let source = debug.source();
+        // NOTE: Chunk names may be prefixed to tell Luau how to display them,
+        // but we want the plain names here to match the names of source files
+        let file = source
+            .source
+            .map(|s| s.trim_start_matches(['=', '@']).to_string())
+            .unwrap_or_default();
+        Self {
+            name: debug
+                .names()
+                .name
+                .map_or_else(|| "<anonymous>".to_string(), |name| name.to_string()),
+            file,
+            line: source.line_defined.unwrap_or_default(),
+        }
+    }
+
+    fn folded_name(&self) -> String {
+        // NOTE: Semicolons separate frames in the folded format
+        let name = if self.line > 0 {
+            format!("{} ({}:{})", self.name, self.file, self.line)
+        } else {
+            format!("{} ({})", self.name, self.file)
+        };
+        name.replace(';', ":")
+    }
+}
+
+/**
+    Call stacks sampled by the profiler, with the number of times each stack was sampled.
+*/
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ProfileReport {
+    interval: Duration,
+    stacks: Vec<(Vec<ProfileFrame>, u64)>,
+}
+
+impl ProfileReport {
+    /**
+        Gets the time between each sample.
+    */
+    pub fn interval(&self) -> Duration {
+        self.interval
+    }
+
+    /**
+        Gets all sampled call stacks, starting from the outermost function,
+        together with the number of times that each stack was sampled.
+    */
+    pub fn stacks(&self) -> &[(Vec<ProfileFrame>, u64)] {
+        &self.stacks
+    }
+
+    /**
+        Writes this report in the given format.
+    */
+    pub fn to_format(&self, format: ProfileFormat) -> String {
+        match format {
+            ProfileFormat::Folded => self.to_folded(),
+            ProfileFormat::Speedscope => self.to_speedscope(),
+        }
+    }
+
+    /**
+        Writes this report as folded stacks, as used by `flamegraph.pl` and `inferno`.
+    */
+    pub fn to_folded(&self) -> String {
+        let mut folded = String::new();
+        for (stack, count) in &self.stacks {
+            let names = stack
+                .iter()
+                .map(ProfileFrame::folded_name)
+                .collect::<Vec<_>>();
+            // NOTE: Writing to a string can not fail, so we ignore the result here
+            writeln!(folded, "{} {count}", names.join(";")).ok();
+        }
+        folded
+    }
+
+    /**
+        Writes this report in the speedscope file format.
+    */
+    pub fn to_speedscope(&self) -> String {
+        let mut frames = Vec::new();
+        let mut frame_indices = HashMap::new();
+        let mut samples = Vec::new();
+        let mut weights = Vec::new();
+
+        let interval = u64::try_from(self.interval.as_micros()).unwrap_or(u64::MAX);
+        for (stack, count) in &self.stacks {
+            let indices = stack
+                .iter()
+                .map(|frame| {
+                    *frame_indices.entry(frame).or_insert_with(|| {
+                        frames.push(json!({
+                            "name": frame.name,
+                            "file": frame.file,
+                            "line": frame.line,
+                        }));
+                        frames.len() - 1
+                    })
+                })
+                .collect::<Vec<_>>();
+            samples.push(indices);
+            weights.push(count.saturating_mul(interval));
+        }
+
+        let total = weights.iter().sum::<u64>();
+        let profile = json!({
+            "$schema": "https://www.speedscope.app/file-format-schema.json",
+            "exporter": "luneweb",
+            "name": "luneweb",
+            "activeProfileIndex": 0,
+            "shared": { "frames": frames },
+            "profiles": [{
+                "type": "sampled",
+                "name": "luneweb",
+                "unit": "microseconds",
+                "startValue": 0,
+                "endValue": total,
+                "samples": samples,
+                "weights": weights,
+            }],
+        });
+        serde_json::to_string_pretty(&profile).expect("Profile should always serialize")
+    }
+}
+
+/**
+    A sampling profiler for a Luau VM.
+
+    Samples are taken using interrupts, meaning that only time spent
+    running Luau code is sampled, and not any time spent waiting.
+*/
+#[derive(Debug, Default)]
+pub struct Profiler {
+    interval: Cell<Duration>,
+    next_sample: Cell<Option<Instant>>,
+    interrupt: Cell<Option<usize>>,
+    samples: RefCell<HashMap<Vec<ProfileFrame>, u64>>,
+}
+
+impl Profiler {
+    /**
+        Gets the profiler for the given Lua state, if profiling has ever been started.
+    */
+    pub fn get(lua: &Lua) -> Option<Rc<Self>> {
+        lua.app_data_ref::<Rc<Self>>().map(|this| Rc::clone(&this))
+    }
+
+    /**
+        Starts sampling call stacks, at the given number of samples per second.
+
+        If the profiler is already running, this changes its sample rate.
+    */
+    pub fn start(lua: &Lua, sample_rate: u32) {
+        let this = Self::get(lua).unwrap_or_else(|| {
+            let this = Rc::new(Self::default());
+            lua.set_app_data(Rc::clone(&this));
+            this
+        });
+
+        this.interval
+            .set(Duration::from_secs(1) / sample_rate.max(1));
+        if this.interrupt.get().is_none() {
+            let inner = Rc::clone(&this);
+            let id = Interrupts::add(lua, move |lua| {
+                inner.sample(lua);
+                Ok(LuaVmState::Continue)
+            });
+            this.interrupt.set(Some(id));
+        }
+    }
+
+    /**
+        Stops sampling call stacks. Samples that have already been taken are kept.
+    */
+    pub fn stop(lua: &Lua) {
+        if let Some(this) = Self::get(lua) {
+            if let Some(id) = this.interrupt.take() {
+                Interrupts::remove(lua, id);
+            }
+            this.next_sample.set(None);
+        }
+    }
+
+    fn sample(&self, lua: &Lua) {
+        let now = Instant::now();
+        match self.next_sample.get() {
+            Some(next) if now < next => return,
+            // NOTE: The first interrupt only schedules the first sample, since the
+            // time before it may have been spent waiting and not running Luau code
+            None => {
+                self.next_sample.set(Some(now + self.interval.get()));
+                return;
+            }
+            Some(_) => self.next_sample.set(Some(now + self.interval.get())),
+        }
+
+        let mut stack = Vec::new();
+        let mut level = 0;
+        while let Some(debug) = lua.inspect_stack(level) {
+            stack.push(ProfileFrame::from_debug(&debug));
+            level += 1;
+        }
+        stack.reverse();
+
+        *self.samples.borrow_mut().entry(stack).or_default() += 1;
+    }
+
+    /**
+        Creates a report containing all samples taken so far.
+    */
+    pub fn report(&self) -> ProfileReport {
+        let mut stacks = self
+            .samples
+            .borrow()
+            .iter()
+            .map(|(stack, count)| (stack.clone(), *count))
+            .collect::<Vec<_>>();
+        stacks.sort();
+        ProfileReport {
+            interval: self.interval.get(),
+            stacks,
+        }
+    }
+}
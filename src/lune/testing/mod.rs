@@ -0,0 +1,380 @@
+use std::{
+    cell::Cell,
+    path::Path,
+    process::ExitCode,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use mlua::prelude::*;
+
+use crate::lune::{util::interrupts::Interrupts, Runtime, RuntimeError};
+
+mod report;
+
+pub use report::{TestFileReport, TestFormat, TestReport, TestResult, TestStatus};
+
+const FRAMEWORK_IMPL: &str = include_str!("framework.luau");
+
+/**
+    A test declared in a test file, as collected by the test framework.
+*/
+#[derive(Debug, Clone)]
+struct DeclaredTest {
+    names: Vec<String>,
+    skip: bool,
+    only: bool,
+}
+
+impl<'lua> FromLua<'lua> for DeclaredTest {
+    fn from_lua(value: LuaValue<'lua>, _: &'lua Lua) -> LuaResult<Self> {
+        let LuaValue::Table(table) = value else {
+            return Err(LuaError::runtime(format!(
+                "Invalid test - expected table, got {}",
+                value.type_name()
+            )));
+        };
+        Ok(Self {
+            names: table.get("names")?,
+            skip: table.get("skip")?,
+            only: table.get("only")?,
+        })
+    }
+}
+
+/**
+    A runtime with the test framework injected into it, which
+    keeps track of all errors that happen while running scripts.
+*/
+struct TestRuntime {
+    runtime: Runtime,
+    framework: LuaRegistryKey,
+    errors: Arc<Mutex<Vec<String>>>,
+    cancelled: Cell<bool>,
+}
+
+impl TestRuntime {
+    fn new() -> LuaResult<Self> {
+        let errors = Arc::new(Mutex::new(Vec::new()));
+        let errors_inner = Arc::clone(&errors);
+        let runtime = Runtime::new()
+            .with_error_printing(false)
+            .with_error_callback(move |error| {
+                errors_inner
+                    .lock()
+                    .expect("Test errors lock was poisoned")
+                    .push(format_error(error.clone()));
+            });
+
+        let lua = runtime.lua();
+        let framework = lua
+            .load(FRAMEWORK_IMPL)
+            .set_name("test")
+            .call::<_, LuaTable>(())?;
+        for pair in framework.get::<_, LuaTable>("globals")?.pairs() {
+            let (key, value): (LuaValue, LuaValue) = pair?;
+            lua.globals().set(key, value)?;
+        }
+        let framework = lua.create_registry_value(framework)?;
+
+        Ok(Self {
+            runtime,
+            framework,
+            errors,
+            cancelled: Cell::new(false),
+        })
+    }
+
+    fn framework_function(&self, name: &str) -> LuaResult<LuaFunction<'_>> {
+        let lua = self.runtime.lua();
+        let framework = lua.registry_value::<LuaTable>(&self.framework)?;
+        framework.get(name)
+    }
+
+    /**
+        Runs a function as the main thread of the runtime, failing with the first
+        error that happened while running it, or if it did not finish in time.
+    */
+    async fn run(&self, main: LuaFunction<'_>, timeout: Option<Duration>) -> Result<(), String> {
+        let lua = self.runtime.lua();
+
+        // NOTE: The same runtime runs many functions, and only errors
+        // from this function should count towards its own result
+        self.errors
+            .lock()
+            .expect("Test errors lock was poisoned")
+            .clear();
+
+        // NOTE: Scripts that never yield would block the timeout below from ever
+        // firing, so the deadline is also checked whenever Luau code is interrupted
+        let interrupt = timeout.map(|timeout| {
+            let deadline = Instant::now() + timeout;
+            Interrupts::add(lua, move |_| {
+                if Instant::now() >= deadline {
+                    Err(LuaError::runtime(timed_out(timeout)))
+                } else {
+                    Ok(LuaVmState::Continue)
+                }
+            })
+        });
+
+        let result = match timeout {
+            None => Ok(self.runtime.run_function(main).await),
+            Some(timeout) => tokio::time::timeout(timeout, self.runtime.run_function(main))
+                .await
+                .map_err(|_| {
+                    self.cancelled.set(true);
+                    timed_out(timeout)
+                }),
+        };
+
+        if let Some(id) = interrupt {
+            Interrupts::remove(lua, id);
+        }
+
+        let first_error = self
+            .errors
+            .lock()
+            .expect("Test errors lock was poisoned")
+            .first()
+            .cloned();
+        match (result?, first_error) {
+            (_, Some(error)) => Err(error),
+            (Err(error), None) => Err(format_error(error)),
+            (Ok(code), None) if code != ExitCode::SUCCESS => {
+                Err("Exited with a non-zero exit code".to_string())
+            }
+            (Ok(_), None) => Ok(()),
+        }
+    }
+
+    /**
+        Runs a test file, declaring all of its tests.
+    */
+    async fn declare(
+        &self,
+        script_name: &str,
+        contents: &[u8],
+        timeout: Option<Duration>,
+    ) -> Result<Vec<DeclaredTest>, String> {
+        let main = self
+            .runtime
+            .load(script_name, contents)
+            .map_err(format_error)?;
+        self.run(main, timeout).await?;
+        self.framework_function("collected")
+            .and_then(|collected| collected.call(()))
+            .map_err(format_error)
+    }
+}
+
+/**
+    A runner for test files, which declare tests using `describe` and `it`, and
+    make assertions using `expect`, all of which are available as globals.
+
+    Each test file runs in a [`Runtime`] of its own, and its tests run one after the
+    other in that same runtime, once the file has finished declaring them. This means
+    that code at the top level of a file only runs once, and that tests in a file share
+    its globals and any modules that it required, while tests in different files can
+    not interfere with each other. State that each test needs a fresh copy of should
+    be set up in a `beforeEach` hook.
+
+    A runtime that was stopped by a timeout can not run anything else, so the file
+    is then declared again in a new runtime to run any tests that are left.
+*/
+#[derive(Debug, Clone, Default)]
+pub struct TestRunner {
+    filters: Vec<String>,
+    timeout: Option<Duration>,
+}
+
+impl TestRunner {
+    /**
+        Creates a new test runner, which runs all tests without a timeout.
+    */
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /**
+        Only runs tests with full names containing any of the given filters.
+
+        The full name of a test includes the names of its `describe` blocks,
+        separated by `>`, such as `math > addition > adds two numbers`.
+    */
+    pub fn with_filters<V>(mut self, filters: V) -> Self
+    where
+        V: Into<Vec<String>>,
+    {
+        self.filters = filters.into();
+        self
+    }
+
+    /**
+        Fails any test that takes longer than the given duration to run, including
+        any threads spawned by the test, or disables timeouts if given `None`.
+    */
+    pub fn with_timeout(mut self, timeout: Option<Duration>) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    fn is_selected(&self, test: &DeclaredTest) -> bool {
+        if self.filters.is_empty() {
+            return true;
+        }
+        let name = test.names.join(" > ");
+        self.filters.iter().any(|filter| name.contains(filter))
+    }
+
+    /**
+        Runs all selected tests in the test file at the given path,
+        calling the given function with the result of each test.
+
+        Tests that do not match any filters are left out of the report, while tests that
+        are skipped using `.skip`, or by another test in the file using `.only`, are
+        included as skipped. Any errors loading the file are included in the report.
+    */
+    pub async fn run_file(
+        &self,
+        path: impl AsRef<Path>,
+        mut on_result: impl FnMut(&TestResult),
+    ) -> TestFileReport {
+        let path = path.as_ref();
+        let mut report = TestFileReport {
+            path: path.to_path_buf(),
+            error: None,
+            tests: Vec::new(),
+        };
+
+        let contents = match tokio::fs::read(path).await {
+            Ok(contents) => contents,
+            Err(e) => {
+                report.error = Some(format!("Failed to read test file - {e}"));
+                return report;
+            }
+        };
+        // NOTE: We skip the extension here to remove it from stack traces
+        let script_name = path.with_extension("").display().to_string();
+
+        let runtime = match TestRuntime::new() {
+            Ok(runtime) => runtime,
+            Err(e) => {
+                report.error = Some(format_error(e));
+                return report;
+            }
+        };
+        let declared = match runtime.declare(&script_name, &contents, self.timeout).await {
+            Ok(declared) => declared,
+            Err(e) => {
+                report.error = Some(e);
+                return report;
+            }
+        };
+
+        let mut runtime = Ok(runtime);
+        let has_only = declared.iter().any(|test| test.only);
+        for (index, test) in declared.iter().enumerate() {
+            if !self.is_selected(test) {
+                continue;
+            }
+            let result = if test.skip || (has_only && !test.only) {
+                TestResult {
+                    names: test.names.clone(),
+                    status: TestStatus::Skipped,
+                    duration: Duration::ZERO,
+                }
+            } else {
+                if runtime.as_ref().map_or(true, |r| r.cancelled.get()) {
+                    runtime = self.redeclare(&script_name, &contents, &declared).await;
+                }
+                match &runtime {
+                    Ok(runtime) => self.run_test(runtime, index, test).await,
+                    Err(e) => TestResult {
+                        names: test.names.clone(),
+                        status: TestStatus::Failed(e.clone()),
+                        duration: Duration::ZERO,
+                    },
+                }
+            };
+            on_result(&result);
+            report.tests.push(result);
+        }
+
+        report
+    }
+
+    /**
+        Declares the tests in a file again, in a new runtime, making
+        sure that the same tests were declared as the first time.
+    */
+    async fn redeclare(
+        &self,
+        script_name: &str,
+        contents: &[u8],
+        declared: &[DeclaredTest],
+    ) -> Result<TestRuntime, String> {
+        let runtime = TestRuntime::new().map_err(format_error)?;
+        let again = runtime.declare(script_name, contents, self.timeout).await?;
+        if again
+            .iter()
+            .map(|t| &t.names)
+            .ne(declared.iter().map(|t| &t.names))
+        {
+            return Err(
+                "Test file declared different tests when it was ran again after a timeout"
+                    .to_string(),
+            );
+        }
+        Ok(runtime)
+    }
+
+    async fn run_test(
+        &self,
+        runtime: &TestRuntime,
+        index: usize,
+        test: &DeclaredTest,
+    ) -> TestResult {
+        let mut duration = Duration::ZERO;
+        let outcome = async {
+            let bound = |name: &str| {
+                runtime
+                    .framework_function(name)
+                    .and_then(|function| function.bind(index + 1))
+                    .map_err(format_error)
+            };
+            let start = Instant::now();
+            let result = runtime.run(bound("run")?, self.timeout).await;
+            duration = start.elapsed();
+            // NOTE: Hooks that clean up after tests run even if the test failed, but
+            // their own errors are only reported if the test itself passed, and a
+            // runtime that was stopped by a timeout can not run anything else
+            if runtime.cancelled.get() {
+                return result;
+            }
+            let cleanup = runtime.run(bound("cleanup")?, self.timeout).await;
+            result.and(cleanup)
+        }
+        .await;
+
+        TestResult {
+            names: test.names.clone(),
+            status: match outcome {
+                Ok(()) => TestStatus::Passed,
+                Err(message) => TestStatus::Failed(message),
+            },
+            duration,
+        }
+    }
+}
+
+fn format_error(error: impl Into<RuntimeError>) -> String {
+    error.into().disable_colors().to_string()
+}
+
+fn timed_out(timeout: Duration) -> String {
+    format!("Timed out after {timeout:?}")
+}
+
+#[cfg(test)]
+mod tests;
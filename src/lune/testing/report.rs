@@ -0,0 +1,429 @@
+use std::{fmt::Write as _, path::PathBuf, str::FromStr, time::Duration};
+
+/**
+    The format to write test reports in.
+*/
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum TestFormat {
+    #[default]
+    Text,
+    Tap,
+    Junit,
+}
+
+impl TestFormat {
+    /**
+        Gets the file extension typically used for this format.
+    */
+    pub fn extension(self) -> &'static str {
+        match self {
+            Self::Text => "txt",
+            Self::Tap => "tap",
+            Self::Junit => "xml",
+        }
+    }
+}
+
+impl FromStr for TestFormat {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "text" => Ok(Self::Text),
+            "tap" => Ok(Self::Tap),
+            "junit" => Ok(Self::Junit),
+            _ => Err(format!(
+                "Invalid test format '{s}' - expected one of text, tap, junit"
+            )),
+        }
+    }
+}
+
+/**
+    The outcome of a single test.
+*/
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TestStatus {
+    Passed,
+    Failed(String),
+    Skipped,
+}
+
+/**
+    A single test, with the names of all the `describe` blocks it was declared in.
+*/
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TestResult {
+    pub names: Vec<String>,
+    pub status: TestStatus,
+    pub duration: Duration,
+}
+
+impl TestResult {
+    /**
+        Gets the full name of the test, including the names of its `describe` blocks.
+    */
+    pub fn name(&self) -> String {
+        self.names.join(" > ")
+    }
+}
+
+/**
+    All tests in a single test file, or the error that
+    prevented the tests in the file from being collected.
+*/
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TestFileReport {
+    pub path: PathBuf,
+    pub error: Option<String>,
+    pub tests: Vec<TestResult>,
+}
+
+impl TestFileReport {
+    /**
+        Checks if the file was loaded and none of its tests failed.
+    */
+    pub fn is_success(&self) -> bool {
+        self.error.is_none()
+            && !self
+                .tests
+                .iter()
+                .any(|test| matches!(test.status, TestStatus::Failed(_)))
+    }
+
+    fn count(&self, matches: impl Fn(&TestStatus) -> bool) -> usize {
+        self.tests
+            .iter()
+            .filter(|test| matches(&test.status))
+            .count()
+    }
+
+    fn duration(&self) -> Duration {
+        self.tests.iter().map(|test| test.duration).sum()
+    }
+}
+
+/**
+    Results for all tests in a set of test files.
+*/
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct TestReport {
+    pub files: Vec<TestFileReport>,
+}
+
+impl TestReport {
+    /**
+        Checks if all files were loaded and none of their tests failed.
+    */
+    pub fn is_success(&self) -> bool {
+        self.files.iter().all(TestFileReport::is_success)
+    }
+
+    /**
+        Gets the number of tests that passed.
+    */
+    pub fn passed(&self) -> usize {
+        self.count(|status| *status == TestStatus::Passed)
+    }
+
+    /**
+        Gets the number of tests that failed, not including files that failed to load.
+    */
+    pub fn failed(&self) -> usize {
+        self.count(|status| matches!(status, TestStatus::Failed(_)))
+    }
+
+    /**
+        Gets the number of tests that were skipped.
+    */
+    pub fn skipped(&self) -> usize {
+        self.count(|status| *status == TestStatus::Skipped)
+    }
+
+    fn count(&self, matches: impl Fn(&TestStatus) -> bool + Copy) -> usize {
+        self.files.iter().map(|file| file.count(matches)).sum()
+    }
+
+    /**
+        Writes the report in the given format.
+    */
+    pub fn to_format(&self, format: TestFormat) -> String {
+        match format {
+            TestFormat::Text => self.to_text(),
+            TestFormat::Tap => self.to_tap(),
+            TestFormat::Junit => self.to_junit(),
+        }
+    }
+
+    /**
+        Writes the report as plain text, listing every test with its outcome.
+    */
+    pub fn to_text(&self) -> String {
+        let mut out = String::new();
+        for file in &self.files {
+            let _ = writeln!(out, "{}", file.path.display());
+            if let Some(error) = &file.error {
+                let _ = writeln!(out, "  ERROR");
+                let _ = writeln!(out, "{}", indent(error, 4));
+            }
+            for test in &file.tests {
+                let label = match test.status {
+                    TestStatus::Passed => "PASS",
+                    TestStatus::Failed(_) => "FAIL",
+                    TestStatus::Skipped => "SKIP",
+                };
+                let _ = writeln!(
+                    out,
+                    "  {label} {} ({}ms)",
+                    test.name(),
+                    test.duration.as_millis()
+                );
+                if let TestStatus::Failed(message) = &test.status {
+                    let _ = writeln!(out, "{}", indent(message, 4));
+                }
+            }
+        }
+        let _ = writeln!(out, "{}", self.summary());
+        out
+    }
+
+    /**
+        Writes the report using the [Test Anything Protocol](https://testanything.org),
+        version 13, where files that failed to load are reported as failed tests.
+    */
+    pub fn to_tap(&self) -> String {
+        let mut lines = Vec::new();
+        for file in &self.files {
+            let path = file.path.display();
+            if let Some(error) = &file.error {
+                lines.push((format!("{path}"), Some(error.as_str()), ""));
+            }
+            for test in &file.tests {
+                let name = format!("{path} > {}", test.name());
+                match &test.status {
+                    TestStatus::Passed => lines.push((name, None, "")),
+                    TestStatus::Failed(message) => lines.push((name, Some(message.as_str()), "")),
+                    TestStatus::Skipped => lines.push((name, None, " # SKIP")),
+                }
+            }
+        }
+
+        let mut out = String::new();
+        let _ = writeln!(out, "TAP version 13");
+        let _ = writeln!(out, "1..{}", lines.len());
+        for (index, (name, failure, directive)) in lines.iter().enumerate() {
+            // NOTE: A '#' in a description would start a directive, so it must be escaped
+            let name = name.replace('\\', "\\\\").replace('#', "\\#");
+            let number = index + 1;
+            match failure {
+                None => {
+                    let _ = writeln!(out, "ok {number} - {name}{directive}");
+                }
+                Some(message) => {
+                    let _ = writeln!(out, "not ok {number} - {name}");
+                    let _ = writeln!(out, "  ---");
+                    let _ = writeln!(out, "  message: |-");
+                    let _ = writeln!(out, "{}", indent(message, 4));
+                    let _ = writeln!(out, "  ...");
+                }
+            }
+        }
+        out
+    }
+
+    /**
+        Writes the report as JUnit XML, with a test suite for each file, where
+        files that failed to load are reported as test cases with an error.
+    */
+    pub fn to_junit(&self) -> String {
+        let total_duration: Duration = self.files.iter().map(TestFileReport::duration).sum();
+        let errors = self
+            .files
+            .iter()
+            .filter(|file| file.error.is_some())
+            .count();
+
+        let mut out = String::new();
+        let _ = writeln!(out, r#"<?xml version="1.0" encoding="UTF-8"?>"#);
+        let _ = writeln!(
+            out,
+            r#"<testsuites name="luneweb" tests="{}" failures="{}" errors="{errors}" skipped="{}" time="{:.3}">"#,
+            self.count(|_| true) + errors,
+            self.failed(),
+            self.skipped(),
+            total_duration.as_secs_f64(),
+        );
+        for file in &self.files {
+            let path = escape_xml(&file.path.display().to_string());
+            let file_errors = usize::from(file.error.is_some());
+            let _ = writeln!(
+                out,
+                r#"  <testsuite name="{path}" tests="{}" failures="{}" errors="{file_errors}" skipped="{}" time="{:.3}">"#,
+                file.tests.len() + file_errors,
+                file.count(|status| matches!(status, TestStatus::Failed(_))),
+                file.count(|status| *status == TestStatus::Skipped),
+                file.duration().as_secs_f64(),
+            );
+            if let Some(error) = &file.error {
+                let _ = writeln!(
+                    out,
+                    r#"    <testcase name="{path}" classname="{path}" time="0.000">"#
+                );
+                let _ = writeln!(
+                    out,
+                    r#"      <error message="{}">{}</error>"#,
+                    escape_xml(first_line(error)),
+                    escape_xml(error)
+                );
+                let _ = writeln!(out, "    </testcase>");
+            }
+            for test in &file.tests {
+                let _ = write!(
+                    out,
+                    r#"    <testcase name="{}" classname="{path}" time="{:.3}""#,
+                    escape_xml(&test.name()),
+                    test.duration.as_secs_f64(),
+                );
+                match &test.status {
+                    TestStatus::Passed => {
+                        let _ = writeln!(out, " />");
+                    }
+                    TestStatus::Skipped => {
+                        let _ = writeln!(out, ">");
+                        let _ = writeln!(out, "      <skipped />");
+                        let _ = writeln!(out, "    </testcase>");
+                    }
+                    TestStatus::Failed(message) => {
+                        let _ = writeln!(out, ">");
+                        let _ = writeln!(
+                            out,
+                            r#"      <failure message="{}">{}</failure>"#,
+                            escape_xml(first_line(message)),
+                            escape_xml(message)
+                        );
+                        let _ = writeln!(out, "    </testcase>");
+                    }
+                }
+            }
+            let _ = writeln!(out, "  </testsuite>");
+        }
+        let _ = writeln!(out, "</testsuites>");
+        out
+    }
+
+    /**
+        Gets a single line summarizing the number of tests with each outcome.
+    */
+    pub fn summary(&self) -> String {
+        let mut summary = format!(
+            "Tests: {} passed, {} failed, {} skipped, {} total",
+            self.passed(),
+            self.failed(),
+            self.skipped(),
+            self.count(|_| true),
+        );
+        let errors = self
+            .files
+            .iter()
+            .filter(|file| file.error.is_some())
+            .count();
+        if errors > 0 {
+            let _ = write!(summary, " ({errors} files failed to load)");
+        }
+        summary
+    }
+}
+
+fn indent(text: &str, width: usize) -> String {
+    let prefix = " ".repeat(width);
+    text.trim_end()
+        .lines()
+        .map(|line| format!("{prefix}{line}"))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+fn first_line(text: &str) -> &str {
+    text.lines().next().unwrap_or_default()
+}
+
+fn escape_xml(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            // NOTE: Most control characters are not allowed anywhere in XML 1.0 documents
+            c if c.is_control() && !matches!(c, '\n' | '\r' | '\t') => {}
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn report() -> TestReport {
+        TestReport {
+            files: vec![TestFileReport {
+                path: PathBuf::from("math.spec.luau"),
+                error: None,
+                tests: vec![
+                    TestResult {
+                        names: vec!["math".to_string(), "adds".to_string()],
+                        status: TestStatus::Passed,
+                        duration: Duration::from_millis(2),
+                    },
+                    TestResult {
+                        names: vec!["math".to_string(), "a < b".to_string()],
+                        status: TestStatus::Failed("Expected 1 to be 2\nmore".to_string()),
+                        duration: Duration::from_millis(1),
+                    },
+                    TestResult {
+                        names: vec!["todo #1".to_string()],
+                        status: TestStatus::Skipped,
+                        duration: Duration::ZERO,
+                    },
+                ],
+            }],
+        }
+    }
+
+    #[test]
+    fn tap_output() {
+        let tap = report().to_tap();
+        assert_eq!(
+            tap,
+            "TAP version 13\n\
+            1..3\n\
+            ok 1 - math.spec.luau > math > adds\n\
+            not ok 2 - math.spec.luau > math > a < b\n  \
+              ---\n  \
+              message: |-\n    \
+                Expected 1 to be 2\n    \
+                more\n  \
+              ...\n\
+            ok 3 - math.spec.luau > todo \\#1 # SKIP\n"
+        );
+    }
+
+    #[test]
+    fn junit_output() {
+        let junit = report().to_junit();
+        assert!(junit.contains(
+            r#"<testsuite name="math.spec.luau" tests="3" failures="1" errors="0" skipped="1" time="0.003">"#
+        ));
+        assert!(junit.contains(
+            r#"<testcase name="math &gt; adds" classname="math.spec.luau" time="0.002" />"#
+        ));
+        assert!(junit.contains(
+            r#"<failure message="Expected 1 to be 2">Expected 1 to be 2
+more</failure>"#
+        ));
+        assert!(junit.contains("<skipped />"));
+    }
+}
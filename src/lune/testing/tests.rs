@@ -0,0 +1,134 @@
+// NOTE: The test runner reports the failures of the scripts that it runs instead
+// of failing itself, so these tests run it from Rust and check its reports,
+// which would not be possible using the scripts registered in src/tests.rs
+
+use std::time::Duration;
+
+use crate::tests::repo_path;
+
+use super::{TestFileReport, TestRunner, TestStatus};
+
+async fn run(runner: &TestRunner, path: &str) -> TestFileReport {
+    let report = runner.run_file(repo_path(path), |_| {}).await;
+    assert_eq!(report.error, None, "Failed to load {path}");
+    report
+}
+
+fn status<'a>(report: &'a TestFileReport, name: &str) -> &'a TestStatus {
+    &report
+        .tests
+        .iter()
+        .find(|test| test.name() == name)
+        .unwrap_or_else(|| panic!("Missing test '{name}' in {:?}", report.tests))
+        .status
+}
+
+fn failure<'a>(report: &'a TestFileReport, name: &str) -> &'a str {
+    match status(report, name) {
+        TestStatus::Failed(message) => message,
+        status => panic!("Expected test '{name}' to fail, but it was {status:?}"),
+    }
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn testing_passing() {
+    let report = run(&TestRunner::new(), "tests/testing/passing.spec.luau").await;
+    assert!(
+        report.is_success(),
+        "Unexpected failures {:?}",
+        report.tests
+    );
+    assert_eq!(report.tests.len(), 8);
+    assert_eq!(status(&report, "math > adds numbers"), &TestStatus::Passed);
+    assert_eq!(
+        status(&report, "math > nested > runs hooks from outer blocks"),
+        &TestStatus::Passed
+    );
+    assert_eq!(status(&report, "supports yielding"), &TestStatus::Passed);
+    assert_eq!(status(&report, "is skipped"), &TestStatus::Skipped);
+    assert_eq!(status(&report, "is not written yet"), &TestStatus::Skipped);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn testing_failing() {
+    let runner = TestRunner::new().with_timeout(Some(Duration::from_millis(250)));
+    let report = run(&runner, "tests/testing/failing.spec.luau").await;
+    assert!(!report.is_success());
+
+    let message = failure(&report, "failures > fails assertions");
+    assert!(message.contains("Expected 2 to be 3"), "{message}");
+    assert!(
+        message.contains("tests/testing/failing.spec', Line 5"),
+        "{message}"
+    );
+
+    let message = failure(&report, "failures > fails negated assertions");
+    assert!(
+        message.contains(r#"Expected "a" not to be "a""#),
+        "{message}"
+    );
+
+    let message = failure(&report, "failures > fails with errors");
+    assert!(message.contains("Something went wrong"), "{message}");
+
+    let message = failure(&report, "fails with errors in spawned threads");
+    assert!(message.contains("Spawned error"), "{message}");
+
+    let message = failure(&report, "times out while yielding");
+    assert!(message.contains("Timed out after 250ms"), "{message}");
+
+    let message = failure(&report, "times out while running");
+    assert!(message.contains("Timed out after 250ms"), "{message}");
+
+    assert_eq!(status(&report, "passes"), &TestStatus::Passed);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn testing_filters_and_only() {
+    let runner = TestRunner::new().with_filters(vec!["math >".to_string()]);
+    let report = run(&runner, "tests/testing/passing.spec.luau").await;
+    assert_eq!(report.tests.len(), 3);
+    assert!(report.tests.iter().all(|test| test.names[0] == "math"));
+
+    let report = run(&TestRunner::new(), "tests/testing/only.spec.luau").await;
+    assert!(
+        report.is_success(),
+        "Unexpected failures {:?}",
+        report.tests
+    );
+    assert_eq!(status(&report, "is skipped by only"), &TestStatus::Skipped);
+    assert_eq!(status(&report, "focused > runs"), &TestStatus::Passed);
+    assert_eq!(status(&report, "also runs"), &TestStatus::Passed);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn testing_shared_runtime() {
+    // NOTE: The test file writes this using a relative path, so
+    // it is also read relative to the current directory here
+    let runs_path = "bin/testing_shared_runs";
+    tokio::fs::remove_file(runs_path).await.ok();
+
+    let report = run(&TestRunner::new(), "tests/testing/shared.spec.luau").await;
+    assert!(
+        report.is_success(),
+        "Unexpected failures {:?}",
+        report.tests
+    );
+    assert!(
+        report.tests.len() >= 3,
+        "Missing tests in {:?}",
+        report.tests
+    );
+
+    let runs = tokio::fs::read_to_string(runs_path).await.unwrap();
+    assert_eq!(runs, "run\n", "Top level code ran more than once");
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn testing_file_errors() {
+    let report = TestRunner::new()
+        .run_file(repo_path("tests/testing/missing.spec.luau"), |_| {})
+        .await;
+    assert!(report.error.is_some());
+    assert!(!report.is_success());
+}
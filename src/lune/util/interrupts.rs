@@ -0,0 +1,90 @@
+use std::{
+    cell::{Cell, RefCell},
+    rc::Rc,
+};
+
+use mlua::prelude::*;
+
+type InterruptHandler = Rc<dyn Fn(&Lua) -> LuaResult<LuaVmState>>;
+
+/**
+    Interrupt handlers for a Luau VM.
+
+    Luau only supports a single interrupt callback, so any code that needs to
+    interrupt running Luau code should add a handler here instead of setting
+    its own callback, which would otherwise replace any other interrupts.
+*/
+#[derive(Default)]
+pub struct Interrupts {
+    next_id: Cell<usize>,
+    handlers: RefCell<Vec<(usize, InterruptHandler)>>,
+}
+
+impl Interrupts {
+    fn get_or_create(lua: &Lua) -> Rc<Self> {
+        if let Some(this) = lua.app_data_ref::<Rc<Self>>() {
+            return Rc::clone(&this);
+        }
+        let this = Rc::new(Self::default());
+        lua.set_app_data(Rc::clone(&this));
+        this
+    }
+
+    /**
+        Adds an interrupt handler, returning an id that can be used to remove it.
+
+        Handlers are called periodically while Luau code is running, and may return
+        an error to stop the running code, or yield the current thread. The
+        interrupt callback is only set while there are handlers to call.
+    */
+    pub fn add<F>(lua: &Lua, handler: F) -> usize
+    where
+        F: Fn(&Lua) -> LuaResult<LuaVmState> + 'static,
+    {
+        let this = Self::get_or_create(lua);
+
+        let id = this.next_id.get();
+        this.next_id.set(id + 1);
+
+        let mut handlers = this.handlers.borrow_mut();
+        handlers.push((id, Rc::new(handler)));
+        if handlers.len() == 1 {
+            let inner = Rc::clone(&this);
+            lua.set_interrupt(move |lua| inner.interrupt(lua));
+        }
+
+        id
+    }
+
+    /**
+        Removes the interrupt handler with the given id, if it exists.
+    */
+    pub fn remove(lua: &Lua, id: usize) {
+        let this = Self::get_or_create(lua);
+
+        let mut handlers = this.handlers.borrow_mut();
+        handlers.retain(|(handler_id, _)| *handler_id != id);
+        if handlers.is_empty() {
+            lua.remove_interrupt();
+        }
+    }
+
+    fn interrupt(&self, lua: &Lua) -> LuaResult<LuaVmState> {
+        // NOTE: Handlers are cloned out first so that they
+        // may add or remove other handlers while being called
+        let handlers = self
+            .handlers
+            .borrow()
+            .iter()
+            .map(|(_, handler)| Rc::clone(handler))
+            .collect::<Vec<_>>();
+
+        let mut state = LuaVmState::Continue;
+        for handler in handlers {
+            if let LuaVmState::Yield = handler(lua)? {
+                state = LuaVmState::Yield;
+            }
+        }
+        Ok(state)
+    }
+}
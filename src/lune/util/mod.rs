@@ -3,6 +3,7 @@ mod table_builder;
 pub mod connection;
 pub mod formatting;
 pub mod http;
+pub mod interrupts;
 pub mod luaurc;
 pub mod paths;
 pub mod traits;
@@ -12,10 +12,58 @@ pub static CWD: Lazy<PathBuf> = Lazy::new(|| {
 });
 
 pub fn make_absolute_and_clean(path: impl AsRef<Path>) -> PathBuf {
+    make_absolute_and_clean_from(CWD.as_path(), path)
+}
+
+/**
+    Makes the given path absolute, relative to the given base
+    directory if it is not already absolute, and cleans it.
+*/
+pub fn make_absolute_and_clean_from(base: impl AsRef<Path>, path: impl AsRef<Path>) -> PathBuf {
     let path = path.as_ref();
     if path.is_relative() {
-        CWD.join(path).clean()
+        base.as_ref().join(path).clean()
     } else {
         path.clean()
     }
 }
+
+/**
+    Gets the current working directory of the process.
+
+    Unlike `CWD`, this reflects any changes made to the working directory
+    after startup, and falls back to `CWD` if it can not be determined.
+*/
+pub fn current_working_dir() -> PathBuf {
+    match current_dir() {
+        Ok(dir) => dunce::simplified(&dir).to_path_buf(),
+        Err(_) => CWD.clone(),
+    }
+}
+
+/**
+    Makes the given path relative to the given base path, cleaning both paths first.
+
+    Returns `None` if no relative path exists, such as for paths on different drives on Windows.
+*/
+pub fn make_relative(path: impl AsRef<Path>, base: impl AsRef<Path>) -> Option<PathBuf> {
+    pathdiff::diff_paths(path.as_ref().clean(), base.as_ref().clean())
+}
+
+/**
+    Gets the path to the source file for a chunk.
+
+    Scripts ran using the cli have their extensions removed from their chunk
+    names, so this also tries to find source files with common extensions.
+*/
+pub fn chunk_source_path(name: &str) -> String {
+    let path = Path::new(name);
+    if path.is_file() {
+        return name.to_string();
+    }
+    ["luau", "lua"]
+        .into_iter()
+        .map(|extension| format!("{name}.{extension}"))
+        .find(|path| Path::new(path).is_file())
+        .unwrap_or_else(|| name.to_string())
+}
@@ -1,4 +1,7 @@
-use std::process::ExitCode;
+use std::{
+    path::{Path, PathBuf},
+    process::ExitCode,
+};
 
 use anyhow::Result;
 use console::set_colors_enabled;
@@ -9,6 +12,17 @@ use crate::Runtime;
 
 const ARGS: &[&str] = &["Foo", "Bar"];
 
+/**
+    Gets the absolute path to a file in this repository, which does not
+    depend on the current directory that tests happen to be ran from.
+
+    Used by tests that drive parts of Lune that scripts can not drive by
+    themselves, such as the test runner and the debugger, from Rust.
+*/
+pub(crate) fn repo_path(path: &str) -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join(path)
+}
+
 macro_rules! create_tests {
     ($($name:ident: $value:expr,)*) => { $(
         #[tokio::test(flavor = "multi_thread")]
@@ -38,6 +52,13 @@ macro_rules! create_tests {
 }
 
 create_tests! {
+    archive_roundtrip: "archive/roundtrip",
+    archive_traversal: "archive/traversal",
+
+    crypto_hash: "crypto/hash",
+    crypto_hmac: "crypto/hmac",
+    crypto_random: "crypto/random",
+
     datetime_format_local_time: "datetime/formatLocalTime",
     datetime_format_universal_time: "datetime/formatUniversalTime",
     datetime_from_iso_date: "datetime/fromIsoDate",
@@ -49,15 +70,25 @@ create_tests! {
     datetime_to_local_time: "datetime/toLocalTime",
     datetime_to_universal_time: "datetime/toUniversalTime",
 
+    fs_atomic: "fs/atomic",
     fs_files: "fs/files",
     fs_copy: "fs/copy",
     fs_dirs: "fs/dirs",
+    fs_links: "fs/links",
+    fs_lock: "fs/lock",
     fs_metadata: "fs/metadata",
     fs_move: "fs/move",
+    fs_permissions: "fs/permissions",
+    fs_temp: "fs/temp",
 
+    luau_codegen: "luau/codegen",
     luau_compile: "luau/compile",
+    luau_coverage: "luau/coverage",
+    luau_limits: "luau/limits",
     luau_load: "luau/load",
     luau_options: "luau/options",
+    luau_profiler: "luau/profiler",
+    luau_sandbox: "luau/sandbox",
 
     net_request_codes: "net/request/codes",
     net_request_compression: "net/request/compression",
@@ -72,16 +103,27 @@ create_tests! {
     net_socket_wss: "net/socket/wss",
     net_socket_wss_rw: "net/socket/wss_rw",
 
+    path_manipulation: "path/manipulation",
+
     process_args: "process/args",
+    process_chdir: "process/chdir",
+    process_create: "process/create",
     process_cwd: "process/cwd",
     process_env: "process/env",
     process_exit: "process/exit",
+    process_info: "process/info",
+    process_on_exit: "process/onExit",
+    process_on_signal: "process/onSignal",
+    process_pipeline: "process/pipeline",
     process_spawn_async: "process/spawn/async",
     process_spawn_basic: "process/spawn/basic",
     process_spawn_cwd: "process/spawn/cwd",
+    process_spawn_limits: "process/spawn/limits",
+    process_spawn_redirect: "process/spawn/redirect",
     process_spawn_shell: "process/spawn/shell",
     process_spawn_stdin: "process/spawn/stdin",
     process_spawn_stdio: "process/spawn/stdio",
+    process_spawn_timeout: "process/spawn/timeout",
 
     regex_general: "regex/general",
     regex_metamethods: "regex/metamethods",
@@ -110,10 +152,21 @@ create_tests! {
     global_typeof: "globals/typeof",
     global_warn: "globals/warn",
 
+    serde_binary_base64: "serde/binary/base64",
+    serde_binary_hex: "serde/binary/hex",
+    serde_cbor_roundtrip: "serde/cbor/roundtrip",
     serde_compression_files: "serde/compression/files",
+    serde_compression_options: "serde/compression/options",
     serde_compression_roundtrip: "serde/compression/roundtrip",
+    serde_compression_stream: "serde/compression/stream",
+    serde_csv_decode: "serde/csv/decode",
+    serde_csv_encode: "serde/csv/encode",
+    serde_decode_numbers: "serde/decode/numbers",
+    serde_decode_schema: "serde/decode/schema",
+    serde_decode_tables: "serde/decode/tables",
     serde_json_decode: "serde/json/decode",
     serde_json_encode: "serde/json/encode",
+    serde_msgpack_roundtrip: "serde/msgpack/roundtrip",
     serde_toml_decode: "serde/toml/decode",
     serde_toml_encode: "serde/toml/encode",
 
@@ -123,11 +176,26 @@ create_tests! {
     stdio_write: "stdio/write",
     stdio_ewrite: "stdio/ewrite",
 
+    sync_broadcast: "sync/broadcast",
+    sync_channel: "sync/channel",
+    sync_event: "sync/event",
+    sync_mutex: "sync/mutex",
+    sync_semaphore: "sync/semaphore",
+
     task_cancel: "task/cancel",
     task_defer: "task/defer",
     task_delay: "task/delay",
+    task_group: "task/group",
+    task_interval: "task/interval",
+    task_join: "task/join",
+    task_race: "task/race",
+    task_schedule: "task/schedule",
     task_spawn: "task/spawn",
+    task_timeout: "task/timeout",
     task_wait: "task/wait",
+
+    worker_lifecycle: "worker/lifecycle",
+    worker_messages: "worker/messages",
 }
 
 #[cfg(feature = "roblox")]
@@ -198,3 +266,27 @@ create_tests! {
     roblox_reflection_enums: "roblox/reflection/enums",
     roblox_reflection_property: "roblox/reflection/property",
 }
+
+#[tokio::test(flavor = "multi_thread")]
+async fn fs_temp_exit() -> Result<()> {
+    // NOTE: The runtime is kept alive until the end of this test, so that temporary
+    // paths are not removed by the Lua state being dropped, only by process.exit
+    let paths_path = "bin/fs_temp_exit_paths";
+    tokio::fs::remove_file(paths_path).await.ok();
+
+    let script = read_to_string("tests/fs/temp_exit.luau").await?;
+    let mut lune = Runtime::new();
+    let exit_code = lune.run("tests/fs/temp_exit", &script).await?;
+    assert_eq!(exit_code, ExitCode::SUCCESS);
+
+    let paths = read_to_string(paths_path).await?;
+    for path in paths.lines() {
+        assert!(
+            !Path::new(path).exists(),
+            "Temporary path '{path}' was not removed after process.exit"
+        );
+    }
+
+    drop(lune);
+    Ok(())
+}